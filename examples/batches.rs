@@ -0,0 +1,60 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Tier {
+    Basic,
+    Premium { discount: f64 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Account {
+    id: String,
+    owner: String,
+    balance: f64,
+    tier: Tier,
+    tags: Vec<String>,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "id": { "sequence": { "prefix": "ACC-", "width": 6 } },
+        "owner": { "len": [5, 10], "charset": "alphabetic", "case": "lower" },
+        "balance": { "range": [0.0, 10000.0], "decimals": 2 },
+        "tier": {
+            "variant_data": { "Premium": { "discount": [0.05, 0.25] } }
+        },
+        "tags": ["retail", "business", "savings"]
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    // Stream a large batch; the config is checked once, up front.
+    let accounts: Vec<Account> = Account::sample_iter(&config_map, StdRng::seed_from_u64(7))
+        .take(1000)
+        .collect::<Result<_, _>>()?;
+    println!("{:#?}", &accounts[..2]);
+
+    // The same seed gives the same accounts.
+    let again: Vec<Account> = Account::sample_iter(&config_map, StdRng::seed_from_u64(7))
+        .take(1000)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        serde_json::to_value(&accounts).map_err(|e| e.to_string())?,
+        serde_json::to_value(&again).map_err(|e| e.to_string())?
+    );
+
+    // A bad config is reported before anything is sampled.
+    let mut broken = config_map.clone();
+    broken.insert("balance".to_string(), serde_json::json!([10.0, 1.0]));
+    let mut samples = Account::sample_iter(&broken, StdRng::seed_from_u64(7));
+    println!("{}", samples.next().unwrap().unwrap_err());
+    assert!(samples.next().is_none());
+
+    Ok(())
+}
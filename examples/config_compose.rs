@@ -0,0 +1,86 @@
+use samplify_rs::compose::ConfigLoader;
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Address {
+    street: String,
+    city: String,
+    country: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Customer {
+    name: String,
+    age: u32,
+    email: String,
+    shipping: Address,
+    billing: Address,
+    status: Status,
+}
+
+fn main() -> Result<(), String> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/configs/compose");
+    let happy_path = format!("{}/happy_path.json", dir);
+    let edge_cases = format!("{}/edge_cases.json", dir);
+
+    // `$ref` reuses the address config of `shared.json` for both addresses;
+    // keys next to it replace those of the shared config.
+    let config = ConfigLoader::new().load::<Customer>(&happy_path)?;
+    assert_eq!(config["shipping"]["city"], json!(["Berlin", "Paris", "London"]));
+    assert_eq!(config["billing"]["city"], json!(["Berlin"]));
+    assert!(!config.contains_key("$defs") && !config.contains_key("$profiles"));
+    println!("{:?}", Customer::sample_with_config(&config)?);
+
+    // The edge cases extend the happy path, and their `eu` profile adds to
+    // the one they inherit.
+    let config = ConfigLoader::new().with_profile("eu").load::<Customer>(&edge_cases)?;
+    assert_eq!(config["age"], json!([0, 1]));
+    assert_eq!(config["email"], json!({ "template": "{name}@example.com" }));
+    assert_eq!(config["shipping"]["country"], json!(["DE", "FR"]));
+    assert_eq!(config["billing"], json!({ "street": ["Main St", "High St", "Station Rd"], "city": ["Berlin"], "country": ["DE"] }));
+    assert_eq!(config["status"]["variants"], json!(["Suspended"]));
+    println!("{:?}", Customer::sample_with_config(&config)?);
+
+    // Overrides replace values by path after everything else is merged.
+    let config = ConfigLoader::new()
+        .with_profile("eu")
+        .with_override("age", json!([30, 40]))
+        .with_override("status.variant_data.Suspended.reason", json!(["fraud"]))
+        .load::<Customer>(&edge_cases)?;
+    let customer = Customer::sample_with_config(&config)?;
+    println!("{:?}", customer);
+    assert!((30..=40).contains(&customer.age));
+
+    // Configs built in code compose the same way.
+    let config = ConfigLoader::new().compose::<Address>(
+        json!({
+            "$defs": { "cities": ["Oslo", "Bergen"] },
+            "street": ["Storgata"],
+            "city": { "$ref": "#/$defs/cities" },
+            "country": ["NO"]
+        })
+        .as_object()
+        .cloned()
+        .unwrap(),
+    )?;
+    assert_eq!(config["city"], json!(["Oslo", "Bergen"]));
+
+    let error = ConfigLoader::new().with_profile("ue").load::<Customer>(&happy_path).unwrap_err();
+    println!("{}", error);
+    assert!(error.starts_with("Unknown profile 'ue' in ") && error.ends_with("; did you mean 'eu'?"));
+
+    let cyclic = json!({ "$defs": { "a": { "$ref": "#/$defs/b" }, "b": { "$ref": "#/$defs/a" } }, "street": { "$ref": "#/$defs/a" } });
+    let error = ConfigLoader::new().compose::<Address>(cyclic.as_object().cloned().unwrap()).unwrap_err();
+    println!("{}", error);
+    assert_eq!(error, "'$ref' '#/$defs/b' in the config refers to itself");
+
+    Ok(())
+}
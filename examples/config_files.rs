@@ -0,0 +1,60 @@
+use samplify_rs::load::{self, Format};
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct User {
+    name: String,
+    age: u32,
+    email: String,
+    active: bool,
+    address: Address,
+    status: Status,
+}
+
+fn main() -> Result<(), String> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/configs");
+
+    // The same config written in each format reads to the same map.
+    let json = load::from_path(format!("{}/user.json", dir))?;
+    let toml = load::from_path(format!("{}/user.toml", dir))?;
+    let yaml = load::from_path(format!("{}/user.yaml", dir))?;
+    assert_eq!(json, toml);
+    assert_eq!(json, yaml);
+
+    let user = User::sample_with_config(&yaml)?;
+    println!("{:?}", user);
+
+    // Parse errors point at the line and column.
+    let error = load::parse("name: [Alice, Bob]\nage: [18, 65\n", Format::Yaml).unwrap_err();
+    println!("{}", error);
+    assert!(error.starts_with("YAML config, line 3, column 1: "));
+
+    let error = load::parse("name = [\"Alice\"]\nage = [18, 65\n", Format::Toml).unwrap_err();
+    println!("{}", error);
+    assert_eq!(error, "TOML config, line 3, column 1: invalid array; expected `]`");
+
+    let error = load::parse("{\n  \"name\": [\"Alice\"],\n  \"age\": [18 65]\n}", Format::Json).unwrap_err();
+    println!("{}", error);
+    assert_eq!(error, "JSON config, line 3, column 14: expected `,` or `]`");
+
+    let error = load::parse("age = [18, inf]", Format::Toml).unwrap_err();
+    assert_eq!(error, "TOML config: the number inf at 'age.1' cannot be used in a config");
+
+    let error = load::from_path("user.ini").unwrap_err();
+    assert_eq!(error, "Cannot tell the config format of 'user.ini'; expected a .json, .toml, .yaml or .yml file");
+
+    Ok(())
+}
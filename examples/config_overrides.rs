@@ -0,0 +1,80 @@
+use samplify_rs::compose::ConfigLoader;
+use samplify_rs::overrides::Override;
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct User {
+    name: String,
+    age: u32,
+    zipcode: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Account {
+    id: u64,
+    user: User,
+    status: Status,
+}
+
+fn main() -> Result<(), String> {
+    let config = json!({
+        "id": [1, 1000],
+        "user": { "name": ["Bob", "Carol"], "age": [18, 65], "zipcode": ["75001"] },
+        "status": { "variants": ["Active"] }
+    })
+    .as_object()
+    .cloned()
+    .unwrap();
+
+    // In CI: SAMPLIFY__user__age=[30,40] cargo test
+    std::env::set_var("SAMPLIFY__user__age", "[30,40]");
+    let args = ["user.name=Alice", "user.zipcode=10001", "status.variants=[\"Suspended\"]", "status.variant_data.Suspended.reason=fraud"];
+    let config = ConfigLoader::new().with_env("SAMPLIFY").with_args(args)?.compose::<Account>(config)?;
+
+    assert_eq!(config["user"]["age"], json!([30, 40]));
+    assert_eq!(config["user"]["name"], json!("Alice"));
+    // `10001` is a number in JSON, but the field is a `String`, so the text is used.
+    assert_eq!(config["user"]["zipcode"], json!("10001"));
+    assert_eq!(config["status"]["variant_data"], json!({ "Suspended": { "reason": "fraud" } }));
+
+    let account = Account::sample_with_config(&config)?;
+    println!("{:?}", account);
+    assert!((30..=40).contains(&account.user.age));
+    std::env::remove_var("SAMPLIFY__user__age");
+
+    // Overrides are checked against the type before anything is sampled.
+    let vars = [("SAMPLIFY__user__agee".to_string(), "[30,40]".to_string())];
+    let error = Override::from_vars("SAMPLIFY", vars)[0].apply(&Account::config_shape(), &mut config.clone()).unwrap_err();
+    println!("{}", error);
+    assert_eq!(
+        error,
+        "Invalid override of 'user.agee' from environment variable 'SAMPLIFY__user__agee': \
+         Unknown field 'agee' in the config of 'User'; did you mean 'age'?"
+    );
+
+    let error = ConfigLoader::new().with_args(["user.age=true"])?.compose::<Account>(config.clone()).unwrap_err();
+    println!("{}", error);
+    assert!(error.starts_with("Invalid override of 'user.age': "));
+
+    let error = ConfigLoader::new()
+        .with_args(["status.variant_data.Suspend.reason=fraud"])?
+        .compose::<Account>(config.clone())
+        .unwrap_err();
+    println!("{}", error);
+    assert_eq!(
+        error,
+        "Invalid override of 'status.variant_data.Suspend.reason': Unknown variant 'Suspend' in the config of 'Status'; did you mean 'Suspended'?"
+    );
+
+    assert!(Override::parse("user.age").is_err());
+
+    Ok(())
+}
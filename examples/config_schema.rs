@@ -0,0 +1,52 @@
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Channel {
+    Email { address: String },
+    Sms(String),
+    None,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Contact {
+    name: String,
+    channel: Channel,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Team {
+    id: u32,
+    name: String,
+    budget: f64,
+    active: bool,
+    tags: Vec<String>,
+    lead: Option<Contact>,
+    members: Vec<Contact>,
+    sub_teams: Vec<Team>,
+}
+
+fn main() -> Result<(), String> {
+    let schema = Team::config_json_schema();
+    println!("{}", serde_json::to_string_pretty(&schema).map_err(|e| e.to_string())?);
+
+    // Every type reachable from `Team` is defined once, including `Team` itself.
+    let defs = schema["$defs"].as_object().unwrap();
+    assert!(defs.contains_key("Team") && defs.contains_key("Contact") && defs.contains_key("Channel"));
+
+    // The top level also takes the keys of composed configs.
+    let top = &schema["anyOf"][0];
+    assert!(top["properties"]["$defs"].is_object() && top["properties"]["$profiles"].is_object());
+    assert_eq!(schema["anyOf"][1]["required"], json!(["$extends"]));
+
+    let team = &defs["Team"];
+    assert_eq!(team["additionalProperties"], json!(false));
+    assert_eq!(team["required"], json!(["id", "name", "budget", "active"]));
+    let members = &team["properties"]["members"]["anyOf"][0];
+    assert_eq!(members["items"]["anyOf"][0], json!({ "$ref": "#/$defs/Contact" }));
+    assert_eq!(defs["Channel"]["properties"]["variants"]["items"], json!({ "enum": ["Email", "Sms", "None"] }));
+    assert_eq!(defs["Channel"]["properties"]["variant_data"]["properties"]["Sms"]["required"], json!(["field0"]));
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use samplify_rs::config::ConfigOf;
+use samplify_rs::{SampleContext, Sampleable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Expr {
+    Number(i64),
+    Neg(Box<Expr>),
+    Sum { terms: Vec<Expr> },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String, days: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Account {
+    id: u64,
+    owner: String,
+    balance: f64,
+    verified: bool,
+    tags: Vec<String>,
+    addresses: Vec<Address>,
+    billing: Option<Address>,
+    status: Status,
+    formula: Expr,
+    parent: Option<Box<Account>>,
+}
+
+fn main() -> Result<(), String> {
+    let template = Account::config_template();
+    println!("{}", serde_json::to_string_pretty(&template).map_err(|e| e.to_string())?);
+
+    // The template is a working config, which strict mode and the typed config
+    // both accept.
+    let config = template.as_object().unwrap();
+    let mut ctx = SampleContext::new().with_strict(true).with_seed(3);
+    let account = Account::sample_with_context(config, &mut ctx)?;
+    println!("{:#?}", account);
+    let typed: ConfigOf<Account> = serde_json::from_value(template.clone()).map_err(|e| e.to_string())?;
+    Account::sample_with_config(&typed)?;
+
+    // Self-referential fields recurse, and types that lead back to themselves
+    // in other ways stop where they would repeat.
+    assert_eq!(template["parent"], serde_json::json!({ "recursive": true }));
+    assert_eq!(template["formula"]["variant_data"]["Sum"]["terms"], serde_json::json!([]));
+    assert_eq!(template["formula"]["variants"], serde_json::json!(["Number", "Sum"]));
+    assert_eq!(template["status"]["variants"], serde_json::json!(["Active", "Suspended"]));
+
+    Ok(())
+}
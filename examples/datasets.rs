@@ -0,0 +1,49 @@
+use samplify_rs::{SampleContext, Sampleable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Customer {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Payment {
+    id: u64,
+    customer_id: u64,
+    amount: f64,
+}
+
+fn main() -> Result<(), String> {
+    let customer_config: serde_json::Map<String, serde_json::Value> = serde_json::from_str(
+        r#"
+        {
+            "id": { "sequence": true },
+            "name": ["Alice", "Bob", "Carol", "Dave"]
+        }
+        "#,
+    )
+    .map_err(|e| e.to_string())?;
+
+    // Every customer gets between one and three payments.
+    let payment_config: serde_json::Map<String, serde_json::Value> = serde_json::from_str(
+        r#"
+        {
+            "id": { "sequence": { "start": 1000 } },
+            "customer_id": { "ref": "Customer.id", "cardinality": [1, 3] },
+            "amount": { "range": [5.0, 500.0], "decimals": 2 }
+        }
+        "#,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut ctx = SampleContext::new();
+    let customers = Customer::sample_many_with_context(&customer_config, 4, &mut ctx)?;
+    ctx.register("Customer", &customers)?;
+    let payments = Payment::sample_many_with_context(&payment_config, 8, &mut ctx)?;
+
+    println!("{:#?}", customers);
+    println!("{:#?}", payments);
+
+    Ok(())
+}
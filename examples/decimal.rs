@@ -0,0 +1,38 @@
+use rust_decimal::Decimal;
+use samplify_rs::Sampleable;
+
+#[allow(dead_code)]
+#[derive(Debug, Sampleable)]
+struct Payment {
+    currency: String,
+    amount: Decimal,
+    fee: Decimal,
+    fx_rate: Option<Decimal>,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "currency": ["USD", "EUR", "GBP"],
+        "amount": {
+            "range": ["0.01", "99999.99"],
+            "distribution": "log_uniform"
+        },
+        "fee": ["0.50", "2.5"],
+        "fx_rate": {
+            "range": ["0.8", "1.2"],
+            "scale": 6,
+            "distribution": "normal"
+        }
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    let sample_payment = Payment::sample_with_config(&config_map)?;
+
+    println!("{:#?}", sample_payment);
+
+    Ok(())
+}
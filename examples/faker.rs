@@ -0,0 +1,45 @@
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Address {
+    street: String,
+    city: String,
+    zipcode: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Customer {
+    name: String,
+    email: String,
+    phone: String,
+    employer: String,
+    address: Address,
+    notes: String,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "name": { "fake": "name.full" },
+        "email": { "fake": "internet.email" },
+        "phone": { "fake": "phone.e164" },
+        "employer": { "fake": "company.name" },
+        "address": {
+            "street": { "fake": "address.street" },
+            "city": { "fake": "address.city" },
+            "zipcode": { "fake": "address.postcode" }
+        },
+        "notes": { "fake": "lorem.sentence" }
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    let sample_customer = Customer::sample_with_config(&config_map)?;
+
+    println!("{:#?}", sample_customer);
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use samplify_rs::{Presets, SampleContext, Sampleable};
+use serde::{Deserialize, Serialize};
+
+// A small slice of a pacs.008 message, shaped like code generated from the
+// ISO 20022 schemas: simple types are structs with a single `value` field.
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct BICFIDec2014Identifier {
+    value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct IBAN2007Identifier {
+    value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct ISODateTime {
+    value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct ActiveCurrencyAndAmount {
+    value: f64,
+    ccy: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct GroupHeader {
+    msg_id: String,
+    cre_dt_tm: ISODateTime,
+    nb_of_txs: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct FinancialInstitutionIdentification {
+    bicfi: Option<BICFIDec2014Identifier>,
+    lei: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct CreditTransferTransaction {
+    end_to_end_id: String,
+    uetr: Option<String>,
+    intr_bk_sttlm_amt: ActiveCurrencyAndAmount,
+    dbtr_agt: FinancialInstitutionIdentification,
+    dbtr_acct: IBAN2007Identifier,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct FIToFICustomerCreditTransfer {
+    grp_hdr: GroupHeader,
+    cdt_trf_tx_inf: Vec<CreditTransferTransaction>,
+}
+
+fn main() -> Result<(), String> {
+    // Only what differs from the presets needs to be configured.
+    let config_json = r#"
+    {
+        "grp_hdr": { "nb_of_txs": "2" },
+        "cdt_trf_tx_inf": [
+            { "dbtr_agt": {} },
+            { "dbtr_agt": {}, "intr_bk_sttlm_amt": { "ccy": "JPY" } }
+        ]
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    let mut ctx = SampleContext::new().with_presets(Presets::iso20022());
+    let sample_message = FIToFICustomerCreditTransfer::sample_with_context(&config_map, &mut ctx)?;
+
+    println!("{:#?}", sample_message);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use samplify_rs::{SampleContext, Sampleable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Line {
+    line_no: u32,
+    sku: String,
+    quantity: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Order {
+    id: u64,
+    reference: String,
+    customer: String,
+    amount: f64,
+    lines: Vec<Line>,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "id": { "sequence": true },
+        "reference": { "len": [4, 4], "charset": "hex", "unique": true },
+        "customer": { "len": [6, 12], "charset": "alphabetic" },
+        "amount": { "range": [1.0, 5000.0], "decimals": 2 },
+        "lines": [
+            { "line_no": { "sequence": true }, "sku": ["A-1", "B-2", "C-3"], "quantity": [1, 10] },
+            { "line_no": { "sequence": true }, "sku": ["A-1", "B-2", "C-3"], "quantity": [1, 10] }
+        ]
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    let n = 50_000;
+    let orders = Order::sample_many_par(&config_map, n, 42)?;
+    println!("{:#?}", &orders[..2]);
+
+    // Sequences count through the whole batch, as they would sequentially.
+    assert!(orders.iter().enumerate().all(|(i, order)| order.id == i as u64 + 1));
+    assert_eq!(orders[n - 1].lines[1].line_no, 2 * n as u32);
+
+    // Unique values hold across chunks.
+    let mut references: Vec<&str> = orders.iter().map(|order| order.reference.as_str()).collect();
+    references.sort_unstable();
+    references.dedup();
+    assert_eq!(references.len(), n);
+
+    // The output depends on the seed only, not on the number of threads.
+    let sampler = Order::compile_sampler(&config_map)?;
+    let single = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .map_err(|e| e.to_string())?
+        .install(|| sampler.sample_many_par(n, &mut SampleContext::new().with_seed(42)))?;
+    assert_eq!(
+        serde_json::to_value(&orders).map_err(|e| e.to_string())?,
+        serde_json::to_value(&single).map_err(|e| e.to_string())?
+    );
+
+    Ok(())
+}
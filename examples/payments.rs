@@ -0,0 +1,50 @@
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Account {
+    iban: String,
+    bic: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Transfer {
+    debtor: Account,
+    creditor: Account,
+    legal_entity: String,
+    card_number: String,
+    routing_number: String,
+    sort_code: String,
+    amount: f64,
+    currency: String,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "debtor": {
+            "iban": { "generator": "iban", "country": ["DE", "FR", "NL"], "formatted": true },
+            "bic": { "generator": "bic", "country": "DE" }
+        },
+        "creditor": {
+            "iban": { "generator": "iban", "country": "GB" },
+            "bic": { "generator": "bic", "country": "GB", "length": 11 }
+        },
+        "legal_entity": { "generator": "lei" },
+        "card_number": { "generator": "card_pan", "scheme": ["visa", "amex"] },
+        "routing_number": { "generator": "aba_routing" },
+        "sort_code": { "generator": "uk_sort_code" },
+        "amount": { "range": [10.0, 100000.0], "currency_field": "currency" },
+        "currency": { "generator": "currency" }
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    let sample_transfer = Transfer::sample_with_config(&config_map)?;
+
+    println!("{:#?}", sample_transfer);
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Transfer {
+    #[sample(regex = "[A-Z]{4}[A-Z]{2}[A-Z0-9]{2}([A-Z0-9]{3})?")]
+    bic: String,
+    reference: String,
+    postcode: String,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "reference": {
+            "regex": "[A-Z]{2}[0-9]{2}[A-Z0-9]{12,}",
+            "max_repeat": 18
+        },
+        "postcode": { "regex": "\\d{5}" }
+    }
+    "#;
+
+    let config_map: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+
+    // `bic` is not in the config, so the pattern from the attribute is used.
+    let sample_transfer = Transfer::sample_with_config(&config_map)?;
+
+    println!("{:#?}", sample_transfer);
+
+    Ok(())
+}
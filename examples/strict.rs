@@ -0,0 +1,72 @@
+use samplify_rs::{SampleContext, Sampleable, Sampler};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Address {
+    street: String,
+    zipcode: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct User {
+    name: String,
+    address: Address,
+    status: Status,
+}
+
+fn config(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    value.as_object().cloned().unwrap()
+}
+
+fn main() -> Result<(), String> {
+    let valid = config(json!({
+        "name": ["Alice", "Bob"],
+        "address": { "street": ["Main St"], "zipcode": ["10001"] },
+        "status": {
+            "variants": ["Active", "Suspended"],
+            "variant_data": { "Suspended": { "reason": ["fraud"] } }
+        },
+        "$unique": ["name"]
+    }));
+    let user = User::sample_with_context(&valid, &mut SampleContext::new().with_strict(true))?;
+    println!("{:?}", user);
+
+    // Without strict mode, the misspelled key is ignored and the real one is
+    // reported missing.
+    let typo = config(json!({
+        "name": ["Alice"],
+        "address": { "street": ["Main St"], "zipcod": ["10001"] },
+        "status": { "variants": ["Active"] }
+    }));
+    let lenient = User::sample_with_config(&typo).unwrap_err();
+    println!("{}", lenient);
+    assert_eq!(lenient, "Configuration for 'zipcode' is missing");
+
+    let strict = User::sample_with_context(&typo, &mut SampleContext::new().with_strict(true)).unwrap_err();
+    println!("{}", strict);
+    assert_eq!(strict, "Unknown field 'zipcod' in the config of 'Address'; did you mean 'zipcode'?");
+
+    // Variant names in `variant_data` are checked too, also when compiling.
+    let typo = config(json!({
+        "name": ["Alice"],
+        "address": { "street": ["Main St"], "zipcode": ["10001"] },
+        "status": { "variants": ["Active"], "variant_data": { "Suspend": { "reason": ["fraud"] } } }
+    }));
+    assert!(User::sample_with_config(&typo).is_ok());
+    let error = Sampler::<User>::new(&typo, &SampleContext::new().with_strict(true)).unwrap_err();
+    println!("{}", error);
+    assert_eq!(error, "Unknown variant 'Suspend' in the config of 'Status'; did you mean 'Suspended'?");
+
+    let typo = config(json!({ "name": ["Alice"], "$uniqe": ["name"] }));
+    let error = User::sample_with_context(&typo, &mut SampleContext::new().with_strict(true)).unwrap_err();
+    assert_eq!(error, "Unknown directive '$uniqe' in the config of 'User'; did you mean '$unique'?");
+
+    Ok(())
+}
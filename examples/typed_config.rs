@@ -0,0 +1,77 @@
+use samplify_rs::config::{ConfigOf, IntConfig, Length, Nested, StringConfig, StringOptions, VariantOf};
+use samplify_rs::Sampleable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+enum Status {
+    Active,
+    Suspended { reason: String },
+    Closed(u32),
+}
+
+#[derive(Debug, Serialize, Deserialize, Sampleable)]
+struct Customer {
+    id: String,
+    name: String,
+    age: u32,
+    status: Status,
+    tags: Vec<String>,
+    referrer: Option<Box<Customer>>,
+}
+
+fn main() -> Result<(), String> {
+    let config_json = r#"
+    {
+        "id": { "sequence": { "prefix": "C-", "width": 5 } },
+        "name": ["Alice", "Bob", "Charlie"],
+        "age": [18, 65],
+        "status": {
+            "variants": ["Active", "Suspended", "Closed"],
+            "variant_data": {
+                "Suspended": { "reason": ["fraud", "unpaid"] },
+                "Closed": { "field0": [2000, 2025] }
+            }
+        },
+        "tags": ["vip", "new", "churn-risk"],
+        "referrer": { "recursive": { "max_depth": 2, "leaf_probability": 0.5 } },
+        "$constraints": ["age >= 21"]
+    }
+    "#;
+
+    // Typed configs take the same JSON, and check its keys while reading it.
+    let config: ConfigOf<Customer> = serde_json::from_str(config_json).map_err(|e| e.to_string())?;
+    let customer = Customer::sample_with_config(&config)?;
+    println!("{:#?}", customer);
+    assert!(customer.age >= 21);
+
+    // A misspelled key is an error instead of being ignored.
+    let typo = config_json.replace("\"variant_data\"", "\"variant_dat\"");
+    let error = serde_json::from_str::<ConfigOf<Customer>>(&typo).unwrap_err();
+    println!("{}", error);
+    assert!(error.to_string().contains("unknown field `variant_dat`"));
+
+    let typo = config_json.replace("\"leaf_probability\"", "\"leaf_probabilty\"");
+    assert!(serde_json::from_str::<ConfigOf<Customer>>(&typo).is_err());
+
+    let typo = config_json.replace("\"Suspended\", \"Closed\"]", "\"Suspend\", \"Closed\"]");
+    assert!(serde_json::from_str::<ConfigOf<Customer>>(&typo).is_err());
+
+    // Typed configs can also be built in code.
+    let config = ConfigOf::<Customer> {
+        id: Some(StringConfig::Options(Box::new(StringOptions { len: Some(Length::Fixed(8)), ..Default::default() }))),
+        name: Some(StringConfig::Literal("Dana".to_string())),
+        age: Some(IntConfig::Range([30, 40])),
+        status: Some(Nested::new(ConfigOf::<Status> {
+            variants: Some(vec![VariantOf::<Status>::Active]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    let customer = Customer::sample_with_config(&config)?;
+    println!("{:#?}", customer);
+    assert_eq!(customer.name, "Dana");
+    assert!(matches!(customer.status, Status::Active));
+    assert!(customer.referrer.is_none());
+
+    Ok(())
+}
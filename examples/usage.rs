@@ -1,6 +1,5 @@
 use samplify_rs::Sampleable;
 use serde::{Deserialize, Serialize};
-use serde_json;
 
 #[derive(Debug, Serialize, Deserialize, Sampleable)]
 enum Status {
@@ -28,6 +27,7 @@ struct User {
 
 #[derive(Debug, Serialize, Deserialize, Sampleable)]
 struct Email {
+    address: String,
     account: String,
     domain: String,
 }
@@ -50,6 +50,7 @@ fn main() -> Result<(), String> {
             }
         ],
         "email": {
+            "address": { "template": "{account}@{domain}" },
             "account": ["hari", "shankar", "harishankar"],
             "domain": ["gmail.com", "hotmail.com"]
         },
@@ -0,0 +1,1192 @@
+use proc_macro::TokenStream;
+use quote::{quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Type};
+
+#[proc_macro_derive(Sampleable, attributes(sample))]
+pub fn sampleable_derive(input: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree.
+    let input = parse_macro_input!(input as DeriveInput);
+
+    // Get the name of the struct or enum.
+    let name = input.ident.clone();
+    let vis = input.vis.clone();
+
+    // Match on the data type: struct or enum
+    match input.data {
+        Data::Struct(data_struct) => {
+            // Handle structs
+            expand_struct(name, &vis, &input.attrs, data_struct)
+        },
+        Data::Enum(data_enum) => {
+            // Handle enums
+            expand_enum(name, &vis, data_enum)
+        },
+        _ => {
+            unimplemented!("Sampleable can only be derived for structs and enums");
+        }
+    }
+}
+
+fn expand_struct(name: syn::Ident, vis: &syn::Visibility, attrs: &[syn::Attribute], data_struct: syn::DataStruct) -> TokenStream {
+    // Extract the fields from the struct.
+    let fields = match data_struct.fields {
+        Fields::Named(fields_named) => fields_named.named,
+        _ => unimplemented!("Sampleable can only be derived for structs with named fields"),
+    };
+
+    // Generate code for each field.
+    let named_fields: Vec<(String, &syn::Field)> = fields.iter()
+        .map(|field| (field.ident.as_ref().unwrap().to_string(), field))
+        .collect();
+    let (plan_bindings, plan_values, computed) = match generate_plan_bindings(&named_fields, Some(&name)) {
+        Ok(generated) => generated,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // Computed fields are filled in declaration order once the others are
+    // sampled, so each function sees the fields computed before it. Only the
+    // values that constraints read are recorded.
+    let computations = computed.iter().map(|(index, compute)| {
+        let field = named_fields[*index].1;
+        let ident = field.ident.as_ref().unwrap();
+        let record = if is_recordable(&field.ty) {
+            quote! {
+                if __fields.field(#index).is_recorded() {
+                    __values.set(#index, ::samplify_rs::fields::FieldValue::to_scalar(&__sample.#ident));
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            __sample.#ident = #compute(&__sample);
+            #record
+        }
+    }).collect::<Vec<_>>();
+    let sample_binding = if computed.is_empty() {
+        quote! { let __sample }
+    } else {
+        quote! { let mut __sample }
+    };
+    let field_shapes = match generate_field_shapes(&named_fields, Some(&name)) {
+        Ok(shapes) => shapes,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let name_str = name.to_string();
+    let field_idents: Vec<_> = fields.iter().map(|field| field.ident.as_ref().unwrap()).collect();
+    let constraints = match parse_struct_attrs(attrs) {
+        Ok(constraints) => constraints,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // Constraints from `#[sample(constraint = "...")]` become hidden methods
+    // so that `self` in the expression refers to the sampled struct.
+    let constraint_methods: Vec<syn::Ident> = (0..constraints.len())
+        .map(|i| quote::format_ident!("__samplify_constraint_{}", i))
+        .collect();
+    let constraint_sources: Vec<_> = constraints.iter().map(|(source, _)| source).collect();
+    let constraint_exprs = constraints.iter().map(|(_, expr)| expr);
+    let constraint_impl = if constraints.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            impl #name {
+                #(
+                    #[doc(hidden)]
+                    fn #constraint_methods(&self) -> bool {
+                        #constraint_exprs
+                    }
+                )*
+            }
+        }
+    };
+
+    // Resample until the `$constraints` of the config and the constraint
+    // attributes hold, or the attempts run out. Unique values and sequence
+    // numbers drawn by a rejected attempt are given back.
+    let has_constraint_attrs = !constraints.is_empty();
+    let rejection_loop = quote! {
+        let __guarded = #has_constraint_attrs || !__constraints.is_empty();
+        let mut __attempts = 0;
+        loop {
+            __attempts += 1;
+            let __attempt = ctx.attempt(__guarded, |ctx| {
+                #plan_bindings
+
+                #sample_binding = Self {
+                    #(#field_idents: #plan_values),*
+                };
+                #(#computations)*
+                let __violated: Option<&str> = __constraints.violated(&__values)?
+                    #(.or_else(|| if __sample.#constraint_methods() { None } else { Some(#constraint_sources) }))*;
+                Ok(match __violated {
+                    None => Ok(__sample),
+                    Some(constraint) => Err(constraint),
+                })
+            })?;
+            match __attempt {
+                Ok(__sample) => return Ok(__sample),
+                Err(constraint) if __attempts >= __constraints.max_attempts() => {
+                    return Err(format!(
+                        "Could not satisfy constraint '{}' for '{}' after {} attempts",
+                        constraint, stringify!(#name), __attempts
+                    ));
+                }
+                Err(_) => {}
+            }
+        }
+    };
+    let plan_body = if named_fields.is_empty() {
+        quote! { Ok(Self {}) }
+    } else {
+        quote! {
+            let __fields = __plan.fields();
+            let __constraints = __plan.constraints();
+            #rejection_loop
+        }
+    };
+
+    // The typed config, with the `$` directives a struct config accepts. Its
+    // name is hidden behind `ConfigOf<Name>` so it cannot clash with a type
+    // of the user's crate.
+    let config_name = quote::format_ident!("__SamplifyConfig_{}", name);
+    let config_doc = format!("Typed sample config for [`{}`]; see `samplify_rs::config::ConfigOf`.", name_str);
+    let directives = quote! {
+        #[serde(rename = "$locale", skip_serializing_if = "Option::is_none")]
+        pub sample_locale: Option<String>,
+        #[serde(rename = "$constraints", skip_serializing_if = "Option::is_none")]
+        pub sample_constraints: Option<::samplify_rs::config::OneOrMany<String>>,
+        #[serde(rename = "$max_attempts", skip_serializing_if = "Option::is_none")]
+        pub sample_max_attempts: Option<u32>,
+        #[serde(rename = "$unique", skip_serializing_if = "Option::is_none")]
+        pub sample_unique: Option<Vec<String>>,
+    };
+    let config_struct = match generate_config_struct(vis, &config_name, &config_doc, &named_fields, Some(&name), directives) {
+        Ok(config_struct) => config_struct,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let typed_fields = match generate_typed_fields(&named_fields, &quote!(self)) {
+        Ok(typed_fields) => typed_fields,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let config_impls = generate_config_impls(&name, &config_name);
+
+    // Implement Sampleable; sample_with_config is provided by the trait.
+    let expanded = quote! {
+        #constraint_impl
+        #config_struct
+        #config_impls
+
+        impl ::samplify_rs::config::ConfigSource<#name> for #config_name {
+            fn compile(&self, ctx: &::samplify_rs::SampleContext) -> Result<::samplify_rs::plan::Plan, String> {
+                ::samplify_rs::config::ConfigSource::<#name>::compile_with_defaults(self, &serde_json::Map::new(), ctx)
+            }
+
+            fn compile_with_defaults(&self, defaults: &serde_json::Map<String, serde_json::Value>, ctx: &::samplify_rs::SampleContext) -> Result<::samplify_rs::plan::Plan, String> {
+                ::samplify_rs::plan::TypedStruct {
+                    fields: #typed_fields,
+                    locale: self.sample_locale.as_deref(),
+                    constraints: self.sample_constraints.as_ref(),
+                    max_attempts: self.sample_max_attempts,
+                }
+                .compile(&<#name as ::samplify_rs::Sampleable>::config_shape(), defaults, ctx)
+            }
+        }
+
+        impl ::samplify_rs::Sampleable for #name {
+            fn sample_with_context(config: &serde_json::Map<String, serde_json::Value>, ctx: &mut ::samplify_rs::SampleContext) -> Result<Self, String> {
+                ::samplify_rs::plan::sample_compiled(config, ctx)
+            }
+
+            fn sample_plan(plan: &::samplify_rs::plan::Plan, ctx: &mut ::samplify_rs::SampleContext) -> Result<Self, String> {
+                let __plan = plan.as_struct(#name_str)?;
+                ctx.scoped_locale(__plan.locale(), |ctx| {
+                    use rand::Rng;
+                    use rand::seq::SliceRandom;
+
+                    #plan_body
+                })
+            }
+
+            fn nested_config(value: serde_json::Value) -> Result<::samplify_rs::config::Nested<Self>, String> {
+                serde_json::from_value::<#config_name>(value).map(::samplify_rs::config::Nested::new).map_err(|e| e.to_string())
+            }
+
+            fn config_shape() -> ::samplify_rs::shape::Shape {
+                ::samplify_rs::shape::Shape::Struct(::samplify_rs::shape::StructShape {
+                    name: #name_str,
+                    module: module_path!(),
+                    fields: vec![#(#field_shapes),*],
+                })
+            }
+        }
+    };
+
+    // Return the generated code.
+    TokenStream::from(expanded)
+}
+
+fn expand_enum(name: syn::Ident, vis: &syn::Visibility, data_enum: syn::DataEnum) -> TokenStream {
+    // Get the variants
+    let variants = data_enum.variants;
+    let name_str = name.to_string();
+
+    let mut variant_plan_cases = Vec::new();
+    let mut variant_shapes = Vec::new();
+    let mut variant_configs = Vec::new();
+    let mut variant_data_fields = Vec::new();
+    let mut typed_variant_data = Vec::new();
+    for variant in variants.iter() {
+        let variant_name = &variant.ident;
+        let variant_name_str = variant_name.to_string();
+        let variant_fields: Vec<(String, &syn::Field)> = match &variant.fields {
+            Fields::Unit => Vec::new(),
+            Fields::Named(fields_named) => fields_named.named.iter()
+                .map(|field| (field.ident.as_ref().unwrap().to_string(), field))
+                .collect(),
+            Fields::Unnamed(fields_unnamed) => fields_unnamed.unnamed.iter().enumerate()
+                .map(|(i, field)| (format!("field{}", i), field))
+                .collect(),
+        };
+        let variant_field_shapes = if matches!(variant.fields, Fields::Unit) {
+            quote! { None }
+        } else {
+            match generate_field_shapes(&variant_fields, None) {
+                Ok(shapes) => quote! { Some(vec![#(#shapes),*]) },
+                Err(err) => return err.to_compile_error().into(),
+            }
+        };
+        variant_shapes.push(quote! {
+            ::samplify_rs::shape::VariantShape { name: #variant_name_str, fields: #variant_field_shapes }
+        });
+        if !matches!(variant.fields, Fields::Unit) {
+            let data_name = quote::format_ident!("__SamplifyVariantData_{}_{}", name, variant_name);
+            let data_doc = format!("Typed sample config for the `{}` variant of [`{}`].", variant_name_str, name_str);
+            let directives = quote! {
+                #[serde(rename = "$unique", skip_serializing_if = "Option::is_none")]
+                pub sample_unique: Option<Vec<String>>,
+            };
+            match generate_config_struct(vis, &data_name, &data_doc, &variant_fields, None, directives) {
+                Ok(config_struct) => variant_configs.push(config_struct),
+                Err(err) => return err.to_compile_error().into(),
+            }
+            variant_data_fields.push(quote! {
+                #[serde(rename = #variant_name_str, skip_serializing_if = "Option::is_none")]
+                pub #variant_name: Option<#data_name>,
+            });
+            let typed_fields = match generate_typed_fields(&variant_fields, &quote!(__config)) {
+                Ok(typed_fields) => typed_fields,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            typed_variant_data.push(quote! {
+                if let Some(__config) = &__data.#variant_name {
+                    __variant_data.push((#variant_name_str, #typed_fields));
+                }
+            });
+        }
+
+        let (plan_bindings, plan_values) = match generate_variant_bindings(&variant_fields) {
+            Ok(generated) => generated,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let variant_plan_case = match &variant.fields {
+            Fields::Unit => quote! {
+                #variant_name_str => #name::#variant_name
+            },
+            Fields::Named(fields_named) => {
+                let field_idents = fields_named.named.iter().map(|field| field.ident.as_ref().unwrap());
+                quote! {
+                    #variant_name_str => {
+                        #plan_bindings
+                        #name::#variant_name {
+                            #(#field_idents: #plan_values),*
+                        }
+                    }
+                }
+            }
+            Fields::Unnamed(_) => quote! {
+                #variant_name_str => {
+                    #plan_bindings
+                    #name::#variant_name(
+                        #(#plan_values),*
+                    )
+                }
+            },
+        };
+        variant_plan_cases.push(variant_plan_case);
+    }
+
+    // The typed config: the variants to pick from, and the config of each
+    // variant with fields.
+    let config_name = quote::format_ident!("__SamplifyConfig_{}", name);
+    let variant_enum = quote::format_ident!("__SamplifyVariant_{}", name);
+    let variant_data = quote::format_ident!("__SamplifyVariantData_{}", name);
+    let variant_idents: Vec<_> = variants.iter().map(|v| &v.ident).collect();
+    let variant_name_strs: Vec<String> = variant_idents.iter().map(|ident| ident.to_string()).collect();
+    let config_doc = format!("Typed sample config for [`{}`]; see `samplify_rs::config::ConfigOf`.", name_str);
+    let variant_enum_doc = format!("The variants of [`{}`], for the `variants` of its typed config.", name_str);
+    let variant_data_doc = format!("The `variant_data` of the typed config of [`{}`].", name_str);
+    let config_impls = generate_config_impls(&name, &config_name);
+
+    // Implement Sampleable for the enum
+    let expanded = quote! {
+        #(#variant_configs)*
+
+        #[doc = #variant_enum_doc]
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, ::samplify_rs::serde::Serialize, ::samplify_rs::serde::Deserialize)]
+        #[serde(crate = "::samplify_rs::serde")]
+        #vis enum #variant_enum {
+            #(#variant_idents),*
+        }
+
+        #[doc = #variant_data_doc]
+        #[doc(hidden)]
+        #[allow(non_snake_case, non_camel_case_types)]
+        #[derive(Debug, Clone, Default, ::samplify_rs::serde::Serialize, ::samplify_rs::serde::Deserialize)]
+        #[serde(crate = "::samplify_rs::serde", default, deny_unknown_fields)]
+        #vis struct #variant_data {
+            #(#variant_data_fields)*
+        }
+
+        #[doc = #config_doc]
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Default, ::samplify_rs::serde::Serialize, ::samplify_rs::serde::Deserialize)]
+        #[serde(crate = "::samplify_rs::serde", default, deny_unknown_fields)]
+        #vis struct #config_name {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub variants: Option<Vec<#variant_enum>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub variant_data: Option<#variant_data>,
+            #[serde(rename = "$locale", skip_serializing_if = "Option::is_none")]
+            pub sample_locale: Option<String>,
+        }
+
+        #config_impls
+
+        impl ::samplify_rs::config::ConfigurableEnum for #name {
+            type Variant = #variant_enum;
+            type VariantData = #variant_data;
+        }
+
+        impl ::samplify_rs::config::ConfigSource<#name> for #config_name {
+            fn compile(&self, ctx: &::samplify_rs::SampleContext) -> Result<::samplify_rs::plan::Plan, String> {
+                let mut __variant_data = Vec::new();
+                if let Some(__data) = &self.variant_data {
+                    #(#typed_variant_data)*
+                }
+                ::samplify_rs::plan::TypedEnum {
+                    variants: self.variants.as_ref().map(|variants| {
+                        variants.iter().map(|variant| match variant {
+                            #(#variant_enum::#variant_idents => #variant_name_strs,)*
+                        }).collect()
+                    }),
+                    variant_data: __variant_data,
+                    locale: self.sample_locale.as_deref(),
+                }
+                .compile(&<#name as ::samplify_rs::Sampleable>::config_shape(), ctx)
+            }
+        }
+
+        impl ::samplify_rs::Sampleable for #name {
+            fn sample_with_context(config: &serde_json::Map<String, serde_json::Value>, ctx: &mut ::samplify_rs::SampleContext) -> Result<Self, String> {
+                ::samplify_rs::plan::sample_compiled(config, ctx)
+            }
+
+            fn sample_plan(plan: &::samplify_rs::plan::Plan, ctx: &mut ::samplify_rs::SampleContext) -> Result<Self, String> {
+                let __plan = plan.as_enum(#name_str)?;
+                ctx.scoped_locale(__plan.locale(), |ctx| {
+                    use rand::Rng;
+                    use rand::seq::SliceRandom;
+
+                    let __variant = __plan.choose(ctx.rng());
+                    let __fields = __variant.fields();
+                    let result = match __variant.name() {
+                        #(#variant_plan_cases,)*
+                        _ => return Err(format!("Variant '{}' is not recognized", __variant.name())),
+                    };
+
+                    Ok(result)
+                })
+            }
+
+            fn nested_config(value: serde_json::Value) -> Result<::samplify_rs::config::Nested<Self>, String> {
+                serde_json::from_value::<#config_name>(value).map(::samplify_rs::config::Nested::new).map_err(|e| e.to_string())
+            }
+
+            fn config_shape() -> ::samplify_rs::shape::Shape {
+                ::samplify_rs::shape::Shape::Enum(::samplify_rs::shape::EnumShape {
+                    name: #name_str,
+                    module: module_path!(),
+                    variants: vec![#(#variant_shapes),*],
+                })
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+// A typed config struct with an optional config for each field of `fields`
+// that is not computed, followed by the `directives` fields.
+fn generate_config_struct(vis: &syn::Visibility, config_name: &syn::Ident, doc: &str, fields: &[(String, &syn::Field)], self_name: Option<&syn::Ident>, directives: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let mut config_fields = Vec::new();
+    for (field_name_str, field) in fields {
+        if parse_field_attrs(field)?.compute.is_some() {
+            continue;
+        }
+        let ident = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => quote::format_ident!("{}", field_name_str),
+        };
+        let config_type = generate_config_type(&field.ty, self_name);
+        config_fields.push(quote! {
+            #[serde(rename = #field_name_str, skip_serializing_if = "Option::is_none")]
+            pub #ident: Option<#config_type>,
+        });
+    }
+    Ok(quote! {
+        #[doc = #doc]
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Default, ::samplify_rs::serde::Serialize, ::samplify_rs::serde::Deserialize)]
+        #[serde(crate = "::samplify_rs::serde", default, deny_unknown_fields)]
+        #vis struct #config_name {
+            #(#config_fields)*
+            #directives
+        }
+    })
+}
+
+// Ties the typed config to the type, so that `ConfigOf<Name>` names it.
+fn generate_config_impls(name: &syn::Ident, config_name: &syn::Ident) -> proc_macro2::TokenStream {
+    quote! {
+        impl ::samplify_rs::config::Configurable for #name {
+            type Config = #config_name;
+        }
+    }
+}
+
+// The `TypedFields` of `config`, a typed config struct generated for `fields`
+// by `generate_config_struct`.
+fn generate_typed_fields(fields: &[(String, &syn::Field)], config: &proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let mut entries = Vec::new();
+    for (field_name_str, field) in fields {
+        if parse_field_attrs(field)?.compute.is_some() {
+            entries.push(quote! { None });
+            continue;
+        }
+        let ident = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => quote::format_ident!("{}", field_name_str),
+        };
+        entries.push(quote! {
+            #config.#ident.as_ref().map(|config| config as &dyn ::samplify_rs::config::FieldConfig)
+        });
+    }
+    Ok(quote! {
+        ::samplify_rs::plan::TypedFields {
+            fields: vec![#(#entries),*],
+            unique: #config.sample_unique.as_deref(),
+        }
+    })
+}
+
+// The typed config of a field. Fields of other types hold a `Nested` config,
+// which also lets a type reach itself (for example an enum variant holding
+// the enum).
+fn generate_config_type(ty: &Type, self_name: Option<&syn::Ident>) -> proc_macro2::TokenStream {
+    if let Some(recursion) = self_name.and_then(|self_name| self_recursion(ty, self_name)) {
+        let nested = match recursion {
+            Recursion::Vec => quote! { Vec<::samplify_rs::config::Nested<#self_name>> },
+            Recursion::OptionBox | Recursion::Box => quote! { ::samplify_rs::config::Nested<#self_name> },
+        };
+        return quote! { ::samplify_rs::config::RecursiveConfig<#nested> };
+    }
+    if is_option(ty) || is_box(ty) {
+        return generate_config_type(&get_inner_type(ty), self_name);
+    }
+    if is_vec(ty) {
+        let item_type = get_inner_type(ty);
+        let item = if is_primitive(&item_type) || is_decimal(&item_type) {
+            generate_config_value_type(&item_type)
+        } else {
+            quote! { ::samplify_rs::config::Nested<#item_type> }
+        };
+        return quote! { Vec<#item> };
+    }
+    if is_decimal(ty) {
+        return quote! { ::samplify_rs::config::DecimalConfig };
+    }
+    if is_primitive(ty) {
+        if let Type::Path(type_path) = ty {
+            match type_path.path.segments.last().unwrap().ident.to_string().as_str() {
+                "String" => return quote! { ::samplify_rs::config::StringConfig },
+                "bool" => return quote! { ::samplify_rs::config::BoolConfig },
+                "f32" | "f64" => return quote! { ::samplify_rs::config::FloatConfig },
+                _ => return quote! { ::samplify_rs::config::IntConfig },
+            }
+        }
+    }
+    quote! { ::samplify_rs::config::Nested<#ty> }
+}
+
+// The type of one value of a `Vec` of primitives, as written in its config.
+fn generate_config_value_type(ty: &Type) -> proc_macro2::TokenStream {
+    if is_decimal(ty) {
+        return quote! { String };
+    }
+    match ty {
+        Type::Path(type_path) => match type_path.path.segments.last().unwrap().ident.to_string().as_str() {
+            "String" => quote! { String },
+            "bool" => quote! { bool },
+            "f32" | "f64" => quote! { f64 },
+            _ => quote! { i64 },
+        },
+        _ => quote! { serde_json::Value },
+    }
+}
+
+// Field bindings for an enum variant, which has no struct to pass to a
+// `compute` function.
+fn generate_variant_bindings(fields: &[(String, &syn::Field)]) -> syn::Result<(proc_macro2::TokenStream, Vec<proc_macro2::TokenStream>)> {
+    let (bindings, values, computed) = generate_plan_bindings(fields, None)?;
+    match computed.first() {
+        Some((index, _)) => Err(syn::Error::new_spanned(fields[*index].1, "`compute` is only supported on struct fields")),
+        None => Ok((bindings, values)),
+    }
+}
+
+// Declares `__values`, the slots of the sibling values that templates,
+// `currency_field` and constraints read, one per field in declaration order.
+fn values_binding(fields: &[(String, &syn::Field)], records_values: bool) -> proc_macro2::TokenStream {
+    let field_names = fields.iter().map(|(field_name_str, _)| field_name_str);
+    let count = fields.len();
+    let mutability = if records_values { quote! { mut } } else { quote! {} };
+    quote! {
+        let mut __slots: [Option<::samplify_rs::fields::Scalar>; #count] = ::core::array::from_fn(|_| None);
+        let #mutability __values = ::samplify_rs::fields::FieldValues::new(&[#(#field_names),*], &mut __slots);
+    }
+}
+
+// Generates statements that sample `fields` into locals from the compiled
+// field specs of `__fields`, a `FieldsPlan`. Fields are matched by
+// declaration index; the plan lists them in sampling order, so fields
+// referring to siblings (e.g. templates) come after them. Also returns, per
+// field, the expression that reads its local, and the index and function of
+// every `compute` field; those start out as `Default::default()` and are left
+// for the caller to fill in. `self_name` is the struct being derived, whose
+// self-referential fields may be recursive.
+#[allow(clippy::type_complexity)]
+fn generate_plan_bindings(fields: &[(String, &syn::Field)], self_name: Option<&syn::Ident>) -> syn::Result<(proc_macro2::TokenStream, Vec<proc_macro2::TokenStream>, Vec<(usize, syn::Path)>)> {
+    let mut values = Vec::new();
+    let mut declarations = Vec::new();
+    let mut arms = Vec::new();
+    let mut computed = Vec::new();
+    let mut records_values = false;
+    for (index, (field_name_str, field)) in fields.iter().enumerate() {
+        let field_type = &field.ty;
+        let attrs = parse_field_attrs(field)?;
+        if is_recordable(field_type) {
+            records_values = true;
+        }
+        if let Some(compute) = attrs.compute {
+            values.push(quote! { ::core::default::Default::default() });
+            computed.push((index, compute));
+            continue;
+        }
+
+        let source = quote! { __field.source() };
+        let sample_code = match self_name.and_then(|self_name| self_recursion(field_type, self_name)) {
+            Some(recursion) => {
+                let recursive_code = generate_recursion(recursion, field_name_str, quote! {
+                    <Self as ::samplify_rs::Sampleable>::sample_plan(plan, ctx)
+                });
+                let sample_code = generate_plan_sample_code(field_type, field_name_str, &quote! { __source });
+                quote! {
+                    match #source {
+                        ::samplify_rs::plan::Source::Recursive(__recursive) => { #recursive_code }
+                        __source => { #sample_code }
+                    }
+                }
+            }
+            None => generate_plan_sample_code(field_type, field_name_str, &source),
+        };
+        let local = quote::format_ident!("__field_{}", index);
+        let (sample_code, record) = if is_recordable(field_type) {
+            let sample_code = quote! {
+                match #source {
+                    ::samplify_rs::plan::Source::Ref(__spec) => {
+                        let __referenced = ctx.draw_ref(__spec, ::samplify_rs::context::FieldAt::Plan(__field))?;
+                        ::samplify_rs::fields::FieldValue::from_field_value(&__referenced).ok_or_else(|| {
+                            format!("Value {} referenced by field '{}' does not fit its type", __referenced, #field_name_str)
+                        })?
+                    }
+                    _ => { #sample_code }
+                }
+            };
+            let sample_code = quote! {
+                if __field.is_unique() {
+                    let mut __attempts = 0;
+                    loop {
+                        let __value: #field_type = #sample_code;
+                        let __key = ::samplify_rs::fields::FieldValue::to_scalar(&__value);
+                        if ctx.claim_unique(::samplify_rs::context::FieldAt::Plan(__field), &__key) {
+                            break __value;
+                        }
+                        __attempts += 1;
+                        if __attempts >= ::samplify_rs::unique::MAX_ATTEMPTS {
+                            return Err(format!(
+                                "Could not find a unique value for '{}' after {} attempts; its value space may be exhausted",
+                                ctx.field_path(#field_name_str), __attempts
+                            ));
+                        }
+                    }
+                } else {
+                    #sample_code
+                }
+            };
+            let record = quote! {
+                if __field.is_recorded() {
+                    __values.set(#index, ::samplify_rs::fields::FieldValue::to_scalar(&__value));
+                }
+            };
+            (sample_code, record)
+        } else {
+            (sample_code, quote! {})
+        };
+
+        declarations.push(quote! {
+            let mut #local: Option<#field_type> = None;
+        });
+        arms.push(quote! {
+            #index => {
+                let __field = __fields.field(#index);
+                let __value: #field_type = { #sample_code };
+                #record
+                #local = Some(__value);
+            }
+        });
+        values.push(quote! { #local.unwrap() });
+    }
+
+    let values_binding = values_binding(fields, records_values);
+    let bindings = if fields.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            #values_binding
+            #(#declarations)*
+            for &__index in __fields.order() {
+                match __index {
+                    #(#arms)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+    };
+    Ok((bindings, values, computed))
+}
+
+// Samples a value of `field_type` from `source`, an expression of type
+// `&Source`, looking through `Option` and `Box`.
+fn generate_plan_sample_code(field_type: &Type, field_name_str: &str, source: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let mismatch = quote! {
+        _ => return Err(format!("Plan for field '{}' does not match its type", #field_name_str)),
+    };
+    if is_option(field_type) {
+        let inner_sample_code = generate_plan_sample_code(&get_inner_type(field_type), field_name_str, &quote! { __source });
+        quote! {
+            match #source {
+                ::samplify_rs::plan::Source::None => None,
+                __source => Some(#inner_sample_code),
+            }
+        }
+    } else if is_box(field_type) {
+        let inner_sample_code = generate_plan_sample_code(&get_inner_type(field_type), field_name_str, source);
+        quote! {
+            Box::new(#inner_sample_code)
+        }
+    } else if is_vec(field_type) {
+        let element_type = get_inner_type(field_type);
+        if is_primitive(&element_type) || is_decimal(&element_type) {
+            quote! {
+                match #source {
+                    ::samplify_rs::plan::Source::None => Vec::new(),
+                    ::samplify_rs::plan::Source::Pick(__choices) => {
+                        let rng = ctx.rng();
+                        let sample_size = rng.gen_range(1..=__choices.len());
+                        __choices.choose_multiple(rng, sample_size)
+                            .filter_map(<#element_type as ::samplify_rs::fields::FieldValue>::from_scalar)
+                            .collect::<Vec<#element_type>>()
+                    }
+                    #mismatch
+                }
+            }
+        } else {
+            quote! {
+                match #source {
+                    ::samplify_rs::plan::Source::None => Vec::new(),
+                    ::samplify_rs::plan::Source::Items(__plans) => {
+                        let mut vec = Vec::with_capacity(__plans.len());
+                        for __item in __plans {
+                            vec.push(ctx.nested(#field_name_str, |ctx| <#element_type as ::samplify_rs::Sampleable>::sample_plan(__item, ctx))?);
+                        }
+                        vec
+                    }
+                    #mismatch
+                }
+            }
+        }
+    } else if is_decimal(field_type) {
+        quote! {
+            match #source {
+                ::samplify_rs::plan::Source::Decimal(__spec) => __spec.sample::<#field_type, _>(ctx.rng(), &__values, #field_name_str)?,
+                #mismatch
+            }
+        }
+    } else if is_primitive(field_type) {
+        let type_ident_str = match field_type {
+            Type::Path(type_path) => type_path.path.segments.last().unwrap().ident.to_string(),
+            _ => String::new(),
+        };
+        let arm = match type_ident_str.as_str() {
+            "f32" | "f64" => quote! {
+                ::samplify_rs::plan::Source::Float(__spec) => __spec.sample(ctx.rng(), &__values, #field_name_str)? as #field_type,
+            },
+            "String" => quote! {
+                ::samplify_rs::plan::Source::String(__spec) => {
+                    __spec.sample(&__values, ctx, ::samplify_rs::context::FieldAt::Plan(__field))?
+                }
+            },
+            "bool" => quote! {
+                ::samplify_rs::plan::Source::Bool(__value) => *__value,
+            },
+            _ => quote! {
+                ::samplify_rs::plan::Source::Integer(__spec) => {
+                    ::samplify_rs::integer::fit::<#field_type>(__spec.sample(ctx, ::samplify_rs::context::FieldAt::Plan(__field)), #field_name_str)?
+                }
+            },
+        };
+        quote! {
+            match #source {
+                #arm
+                #mismatch
+            }
+        }
+    } else {
+        quote! {
+            match #source {
+                ::samplify_rs::plan::Source::Nested(__nested) => {
+                    ctx.nested(#field_name_str, |ctx| <#field_type as ::samplify_rs::Sampleable>::sample_plan(__nested, ctx))?
+                }
+                #mismatch
+            }
+        }
+    }
+}
+
+// `FieldShape` expressions describing the config of `fields`, for
+// `Sampleable::config_shape`.
+fn generate_field_shapes(fields: &[(String, &syn::Field)], self_name: Option<&syn::Ident>) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+    let mut shapes = Vec::new();
+    for (field_name_str, field) in fields {
+        let field_type = &field.ty;
+        let attrs = parse_field_attrs(field)?;
+        let type_name = preset_type_name(field_type);
+        let kind = generate_field_kind(field_type);
+        let optional = is_option(field_type);
+        let computed = attrs.compute.is_some();
+        let required = !optional && !is_vec(field_type) && !computed && attrs.regex.is_none();
+        let recursive = self_name.and_then(|self_name| self_recursion(field_type, self_name)).is_some();
+        let regex = match &attrs.regex {
+            Some(pattern) => quote! { Some(#pattern) },
+            None => quote! { None },
+        };
+        let max_repeat = attrs.max_repeat.unwrap_or(32);
+        shapes.push(quote! {
+            ::samplify_rs::shape::FieldShape {
+                name: #field_name_str,
+                type_name: #type_name,
+                kind: #kind,
+                required: #required,
+                optional: #optional,
+                computed: #computed,
+                recursive: #recursive,
+                regex: #regex,
+                max_repeat: #max_repeat,
+            }
+        });
+    }
+    Ok(shapes)
+}
+
+fn generate_field_kind(ty: &Type) -> proc_macro2::TokenStream {
+    if is_option(ty) || is_box(ty) {
+        return generate_field_kind(&get_inner_type(ty));
+    }
+    if is_vec(ty) {
+        let item = generate_field_kind(&get_inner_type(ty));
+        return quote! { ::samplify_rs::shape::FieldKind::List(Box::new(#item)) };
+    }
+    if is_decimal(ty) {
+        return quote! { ::samplify_rs::shape::FieldKind::Decimal };
+    }
+    if is_primitive(ty) {
+        if let Type::Path(type_path) = ty {
+            match type_path.path.segments.last().unwrap().ident.to_string().as_str() {
+                "String" => return quote! { ::samplify_rs::shape::FieldKind::String },
+                "bool" => return quote! { ::samplify_rs::shape::FieldKind::Bool },
+                "f32" | "f64" => return quote! { ::samplify_rs::shape::FieldKind::Float { max: <#ty>::MAX as f64 } },
+                _ => {
+                    return quote! {
+                        ::samplify_rs::shape::FieldKind::Integer { min: <#ty>::MIN as i128, max: <#ty>::MAX as i128 }
+                    }
+                }
+            }
+        }
+    }
+    quote! { ::samplify_rs::shape::FieldKind::Nested(<#ty as ::samplify_rs::Sampleable>::config_shape) }
+}
+
+// How a field refers to the struct it belongs to.
+#[derive(Clone, Copy)]
+enum Recursion {
+    OptionBox,
+    Box,
+    Vec,
+}
+
+fn self_recursion(ty: &Type, self_name: &syn::Ident) -> Option<Recursion> {
+    if is_option(ty) {
+        let inner = get_inner_type(ty);
+        return (is_box(&inner) && is_self_type(&get_inner_type(&inner), self_name)).then_some(Recursion::OptionBox);
+    }
+    if is_box(ty) && is_self_type(&get_inner_type(ty), self_name) {
+        return Some(Recursion::Box);
+    }
+    if is_vec(ty) && is_self_type(&get_inner_type(ty), self_name) {
+        return Some(Recursion::Vec);
+    }
+    None
+}
+
+// Samples a recursive field given `__recursive`, a `RecursiveSpec`, with
+// `sample` producing one more level of the enclosing struct.
+fn generate_recursion(recursion: Recursion, field_name_str: &str, sample: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let recurse = quote! {
+        ctx.recurse(#field_name_str, |ctx| #sample)?
+    };
+    match recursion {
+        Recursion::OptionBox => quote! {
+            if ctx.recursion_ends(__recursive) {
+                None
+            } else {
+                Some(Box::new(#recurse))
+            }
+        },
+        Recursion::Box => quote! {
+            if ctx.recursion_ends(__recursive) {
+                return Err(format!(
+                    "Recursion through required field '{}' reached its end; use Option<Box<_>> or Vec to stop it",
+                    ctx.field_path(#field_name_str)
+                ));
+            } else {
+                Box::new(#recurse)
+            }
+        },
+        Recursion::Vec => quote! {
+            if ctx.recursion_ends(__recursive) {
+                Vec::new()
+            } else {
+                let __len = __recursive.len(ctx.rng());
+                let mut __items = Vec::with_capacity(__len);
+                for _ in 0..__len {
+                    __items.push(#recurse);
+                }
+                __items
+            }
+        },
+    }
+}
+
+// Options given through `#[sample(...)]` on a field.
+#[derive(Default)]
+struct FieldAttrs {
+    regex: Option<syn::LitStr>,
+    max_repeat: Option<u32>,
+    compute: Option<syn::Path>,
+}
+
+fn parse_field_attrs(field: &syn::Field) -> syn::Result<FieldAttrs> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("sample")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[sample(...)]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("regex") => match nv.lit {
+                    Lit::Str(pattern) => {
+                        check_regex(&pattern)?;
+                        attrs.regex = Some(pattern);
+                    }
+                    lit => return Err(syn::Error::new_spanned(lit, "`regex` must be a string literal")),
+                },
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("max_repeat") => match nv.lit {
+                    Lit::Int(n) => match n.base10_parse()? {
+                        0 => return Err(syn::Error::new_spanned(n, "`max_repeat` must be a positive integer")),
+                        max_repeat => attrs.max_repeat = Some(max_repeat),
+                    },
+                    lit => return Err(syn::Error::new_spanned(lit, "`max_repeat` must be an integer")),
+                },
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("compute") => match nv.lit {
+                    Lit::Str(path) => attrs.compute = Some(path.parse()?),
+                    lit => return Err(syn::Error::new_spanned(lit, "`compute` must be a string literal")),
+                },
+                nested => return Err(syn::Error::new_spanned(nested, "unknown sample attribute")),
+            }
+        }
+    }
+    if attrs.compute.is_some() && attrs.regex.is_some() {
+        return Err(syn::Error::new_spanned(&field.ty, "`compute` cannot be combined with `regex`"));
+    }
+    if attrs.regex.is_some() && !is_string_field(&field.ty) {
+        return Err(syn::Error::new_spanned(&field.ty, "`regex` can only be used on String fields"));
+    }
+    Ok(attrs)
+}
+
+// Options given through `#[sample(...)]` on a struct: the source and parsed
+// expression of each `constraint`.
+fn parse_struct_attrs(attrs: &[syn::Attribute]) -> syn::Result<Vec<(syn::LitStr, syn::Expr)>> {
+    let mut constraints = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("sample")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[sample(...)]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("constraint") => match nv.lit {
+                    Lit::Str(source) => {
+                        let expr: syn::Expr = source.parse()?;
+                        constraints.push((source, expr));
+                    }
+                    lit => return Err(syn::Error::new_spanned(lit, "`constraint` must be a string literal")),
+                },
+                nested => return Err(syn::Error::new_spanned(nested, "unknown sample attribute")),
+            }
+        }
+    }
+    Ok(constraints)
+}
+
+// Reject patterns the runtime generator would refuse, so mistakes surface at
+// compile time instead of on the first sample. Like `samplify_rs::pattern`,
+// anchors at the start and end of the pattern are dropped first.
+#[cfg(feature = "regex")]
+fn check_regex(pattern: &syn::LitStr) -> syn::Result<()> {
+    let invalid = |e: &dyn std::fmt::Display| syn::Error::new_spanned(pattern, format!("invalid regex: {}", e));
+    let hir = regex_syntax::Parser::new().parse(&pattern.value()).map_err(|e| invalid(&e))?;
+    rand_regex::Regex::with_hir(strip_anchors(hir, true, true), 1).map(|_| ()).map_err(|e| invalid(&e))
+}
+
+#[cfg(feature = "regex")]
+fn strip_anchors(hir: regex_syntax::hir::Hir, start: bool, end: bool) -> regex_syntax::hir::Hir {
+    use regex_syntax::hir::{Capture, Hir, HirKind, Look};
+    match hir.into_kind() {
+        HirKind::Look(Look::Start) if start => Hir::empty(),
+        HirKind::Look(Look::End) if end => Hir::empty(),
+        HirKind::Concat(mut subs) => {
+            if start {
+                let leading = subs.iter().take_while(|sub| matches!(sub.kind(), HirKind::Look(Look::Start))).count();
+                subs.drain(..leading);
+            }
+            while end && matches!(subs.last().map(Hir::kind), Some(HirKind::Look(Look::End))) {
+                subs.pop();
+            }
+            let last = subs.len().saturating_sub(1);
+            Hir::concat(subs.into_iter().enumerate().map(|(i, sub)| strip_anchors(sub, start && i == 0, end && i == last)).collect())
+        }
+        HirKind::Alternation(subs) => Hir::alternation(subs.into_iter().map(|sub| strip_anchors(sub, start, end)).collect()),
+        HirKind::Capture(cap) => Hir::capture(Capture { sub: Box::new(strip_anchors(*cap.sub, start, end)), ..cap }),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Repetition(rep) => Hir::repetition(rep),
+        HirKind::Class(class) => Hir::class(class),
+        HirKind::Literal(literal) => Hir::literal(literal.0),
+        HirKind::Empty => Hir::empty(),
+    }
+}
+
+#[cfg(not(feature = "regex"))]
+fn check_regex(pattern: &syn::LitStr) -> syn::Result<()> {
+    Err(syn::Error::new_spanned(pattern, "`regex` requires the `regex` feature of samplify-rs"))
+}
+
+// Helper functions to identify types.
+
+// Name under which presets for a field's type are looked up: the last path
+// segment, looking through `Option` and `Box`.
+fn preset_type_name(ty: &Type) -> String {
+    if is_option(ty) || is_box(ty) {
+        return preset_type_name(&get_inner_type(ty));
+    }
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().unwrap().ident.to_string(),
+        _ => String::new(),
+    }
+}
+
+// Primitive and decimal fields, possibly wrapped in `Option` or `Box`, whose
+// sampled values can be referenced by sibling fields.
+fn is_recordable(ty: &Type) -> bool {
+    if is_option(ty) || is_box(ty) {
+        return is_recordable(&get_inner_type(ty));
+    }
+    is_primitive(ty) || is_decimal(ty)
+}
+
+// `String`, possibly wrapped in `Option` or `Box`.
+fn is_string_field(ty: &Type) -> bool {
+    if is_option(ty) || is_box(ty) {
+        return is_string_field(&get_inner_type(ty));
+    }
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().unwrap().ident == "String",
+        _ => false,
+    }
+}
+
+// `Self` or the name of the type being derived.
+fn is_self_type(ty: &Type, self_name: &syn::Ident) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            type_path.qself.is_none() && (type_path.path.is_ident("Self") || type_path.path.is_ident(self_name))
+        }
+        _ => false,
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().unwrap().ident == "Option",
+        _ => false,
+    }
+}
+
+fn is_vec(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().unwrap().ident == "Vec",
+        _ => false,
+    }
+}
+
+fn get_inner_type(ty: &Type) -> Type {
+    match ty {
+        Type::Path(type_path) => {
+            if let syn::PathArguments::AngleBracketed(args) = &type_path.path.segments.last().unwrap().arguments {
+                if let Some(syn::GenericArgument::Type(inner_type)) = args.args.first() {
+                    inner_type.clone()
+                } else {
+                    panic!("Expected a type argument");
+                }
+            } else {
+                panic!("Expected angle bracketed arguments");
+            }
+        }
+        _ => panic!("Expected a type path"),
+    }
+}
+
+fn is_primitive(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            let ident = &type_path.path.segments.last().unwrap().ident;
+            ["f64", "f32", "i32", "i64", "u32", "u64", "usize", "isize", "String", "bool"].contains(&ident.to_string().as_str())
+        }
+        _ => false,
+    }
+}
+
+// Decimal types are only recognised when the matching feature is enabled, so
+// that a user-defined `Decimal` struct still goes through `sample_with_config`.
+fn is_decimal(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            let ident = &type_path.path.segments.last().unwrap().ident;
+            (cfg!(feature = "rust_decimal") && ident == "Decimal") || (cfg!(feature = "bigdecimal") && ident == "BigDecimal")
+        }
+        _ => false,
+    }
+}
+
+fn is_box(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.path.segments.last().unwrap().ident == "Box",
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_attrs(field: syn::Field) -> Result<Option<String>, String> {
+        parse_field_attrs(&field)
+            .map(|attrs| attrs.compute.map(|path| quote!(#path).to_string()))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn compute_attributes_name_a_function() {
+        let field: syn::FieldsNamed = syn::parse_quote!({
+            #[sample(compute = "totals::sum")]
+            total: u32
+        });
+        assert_eq!(field_attrs(field.named[0].clone()), Ok(Some("totals :: sum".to_string())));
+        let field: syn::FieldsNamed = syn::parse_quote!({
+            #[sample(compute = 3)]
+            total: u32
+        });
+        assert_eq!(field_attrs(field.named[0].clone()).unwrap_err(), "`compute` must be a string literal");
+        let field: syn::FieldsNamed = syn::parse_quote!({
+            #[sample(compute = "not a path")]
+            total: u32
+        });
+        assert!(field_attrs(field.named[0].clone()).is_err());
+    }
+
+    #[test]
+    fn compute_is_only_supported_on_struct_fields() {
+        let fields: syn::FieldsNamed = syn::parse_quote!({
+            amount: u32,
+            #[sample(compute = "total")]
+            total: u32
+        });
+        let fields: Vec<(String, &syn::Field)> = fields.named.iter().map(|field| (field.ident.as_ref().unwrap().to_string(), field)).collect();
+        let error = generate_variant_bindings(&fields).map(|_| ()).unwrap_err();
+        assert_eq!(error.to_string(), "`compute` is only supported on struct fields");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex_attributes_are_checked_at_compile_time() {
+        let check = |pattern: &str| check_regex(&syn::LitStr::new(pattern, proc_macro2::Span::call_site())).map_err(|e| e.to_string());
+        assert_eq!(check("^[A-Z]{2}$"), Ok(()));
+        assert_eq!(check(r"\A\d{4}\z|^x$"), Ok(()));
+        assert!(check("[A-Z").unwrap_err().starts_with("invalid regex: "));
+        assert_eq!(check("a^b").unwrap_err(), "invalid regex: anchor is not supported");
+        assert_eq!(check(r"\bword").unwrap_err(), "invalid regex: anchor is not supported");
+    }
+
+    #[cfg(not(feature = "regex"))]
+    #[test]
+    fn regex_attributes_need_the_feature() {
+        let error = check_regex(&syn::LitStr::new("[a-z]", proc_macro2::Span::call_site())).unwrap_err();
+        assert_eq!(error.to_string(), "`regex` requires the `regex` feature of samplify-rs");
+    }
+}
@@ -0,0 +1,128 @@
+//! Streams of samples drawn with one context.
+
+use serde_json::{Map, Value};
+
+use crate::{SampleContext, Sampleable, Sampler};
+
+/// An endless iterator of samples sharing one [`SampleContext`], returned by
+/// [`Sampleable::sample_iter`].
+///
+/// The config is compiled before the first sample. If it is invalid, the
+/// iterator yields that error once and then ends; a failure while sampling
+/// (such as an exhausted unique field) is yielded without ending it.
+#[derive(Debug)]
+pub struct SampleIter<T> {
+    sampler: Result<Sampler<T>, Option<String>>,
+    ctx: SampleContext,
+}
+
+impl<T: Sampleable> SampleIter<T> {
+    /// Samples from `config` with `ctx`, for example a context holding
+    /// datasets or presets.
+    pub fn new(config: &Map<String, Value>, ctx: SampleContext) -> Self {
+        let sampler = Sampler::new(config, &ctx).map_err(Some);
+        SampleIter { sampler, ctx }
+    }
+
+    /// Samples with an already compiled `sampler`.
+    pub fn from_sampler(sampler: Sampler<T>, ctx: SampleContext) -> Self {
+        SampleIter { sampler: Ok(sampler), ctx }
+    }
+
+    pub(crate) fn failed(error: String) -> Self {
+        SampleIter { sampler: Err(Some(error)), ctx: SampleContext::new() }
+    }
+
+    /// The context shared by the samples, e.g. to read its unique values.
+    pub fn context(&self) -> &SampleContext {
+        &self.ctx
+    }
+
+    /// Gives back the context, to register the samples as a dataset or to
+    /// keep sampling with the same state.
+    pub fn into_context(self) -> SampleContext {
+        self.ctx
+    }
+}
+
+impl<T: Sampleable> Iterator for SampleIter<T> {
+    type Item = Result<T, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.sampler {
+            Ok(sampler) => Some(sampler.sample_with_context(&mut self.ctx)),
+            Err(error) => error.take().map(Err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Account {
+        id: u32,
+        name: String,
+    }
+
+    fn config(id: Value) -> Map<String, Value> {
+        json!({ "id": id, "name": { "len": [4, 12], "charset": "alphanumeric" }, "$unique": ["id"] })
+            .as_object()
+            .unwrap()
+            .clone()
+    }
+
+    fn accounts(seed: u64, n: usize) -> Vec<Account> {
+        Account::sample_iter(&config(json!([1, 1000])), StdRng::seed_from_u64(seed))
+            .take(n)
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn seeded_iterators_repeat_their_samples() {
+        assert_eq!(accounts(7, 50), accounts(7, 50));
+        assert_ne!(accounts(7, 50), accounts(8, 50));
+        // Taking fewer samples gives a prefix of the same stream.
+        assert_eq!(accounts(7, 10), accounts(7, 50)[..10]);
+    }
+
+    #[test]
+    fn invalid_configs_fail_once_before_sampling() {
+        let mut samples = Account::sample_iter(&config(json!([5, 5])), StdRng::seed_from_u64(1));
+        assert_eq!(samples.next(), Some(Err("Range for field 'id' is empty".to_string())));
+        assert_eq!(samples.next(), None);
+        let error = Account::sample_many(&config(json!([5, 5])), 1000).unwrap_err();
+        assert_eq!(error, "Range for field 'id' is empty");
+    }
+
+    #[test]
+    fn sampling_failures_do_not_end_the_stream() {
+        let mut samples = Account::sample_iter(&config(json!([1, 3])), StdRng::seed_from_u64(1));
+        let ids: Vec<u32> = samples.by_ref().take(2).map(|account| account.unwrap().id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(samples.context().unique_values().count("id"), 2);
+        for _ in 0..2 {
+            let error = samples.next().unwrap().unwrap_err();
+            assert!(error.starts_with("Could not find a unique value for 'id'"), "{}", error);
+        }
+        let ctx = samples.into_context();
+        assert_eq!(ctx.unique_values().count("id"), 2);
+    }
+
+    #[test]
+    fn compiled_samplers_stream_with_a_given_context() {
+        let sampler = Account::compile_sampler(&config(json!([1, 1000]))).unwrap();
+        let streamed: Vec<Account> = SampleIter::from_sampler(sampler, SampleContext::new().with_seed(3))
+            .take(20)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut ctx = SampleContext::new().with_seed(3);
+        assert_eq!(streamed, Account::sample_many_with_context(&config(json!([1, 1000])), 20, &mut ctx).unwrap());
+    }
+}
@@ -0,0 +1,518 @@
+//! Building one config from several: base files, shared sub-configs, named
+//! profiles and overrides.
+//!
+//! A [`ConfigLoader`] reads a config file (see [`load`]) and
+//! resolves these keys:
+//!
+//! - `"$extends": "base.yaml"` (or a list of files) at the top level starts
+//!   from the named files, relative to the extending file, and merges the
+//!   rest of the file over them.
+//! - `{"$ref": "#/$defs/address"}` anywhere is replaced by the config the
+//!   JSON pointer points at. The target may be in another file
+//!   (`"address.json"` or `"shared.yaml#/address"`). Keys next to `$ref`
+//!   replace the keys of the same name in the target. Reusable configs
+//!   usually live under a top-level `$defs` object.
+//! - `"$profiles": {"eu": {...}}` at the top level holds named partial
+//!   configs, merged over the config when selected with
+//!   [`ConfigLoader::with_profile`].
+//!
+//! Merging follows the type being sampled: the configs of structs, enums and
+//! their `variant_data` entries merge key by key, while the config of a field
+//! (a range, a list of choices, an options object) replaces the one it
+//! overrides. Objects of types implementing `Sampleable` by hand merge key by
+//! key.
+//!
+//! Overrides set with [`ConfigLoader::with_override`], or read from
+//! environment variables and `key.path=value` arguments (see
+//! [`overrides`]), are applied last. Each replaces the
+//! value at a dot-separated path and is checked against the type. `$defs`,
+//! `$profiles` and `$extends` are removed from the result, which can be
+//! passed to `sample_with_config` or any other sampling function.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::load;
+use crate::overrides::{self, Override};
+use crate::shape::{FieldKind, FieldShape, Shape};
+use crate::strict::did_you_mean;
+use crate::Sampleable;
+
+/// Reads configs, selecting profiles and applying overrides.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    profiles: Vec<String>,
+    overrides: Vec<Override>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        ConfigLoader::default()
+    }
+
+    /// Merges the profile `name` of the `$profiles` of the config. Profiles
+    /// are merged in the order they are selected.
+    pub fn with_profile(mut self, name: &str) -> Self {
+        self.profiles.push(name.to_string());
+        self
+    }
+
+    /// Replaces the value at `path`, such as `"address.city"` or
+    /// `"status.variant_data.Suspended.reason"`, after merging. Objects
+    /// missing along the path are created; numbers index into arrays. The
+    /// field changed must still compile.
+    pub fn with_override(mut self, path: &str, value: Value) -> Self {
+        self.overrides.push(Override::new(path, value));
+        self
+    }
+
+    /// Adds the overrides in the environment variables starting with
+    /// `prefix` and `__`, such as `SAMPLIFY__user__age=[30,40]` for the
+    /// prefix `SAMPLIFY`.
+    pub fn with_env(mut self, prefix: &str) -> Self {
+        self.overrides.extend(Override::from_env(prefix));
+        self
+    }
+
+    /// Adds overrides written as `key.path=value`, such as command-line
+    /// arguments.
+    pub fn with_args<S: AsRef<str>>(mut self, args: impl IntoIterator<Item = S>) -> Result<Self, String> {
+        for arg in args {
+            self.overrides.push(Override::parse(arg.as_ref())?);
+        }
+        Ok(self)
+    }
+
+    /// Reads the config of a `T` from the file at `path` and resolves it.
+    pub fn load<T: Sampleable>(&self, path: impl AsRef<Path>) -> Result<Map<String, Value>, String> {
+        let path = path.as_ref();
+        let mut files = Vec::new();
+        let config = read_extended(path, &T::config_shape(), &mut files)?;
+        self.finish(&T::config_shape(), config, &path.display().to_string(), base_dir(path))
+    }
+
+    /// Resolves a config of a `T` that was read in code. `$extends` and
+    /// `$ref` paths are relative to the working directory.
+    pub fn compose<T: Sampleable>(&self, config: Map<String, Value>) -> Result<Map<String, Value>, String> {
+        let shape = T::config_shape();
+        let mut files = Vec::new();
+        let config = extend(config, &shape, Path::new(""), &mut files)?;
+        self.finish(&shape, config, "the config", PathBuf::new())
+    }
+
+    fn finish(&self, shape: &Shape, mut config: Map<String, Value>, origin: &str, dir: PathBuf) -> Result<Map<String, Value>, String> {
+        self.merge_profiles(shape, &mut config, origin)?;
+        let root = Value::Object(config);
+        let mut resolved = root.clone();
+        resolve_refs(&mut resolved, &Document { root: &root, origin, dir }, &mut Vec::new())?;
+        let mut config = match resolved {
+            Value::Object(config) => config,
+            _ => return Err(format!("The '$ref' at the top level of {} must point at an object", origin)),
+        };
+        config.remove("$defs");
+        overrides::apply_all(&self.overrides, shape, &mut config)?;
+        Ok(config)
+    }
+
+    fn merge_profiles(&self, shape: &Shape, config: &mut Map<String, Value>, origin: &str) -> Result<(), String> {
+        let profiles = match config.remove("$profiles") {
+            Some(Value::Object(profiles)) => profiles,
+            Some(_) => return Err(format!("'$profiles' in {} must be an object of configs", origin)),
+            None => Map::new(),
+        };
+        for name in &self.profiles {
+            match profiles.get(name) {
+                Some(Value::Object(profile)) => merge(shape, config, profile.clone()),
+                Some(_) => return Err(format!("Profile '{}' in {} must be an object", name, origin)),
+                None => {
+                    return Err(match did_you_mean(name, profiles.keys().map(String::as_str)) {
+                        Some(suggestion) => format!("Unknown profile '{}' in {}; did you mean '{}'?", name, origin, suggestion),
+                        None => format!("Unknown profile '{}' in {}", name, origin),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn base_dir(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+// Reads the file at `path` with its `$extends` merged in. `files` holds the
+// files being read, to report cycles.
+fn read_extended(path: &Path, shape: &Shape, files: &mut Vec<PathBuf>) -> Result<Map<String, Value>, String> {
+    let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if files.contains(&key) {
+        return Err(format!("'{}' extends itself through '$extends'", path.display()));
+    }
+    let config = load::from_path(path)?;
+    files.push(key);
+    let config = extend(config, shape, &base_dir(path), files);
+    files.pop();
+    config
+}
+
+// Merges `config` over the files its `$extends` names, relative to `dir`.
+fn extend(mut config: Map<String, Value>, shape: &Shape, dir: &Path, files: &mut Vec<PathBuf>) -> Result<Map<String, Value>, String> {
+    let bases = match config.remove("$extends") {
+        None => return Ok(config),
+        Some(Value::String(base)) => vec![base],
+        Some(Value::Array(bases)) => bases
+            .into_iter()
+            .map(|base| match base {
+                Value::String(base) => Ok(base),
+                _ => Err("'$extends' must be a file name or a list of file names".to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("'$extends' must be a file name or a list of file names".to_string()),
+    };
+    let mut merged = Map::new();
+    for base in bases {
+        let base = read_extended(&dir.join(base), shape, files)?;
+        merge_file(shape, &mut merged, base);
+    }
+    merge_file(shape, &mut merged, config);
+    Ok(merged)
+}
+
+// Merges a file over the files before it. Entries of `$defs` replace the
+// entries of the same name, and profiles merge as configs of `shape`.
+fn merge_file(shape: &Shape, merged: &mut Map<String, Value>, mut config: Map<String, Value>) {
+    for key in ["$defs", "$profiles"] {
+        let overlay = match (merged.get(key), config.get(key)) {
+            (Some(Value::Object(_)), Some(Value::Object(_))) => config.remove(key),
+            _ => None,
+        };
+        if let (Some(Value::Object(base)), Some(Value::Object(overlay))) = (merged.get_mut(key), overlay) {
+            for (name, value) in overlay {
+                match (key, base.get_mut(&name), value) {
+                    ("$profiles", Some(Value::Object(base)), Value::Object(overlay)) => merge(shape, base, overlay),
+                    (_, _, value) => {
+                        base.insert(name, value);
+                    }
+                }
+            }
+        }
+    }
+    merge(shape, merged, config);
+}
+
+/// Merges `overlay` into `base`, key by key for the configs of structs and
+/// enums and replacing the configs of fields.
+pub fn merge(shape: &Shape, base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    match shape {
+        Shape::Struct(shape) => merge_fields(&shape.fields, base, overlay),
+        Shape::Enum(shape) => {
+            for (key, value) in overlay {
+                match (key.as_str(), base.get_mut(&key), value) {
+                    ("variant_data", Some(Value::Object(base_data)), Value::Object(data)) => {
+                        for (name, value) in data {
+                            let fields = shape.variants.iter().find(|variant| variant.name == name).and_then(|variant| variant.fields.as_ref());
+                            match (fields, base_data.get_mut(&name), value) {
+                                (Some(fields), Some(Value::Object(base)), Value::Object(overlay)) => merge_fields(fields, base, overlay),
+                                (_, _, value) => {
+                                    base_data.insert(name, value);
+                                }
+                            }
+                        }
+                    }
+                    (_, _, value) => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        Shape::Opaque => {
+            for (key, value) in overlay {
+                match (base.get_mut(&key), value) {
+                    (Some(Value::Object(base)), Value::Object(overlay)) => merge(&Shape::Opaque, base, overlay),
+                    (_, value) => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn merge_fields(fields: &[FieldShape], base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    for (key, value) in overlay {
+        let nested = fields.iter().find(|field| field.name == key && !field.recursive).and_then(|field| match field.kind {
+            FieldKind::Nested(shape) => Some(shape()),
+            _ => None,
+        });
+        match (nested, base.get_mut(&key), value) {
+            (Some(shape), Some(Value::Object(base)), Value::Object(overlay)) => merge(&shape, base, overlay),
+            (_, _, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// A config that `$ref` pointers without a file name point into.
+struct Document<'a> {
+    root: &'a Value,
+    origin: &'a str,
+    dir: PathBuf,
+}
+
+// Replaces every `$ref` object in `value`. `targets` holds the references
+// being resolved, to report cycles.
+fn resolve_refs(value: &mut Value, document: &Document, targets: &mut Vec<String>) -> Result<(), String> {
+    match value {
+        Value::Object(map) => match map.remove("$ref") {
+            Some(Value::String(target)) => {
+                let mut resolved = lookup(&target, document, targets)?;
+                for sibling in map.values_mut() {
+                    resolve_refs(sibling, document, targets)?;
+                }
+                if !map.is_empty() {
+                    match &mut resolved {
+                        Value::Object(resolved) => resolved.extend(std::mem::take(map)),
+                        _ => return Err(format!("'$ref' '{}' in {} has other keys but does not point at an object", target, document.origin)),
+                    }
+                }
+                *value = resolved;
+            }
+            Some(_) => return Err(format!("'$ref' in {} must be a string", document.origin)),
+            None => {
+                for item in map.values_mut() {
+                    resolve_refs(item, document, targets)?;
+                }
+            }
+        },
+        Value::Array(items) => {
+            for item in items {
+                resolve_refs(item, document, targets)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// The resolved value `target` points at.
+fn lookup(target: &str, document: &Document, targets: &mut Vec<String>) -> Result<Value, String> {
+    let (file, pointer) = target.split_once('#').unwrap_or((target, ""));
+    let key = match file {
+        "" => format!("{}#{}", document.origin, pointer),
+        file => format!("{}#{}", document.dir.join(file).display(), pointer),
+    };
+    if targets.contains(&key) {
+        return Err(format!("'$ref' '{}' in {} refers to itself", target, document.origin));
+    }
+    targets.push(key);
+    let resolved = if file.is_empty() {
+        find(document.root, pointer, target, document.origin, document.origin).and_then(|mut value| {
+            resolve_refs(&mut value, document, targets)?;
+            Ok(value)
+        })
+    } else {
+        let path = document.dir.join(file);
+        let mut files = Vec::new();
+        let root = Value::Object(read_extended(&path, &Shape::Opaque, &mut files)?);
+        let origin = path.display().to_string();
+        let other = Document { root: &root, origin: &origin, dir: base_dir(&path) };
+        find(&root, pointer, target, document.origin, &origin).and_then(|mut value| {
+            resolve_refs(&mut value, &other, targets)?;
+            Ok(value)
+        })
+    };
+    targets.pop();
+    resolved
+}
+
+// The value at `pointer` of `root`, read from `file` for the `$ref` in
+// `origin`.
+fn find(root: &Value, pointer: &str, target: &str, origin: &str, file: &str) -> Result<Value, String> {
+    root.pointer(pointer)
+        .cloned()
+        .ok_or_else(|| format!("'$ref' '{}' in {} points at nothing: {} has no value at '{}'", target, origin, file, pointer))
+}
+
+/// Replaces the value at the dot-separated `path` of `config` with `value`.
+pub fn set_path(config: &mut Map<String, Value>, path: &str, value: Value) -> Result<(), String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(format!("Invalid config path '{}'", path));
+    }
+    let mut root = Value::Object(std::mem::take(config));
+    let result = set_at(&mut root, &segments, 0, value);
+    if let Value::Object(root) = root {
+        *config = root;
+    }
+    result
+}
+
+// Sets the value at `segments[depth..]` below `target`, which is at
+// `segments[..depth]`.
+fn set_at(target: &mut Value, segments: &[&str], depth: usize, value: Value) -> Result<(), String> {
+    let segment = segments[depth];
+    let last = depth + 1 == segments.len();
+    let next = match target {
+        Value::Object(map) if last => {
+            map.insert(segment.to_string(), value);
+            return Ok(());
+        }
+        Value::Object(map) => map.entry(segment).or_insert_with(|| Value::Object(Map::new())),
+        Value::Array(items) => {
+            let index = segment.parse::<usize>().ok().filter(|index| *index < items.len()).ok_or_else(|| {
+                format!("Cannot set '{}': '{}' has no item {}", segments.join("."), segments[..depth].join("."), segment)
+            })?;
+            &mut items[index]
+        }
+        _ => {
+            return Err(format!(
+                "Cannot set '{}': '{}' is not an object",
+                segments.join("."),
+                segments[..depth].join(".")
+            ))
+        }
+    };
+    if last {
+        *next = value;
+        Ok(())
+    } else {
+        set_at(next, segments, depth + 1, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::shape::StructShape;
+
+    fn address() -> Shape {
+        Shape::Struct(StructShape {
+            name: "Address",
+            module: "tests",
+            fields: vec![FieldShape::required("street", FieldKind::String), FieldShape::required("city", FieldKind::String)],
+        })
+    }
+
+    fn customer() -> Shape {
+        Shape::Struct(StructShape {
+            name: "Customer",
+            module: "tests",
+            fields: vec![FieldShape::required("name", FieldKind::String), FieldShape::required("address", FieldKind::Nested(address))],
+        })
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    // A fresh directory holding `files`.
+    fn write_files(test: &str, files: &[(&str, Value)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("samplify-compose-{}-{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, config) in files {
+            fs::write(dir.join(name), config.to_string()).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn merge_follows_the_shape() {
+        let mut base = object(json!({ "name": ["Ann"], "address": { "street": ["Main St"], "city": ["Oslo"] } }));
+        merge(&customer(), &mut base, object(json!({ "name": { "len": 4 }, "address": { "city": ["Bergen"] } })));
+        assert_eq!(
+            Value::Object(base),
+            json!({ "name": { "len": 4 }, "address": { "street": ["Main St"], "city": ["Bergen"] } })
+        );
+    }
+
+    #[test]
+    fn field_configs_replace_each_other() {
+        let mut base = object(json!({ "name": { "len": 4, "charset": "hex" } }));
+        merge(&customer(), &mut base, object(json!({ "name": { "len": 8 } })));
+        assert_eq!(base["name"], json!({ "len": 8 }));
+    }
+
+    #[test]
+    fn extends_merges_the_base_files_in_order() {
+        let dir = write_files(
+            "extends",
+            &[
+                ("base.json", json!({ "name": ["Ann"], "address": { "street": ["Main St"], "city": ["Oslo"] }, "$defs": { "a": 1, "b": 2 } })),
+                ("eu.json", json!({ "$extends": "base.json", "address": { "city": ["Paris"] }, "$defs": { "b": 3 } })),
+                ("test.json", json!({ "$extends": ["eu.json"], "name": ["Bob"] })),
+            ],
+        );
+        let mut files = Vec::new();
+        let config = read_extended(&dir.join("test.json"), &customer(), &mut files).unwrap();
+        assert_eq!(
+            Value::Object(config),
+            json!({ "name": ["Bob"], "address": { "street": ["Main St"], "city": ["Paris"] }, "$defs": { "a": 1, "b": 3 } })
+        );
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn extends_reports_cycles() {
+        let dir = write_files("extends-cycle", &[("a.json", json!({ "$extends": "b.json" })), ("b.json", json!({ "$extends": "a.json" }))]);
+        let error = read_extended(&dir.join("a.json"), &customer(), &mut Vec::new()).unwrap_err();
+        assert!(error.ends_with("a.json' extends itself through '$extends'"), "{}", error);
+    }
+
+    #[test]
+    fn refs_are_replaced_and_siblings_override_the_target() {
+        let config = object(json!({
+            "$defs": { "address": { "street": ["Main St"], "city": ["Oslo"] }, "city": { "$ref": "#/$defs/address/city" } },
+            "name": ["Ann"],
+            "address": { "$ref": "#/$defs/address", "city": { "$ref": "#/$defs/city" } }
+        }));
+        let config = ConfigLoader::new().finish(&customer(), config, "the config", PathBuf::new()).unwrap();
+        assert_eq!(Value::Object(config), json!({ "name": ["Ann"], "address": { "street": ["Main St"], "city": ["Oslo"] } }));
+    }
+
+    #[test]
+    fn refs_report_cycles() {
+        let config = object(json!({ "$defs": { "a": { "$ref": "#/$defs/b" }, "b": { "$ref": "#/$defs/a" } }, "name": { "$ref": "#/$defs/a" } }));
+        let error = ConfigLoader::new().finish(&customer(), config, "the config", PathBuf::new()).unwrap_err();
+        assert_eq!(error, "'$ref' '#/$defs/b' in the config refers to itself");
+    }
+
+    #[test]
+    fn refs_into_other_files_report_the_file_looked_in() {
+        let dir = write_files(
+            "ref-file",
+            &[
+                ("shared.json", json!({ "address": { "street": ["Main St"], "city": ["Oslo"] } })),
+                ("main.json", json!({ "name": ["Ann"], "address": { "$ref": "shared.json#/adress" } })),
+            ],
+        );
+        let main = dir.join("main.json");
+        let shared = dir.join("shared.json");
+        let config = load::from_path(&main).unwrap();
+        let error = ConfigLoader::new().finish(&customer(), config, &main.display().to_string(), dir).unwrap_err();
+        assert_eq!(
+            error,
+            format!(
+                "'$ref' 'shared.json#/adress' in {} points at nothing: {} has no value at '/adress'",
+                main.display(),
+                shared.display()
+            )
+        );
+    }
+
+    #[test]
+    fn set_path_creates_objects_and_indexes_arrays() {
+        let mut config = object(json!({ "name": ["Ann", "Bob"] }));
+        set_path(&mut config, "name.1", json!("Eve")).unwrap();
+        set_path(&mut config, "address.city", json!(["Oslo"])).unwrap();
+        assert_eq!(Value::Object(config.clone()), json!({ "name": ["Ann", "Eve"], "address": { "city": ["Oslo"] } }));
+        assert_eq!(
+            set_path(&mut config, "name.2", json!("Max")).unwrap_err(),
+            "Cannot set 'name.2': 'name' has no item 2"
+        );
+    }
+}
@@ -0,0 +1,978 @@
+//! Typed configs generated by the derive.
+//!
+//! Besides `Sampleable`, `#[derive(Sampleable)]` emits a typed config struct
+//! with one optional field per config key of the type, plus the `$`
+//! directives, named [`ConfigOf<T>`]. It deserializes with
+//! `deny_unknown_fields`, so a misspelled key is an error instead of being
+//! ignored:
+//!
+//! ```
+//! # use samplify_rs::config::ConfigOf;
+//! # use samplify_rs::Sampleable;
+//! # #[derive(Sampleable)]
+//! # struct User { name: String, age: u32, email: String }
+//! let config: ConfigOf<User> = serde_json::from_str(r#"{
+//!     "name": ["Alice", "Bob"],
+//!     "age": [18, 65],
+//!     "email": { "template": "{name}.{int:1..1000}@example.com", "unique": true }
+//! }"#)?;
+//! let user = User::sample_with_config(&config)?;
+//! # assert!(serde_json::from_str::<ConfigOf<User>>(r#"{ "nmae": ["Alice"] }"#).is_err());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The generated types have hidden names that cannot clash with the types
+//! of the crate deriving `Sampleable`; refer to them through [`ConfigOf`]
+//! and, for enums, [`VariantOf`] and [`VariantDataOf`].
+//!
+//! Fields are typed with the configs below, which accept the same JSON as the
+//! runtime parsers and tell their forms apart by JSON type. Fields of other
+//! types hold a [`Nested`] config: the typed config of their derive, or the
+//! JSON object of a type implementing `Sampleable` by hand.
+//!
+//! A typed config compiles straight into a [`Plan`], without going through
+//! JSON, and the values are checked as they are for a JSON config (a
+//! required field left out, an empty range or an unknown fake provider
+//! fail as before).
+
+use std::fmt::{self, Debug};
+
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::ser::{self, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::dataset::RefSpec;
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+use crate::decimal::DecimalSpec;
+use crate::float::FloatSpec;
+use crate::integer::IntSpec;
+use crate::payments::{PaymentGenerator, PaymentOptions};
+use crate::plan::{self, Plan, Source};
+use crate::recursion::RecursiveSpec;
+use crate::sequence::SequenceSpec;
+use crate::shape::{FieldKind, FieldShape};
+use crate::string::{RandomStringSpec, StringSpec};
+use crate::template::Template;
+use crate::{SampleContext, Sampleable};
+
+/// A type with a typed config. Implemented by `#[derive(Sampleable)]`.
+pub trait Configurable: Sampleable {
+    type Config: ConfigSource<Self> + Serialize + DeserializeOwned + Default + Clone + Debug;
+}
+
+/// An enum with a typed config. Implemented by `#[derive(Sampleable)]`.
+pub trait ConfigurableEnum: Configurable {
+    /// The names of the variants, for the `variants` of the config.
+    type Variant;
+    /// The `variant_data` of the config.
+    type VariantData: Default;
+}
+
+/// The typed config generated for `T`.
+pub type ConfigOf<T> = <T as Configurable>::Config;
+
+/// The variants of enum `T`, as listed in the `variants` of its typed config.
+pub type VariantOf<T> = <T as ConfigurableEnum>::Variant;
+
+/// The `variant_data` of the typed config of enum `T`.
+pub type VariantDataOf<T> = <T as ConfigurableEnum>::VariantData;
+
+/// A config that `T` can be sampled from: a JSON map, or the typed config
+/// generated for `T`.
+pub trait ConfigSource<T> {
+    /// Compiles the config for `T`, filling in the presets of `ctx`.
+    fn compile(&self, ctx: &SampleContext) -> Result<Plan, String>;
+
+    /// Like `compile`, taking keys the config leaves out from `defaults`
+    /// first: the preset for the type of the field holding the config.
+    #[doc(hidden)]
+    fn compile_with_defaults(&self, defaults: &Map<String, Value>, ctx: &SampleContext) -> Result<Plan, String> {
+        let _ = defaults;
+        self.compile(ctx)
+    }
+}
+
+impl<T: Sampleable> ConfigSource<T> for Map<String, Value> {
+    fn compile(&self, ctx: &SampleContext) -> Result<Plan, String> {
+        Plan::compile(&T::config_shape(), self, ctx)
+    }
+
+    fn compile_with_defaults(&self, defaults: &Map<String, Value>, ctx: &SampleContext) -> Result<Plan, String> {
+        if defaults.keys().all(|key| self.contains_key(key)) {
+            return ConfigSource::<T>::compile(self, ctx);
+        }
+        let mut merged = self.clone();
+        for (key, value) in defaults {
+            merged.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        Plan::compile(&T::config_shape(), &merged, ctx)
+    }
+}
+
+/// The typed config of one field, compiled into the source of its values.
+pub trait FieldConfig {
+    /// Compiles the config of `field`.
+    fn compile(&self, field: &FieldShape, ctx: &SampleContext) -> Result<Source, String>;
+
+    /// Whether the config marks the field unique.
+    fn is_unique(&self) -> bool {
+        false
+    }
+
+    /// The sibling fields read while sampling the field, such as the
+    /// placeholders of a template.
+    fn dependencies(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// The config of a field whose type is sampled with its own config: the
+/// typed config generated for `T`, or a JSON object if `T` implements
+/// `Sampleable` by hand.
+pub struct Nested<T> {
+    config: Box<dyn NestedConfig<T>>,
+}
+
+trait NestedConfig<T>: ConfigSource<T> + Debug + Send + Sync {
+    fn clone_box(&self) -> Box<dyn NestedConfig<T>>;
+
+    fn to_value(&self) -> Result<Value, serde_json::Error>;
+}
+
+impl<T, C> NestedConfig<T> for C
+where
+    C: ConfigSource<T> + Serialize + Clone + Debug + Send + Sync + 'static,
+{
+    fn clone_box(&self) -> Box<dyn NestedConfig<T>> {
+        Box::new(self.clone())
+    }
+
+    fn to_value(&self) -> Result<Value, serde_json::Error> {
+        serde_json::to_value(self)
+    }
+}
+
+impl<T> Nested<T> {
+    /// Wraps `config`, usually `ConfigOf<T>`.
+    pub fn new<C>(config: C) -> Self
+    where
+        C: ConfigSource<T> + Serialize + Clone + Debug + Send + Sync + 'static,
+    {
+        Nested { config: Box::new(config) }
+    }
+}
+
+impl<T> Clone for Nested<T> {
+    fn clone(&self) -> Self {
+        Nested { config: self.config.clone_box() }
+    }
+}
+
+impl<T> Debug for Nested<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.config.fmt(f)
+    }
+}
+
+impl<T> Serialize for Nested<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.config.to_value().map_err(ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+impl<'de, T: Sampleable> Deserialize<'de> for Nested<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::nested_config(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl<T> ConfigSource<T> for Nested<T> {
+    fn compile(&self, ctx: &SampleContext) -> Result<Plan, String> {
+        self.config.compile(ctx)
+    }
+
+    fn compile_with_defaults(&self, defaults: &Map<String, Value>, ctx: &SampleContext) -> Result<Plan, String> {
+        self.config.compile_with_defaults(defaults, ctx)
+    }
+}
+
+impl<T> FieldConfig for Nested<T> {
+    fn compile(&self, field: &FieldShape, ctx: &SampleContext) -> Result<Source, String> {
+        let plan = match ctx.presets().for_type(field.type_name) {
+            Some(Value::Object(defaults)) => self.compile_with_defaults(defaults, ctx)?,
+            _ => ConfigSource::compile(self, ctx)?,
+        };
+        Ok(Source::Nested(Box::new(plan)))
+    }
+}
+
+impl<T> FieldConfig for Vec<Nested<T>> {
+    fn compile(&self, _field: &FieldShape, ctx: &SampleContext) -> Result<Source, String> {
+        self.iter().map(|item| ConfigSource::compile(item, ctx)).collect::<Result<_, _>>().map(Source::Items)
+    }
+}
+
+// Implements `Deserialize` for an enum whose forms are told apart by the JSON
+// type of the value. Unlike `#[serde(untagged)]`, errors inside the chosen
+// form (such as an unknown key) are reported as they are.
+macro_rules! deserialize_by_json_type {
+    ($name:ident$(<$param:ident>)?, $expected:literal, { $($pattern:pat => $variant:ident),* $(,)? }) => {
+        impl<'de $(, $param: DeserializeOwned)?> Deserialize<'de> for $name$(<$param>)? {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = Value::deserialize(deserializer)?;
+                match value {
+                    $($pattern => serde_json::from_value(value).map($name::$variant).map_err(de::Error::custom),)*
+                    _ => Err(de::Error::custom(concat!("expected ", $expected))),
+                }
+            }
+        }
+    };
+}
+
+/// A value or a list of values, such as the `country` of an IBAN generator.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+/// `cardinality` of a `ref`: `[min, max]`, where `max` may be `null`.
+pub type Cardinality = (u32, Option<u32>);
+
+/// Config of a `String` field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum StringConfig {
+    /// `"value"`
+    Literal(String),
+    /// `["a", "b"]`
+    Choices(Vec<String>),
+    /// `{"fake": "internet.email"}`, `{"len": [8, 16]}`, ...
+    Options(Box<StringOptions>),
+}
+
+deserialize_by_json_type!(StringConfig, "a string, an array or an object", {
+    Value::String(_) => Literal,
+    Value::Array(_) => Choices,
+    Value::Object(_) => Options,
+});
+
+/// The keys of a `String` field config object. Which ones apply depends on
+/// the generator; see [`StringSpec`](crate::string::StringSpec).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StringOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<SequenceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<OneOrMany<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<OneOrMany<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minor_units: Option<OneOrMany<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fake: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_repeat: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unicode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub len: Option<Length>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub case: Option<Case>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<bool>,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+}
+
+/// `len` of a random string: a fixed length or an inclusive `[min, max]`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Length {
+    Fixed(u64),
+    Range([u64; 2]),
+}
+
+deserialize_by_json_type!(Length, "an integer or an array [min, max]", {
+    Value::Number(_) => Fixed,
+    Value::Array(_) => Range,
+});
+
+/// `case` of a random string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+/// Config of an integer field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum IntConfig {
+    /// `[low, high]`, half-open.
+    Range([i64; 2]),
+    /// `{"sequence": true}` or `{"ref": "Customer.id"}`.
+    Options(IntOptions),
+}
+
+deserialize_by_json_type!(IntConfig, "an array [low, high] or an object", {
+    Value::Array(_) => Range,
+    Value::Object(_) => Options,
+});
+
+/// The keys of an integer field config object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IntOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<SequenceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<bool>,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+}
+
+/// `sequence` of an integer or `String` field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum SequenceConfig {
+    /// `true`: count from 1 in steps of 1.
+    Enabled(bool),
+    Options(SequenceOptions),
+}
+
+deserialize_by_json_type!(SequenceConfig, "true or an object", {
+    Value::Bool(_) => Enabled,
+    Value::Object(_) => Options,
+});
+
+/// The keys of a `sequence` object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SequenceOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u64>,
+}
+
+/// Config of a floating-point field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum FloatConfig {
+    /// `[low, high]`
+    Range([f64; 2]),
+    /// `{"range": [0, 100], "decimals": 2}`
+    Options(FloatOptions),
+}
+
+deserialize_by_json_type!(FloatConfig, "an array [low, high] or an object", {
+    Value::Array(_) => Range,
+    Value::Object(_) => Options,
+});
+
+/// The keys of a floating-point field config object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FloatOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<[f64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency_field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<bool>,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+}
+
+/// Config of a `bool` field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum BoolConfig {
+    Value(bool),
+    /// `{"ref": "Customer.active"}`
+    Options(BoolOptions),
+}
+
+deserialize_by_json_type!(BoolConfig, "a boolean or an object", {
+    Value::Bool(_) => Value,
+    Value::Object(_) => Options,
+});
+
+/// The keys of a `bool` field config object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoolOptions {
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+}
+
+/// A decimal bound, written as a string (`"0.01"`) to keep it exact, or as
+/// a number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DecimalBound {
+    Text(String),
+    Number(serde_json::Number),
+}
+
+/// Config of a decimal field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DecimalConfig {
+    /// `"12.50"`
+    Literal(DecimalBound),
+    /// `["0.01", "99.99"]`
+    Range([DecimalBound; 2]),
+    /// `{"range": [...], "scale": 2, "distribution": "log_uniform"}`
+    Options(DecimalOptions),
+}
+
+deserialize_by_json_type!(DecimalConfig, "a decimal, an array [low, high] or an object", {
+    Value::String(_) | Value::Number(_) => Literal,
+    Value::Array(_) => Range,
+    Value::Object(_) => Options,
+});
+
+/// The keys of a decimal field config object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DecimalOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<[DecimalBound; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<Distribution>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<DecimalBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std_dev: Option<DecimalBound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency_field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<bool>,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<Cardinality>,
+}
+
+/// `distribution` of a decimal range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    Uniform,
+    LogUniform,
+    Normal,
+}
+
+/// Config of a self-referential field: a `recursive` object, or the config
+/// `C` of the nested values written out by hand.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RecursiveConfig<C> {
+    Recursive(RecursiveOptions),
+    Config(C),
+}
+
+impl<'de, C: DeserializeOwned> Deserialize<'de> for RecursiveConfig<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        if value.get("recursive").is_some() {
+            serde_json::from_value(value).map(RecursiveConfig::Recursive).map_err(de::Error::custom)
+        } else {
+            serde_json::from_value(value).map(RecursiveConfig::Config).map_err(de::Error::custom)
+        }
+    }
+}
+
+/// `{"recursive": ..., "len": [min, max]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecursiveOptions {
+    pub recursive: Recursion,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub len: Option<[u64; 2]>,
+}
+
+/// The `recursive` entry: `true`, or limits on the recursion.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Recursion {
+    Enabled(bool),
+    Limits(RecursionLimits),
+}
+
+deserialize_by_json_type!(Recursion, "true or an object", {
+    Value::Bool(_) => Enabled,
+    Value::Object(_) => Limits,
+});
+
+/// The keys of a `recursive` object.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecursionLimits {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_depth: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_probability: Option<f64>,
+}
+
+impl<T: Clone> OneOrMany<T> {
+    /// The value or values as a list.
+    pub fn to_vec(&self) -> Vec<T> {
+        match self {
+            OneOrMany::One(value) => vec![value.clone()],
+            OneOrMany::Many(values) => values.clone(),
+        }
+    }
+}
+
+impl DecimalBound {
+    /// The bound as decimal text.
+    pub fn text(&self) -> String {
+        match self {
+            DecimalBound::Text(text) => text.clone(),
+            DecimalBound::Number(number) => number.to_string(),
+        }
+    }
+}
+
+impl SequenceConfig {
+    fn to_spec(&self, field_name: &str) -> Result<SequenceSpec, String> {
+        match self {
+            SequenceConfig::Enabled(true) => SequenceSpec::new(1, 1, String::new(), String::new(), 0, field_name),
+            SequenceConfig::Enabled(false) => Err(format!("Sequence for field '{}' must be true or an object", field_name)),
+            SequenceConfig::Options(options) => SequenceSpec::new(
+                options.start.unwrap_or(1),
+                options.step.unwrap_or(1),
+                options.prefix.clone().unwrap_or_default(),
+                options.suffix.clone().unwrap_or_default(),
+                options.width.unwrap_or(0),
+                field_name,
+            ),
+        }
+    }
+}
+
+impl StringOptions {
+    // Picks the generator by the same precedence as `StringSpec::parse`.
+    fn to_spec(&self, field_name: &str) -> Result<StringSpec, String> {
+        if let Some(sequence) = &self.sequence {
+            return sequence.to_spec(field_name).map(StringSpec::Sequence);
+        }
+        if let Some(generator) = &self.generator {
+            let options = PaymentOptions {
+                country: self.country.as_ref().map(OneOrMany::to_vec),
+                formatted: self.formatted,
+                length: self.length,
+                scheme: self.scheme.as_ref().map(OneOrMany::to_vec),
+                minor_units: self.minor_units.as_ref().map(OneOrMany::to_vec),
+            };
+            return PaymentGenerator::new(generator, options, field_name).map(StringSpec::Payment);
+        }
+        if let Some(template) = &self.template {
+            return StringSpec::template(template, field_name);
+        }
+        if let Some(fake) = &self.fake {
+            return StringSpec::fake(fake, self.locale.as_deref(), field_name);
+        }
+        if let Some(regex) = &self.regex {
+            return StringSpec::regex(regex, self.max_repeat, self.unicode.unwrap_or(false), field_name);
+        }
+        if self.len.is_some() || self.charset.is_some() {
+            let (min_len, max_len) = match self.len {
+                None => (8, 16),
+                Some(Length::Fixed(len)) => (len, len),
+                Some(Length::Range([min, max])) => (min, max),
+            };
+            return RandomStringSpec::new(min_len, max_len, self.charset.as_deref(), self.case, field_name).map(StringSpec::Random);
+        }
+        Err(format!("Configuration for '{}' does not name a string generator", field_name))
+    }
+}
+
+fn reference(reference: &Option<String>, cardinality: Option<Cardinality>, field_name: &str) -> Result<Option<Source>, String> {
+    match reference {
+        Some(reference) => RefSpec::new(reference, cardinality, field_name).map(|spec| Some(Source::Ref(spec))),
+        None => Ok(None),
+    }
+}
+
+impl FieldConfig for StringConfig {
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        let spec = match self {
+            StringConfig::Literal(value) => StringSpec::Literal(value.clone()),
+            StringConfig::Choices(values) if values.is_empty() => {
+                return Err(format!("Values array for field '{}' is empty", field.name))
+            }
+            StringConfig::Choices(values) => StringSpec::Choices(values.clone()),
+            StringConfig::Options(options) => match reference(&options.reference, options.cardinality, field.name)? {
+                Some(source) => return Ok(source),
+                None => options.to_spec(field.name)?,
+            },
+        };
+        Ok(Source::String(spec))
+    }
+
+    fn is_unique(&self) -> bool {
+        matches!(self, StringConfig::Options(options) if options.unique == Some(true))
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        match self {
+            StringConfig::Options(options) => match options.template.as_deref().map(Template::parse) {
+                Some(Ok(template)) => template.placeholders().map(str::to_string).collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl FieldConfig for IntConfig {
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        let spec = match self {
+            IntConfig::Range([start, end]) => IntSpec::range(*start, *end, field.name)?,
+            IntConfig::Options(options) => match (reference(&options.reference, options.cardinality, field.name)?, &options.sequence) {
+                (Some(source), _) => return Ok(source),
+                (None, Some(sequence)) => IntSpec::Sequence(sequence.to_spec(field.name)?),
+                (None, None) => return Err(format!("Configuration for field '{}' must be an array", field.name)),
+            },
+        };
+        if let FieldKind::Integer { min, max } = field.kind {
+            spec.check_bounds(min, max, field.name)?;
+        }
+        Ok(Source::Integer(spec))
+    }
+
+    fn is_unique(&self) -> bool {
+        matches!(self, IntConfig::Options(options) if options.unique == Some(true))
+    }
+}
+
+impl FieldConfig for FloatConfig {
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        let spec = match self {
+            FloatConfig::Range([low, high]) => FloatSpec::new(*low, *high, None, field.name)?,
+            FloatConfig::Options(options) => {
+                if let Some(source) = reference(&options.reference, options.cardinality, field.name)? {
+                    return Ok(source);
+                }
+                let [low, high] = options
+                    .range
+                    .ok_or_else(|| format!("Configuration for field '{}' is missing 'range'", field.name))?;
+                let decimals = FloatSpec::decimals(options.decimals, options.currency_field.clone(), field.name)?;
+                FloatSpec::new(low, high, decimals, field.name)?
+            }
+        };
+        if let FieldKind::Float { max } = field.kind {
+            spec.check_bounds(max, field.name)?;
+        }
+        Ok(Source::Float(spec))
+    }
+
+    fn is_unique(&self) -> bool {
+        matches!(self, FloatConfig::Options(options) if options.unique == Some(true))
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        match self {
+            FloatConfig::Options(options) => options.currency_field.iter().cloned().collect(),
+            FloatConfig::Range(_) => Vec::new(),
+        }
+    }
+}
+
+impl FieldConfig for BoolConfig {
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        match self {
+            BoolConfig::Value(value) => Ok(Source::Bool(*value)),
+            BoolConfig::Options(options) => reference(&options.reference, options.cardinality, field.name)?
+                .ok_or_else(|| format!("Configuration for '{}' must be a boolean", field.name)),
+        }
+    }
+}
+
+impl FieldConfig for DecimalConfig {
+    #[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        let spec = match self {
+            DecimalConfig::Literal(value) => DecimalSpec::range(&value.text(), &value.text(), None, field.name)?,
+            DecimalConfig::Range([low, high]) => DecimalSpec::range(&low.text(), &high.text(), None, field.name)?,
+            DecimalConfig::Options(options) => {
+                if let Some(source) = reference(&options.reference, options.cardinality, field.name)? {
+                    return Ok(source);
+                }
+                let [low, high] = options
+                    .range
+                    .as_ref()
+                    .ok_or_else(|| format!("Configuration for field '{}' is missing 'range'", field.name))?;
+                let currency = DecimalSpec::currency(options.scale, options.currency_field.clone(), field.name)?;
+                DecimalSpec::range(&low.text(), &high.text(), options.scale, field.name)?
+                    .with_distribution(
+                        options.distribution.unwrap_or(Distribution::Uniform),
+                        options.mean.as_ref().map(DecimalBound::text).as_deref(),
+                        options.std_dev.as_ref().map(DecimalBound::text).as_deref(),
+                        field.name,
+                    )?
+                    .with_currency(currency)
+            }
+        };
+        Ok(Source::Decimal(spec))
+    }
+
+    #[cfg(not(any(feature = "rust_decimal", feature = "bigdecimal")))]
+    fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+        Err(format!("Field '{}' needs the 'rust_decimal' or 'bigdecimal' feature", field.name))
+    }
+
+    fn is_unique(&self) -> bool {
+        matches!(self, DecimalConfig::Options(options) if options.unique == Some(true))
+    }
+
+    fn dependencies(&self) -> Vec<String> {
+        match self {
+            DecimalConfig::Options(options) => options.currency_field.iter().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// The values a `Vec` of primitives picks from.
+macro_rules! pick_config {
+    ($($item:ty),*) => {
+        $(impl FieldConfig for Vec<$item> {
+            fn compile(&self, field: &FieldShape, _ctx: &SampleContext) -> Result<Source, String> {
+                match &field.kind {
+                    FieldKind::List(item) => plan::compile_pick(item, self.iter().cloned().map(Value::from), field.name),
+                    _ => Err(format!("Configuration for '{}' must not be an array", field.name)),
+                }
+            }
+        })*
+    };
+}
+
+pick_config!(String, i64, f64, bool, Value);
+
+impl<C: FieldConfig> FieldConfig for RecursiveConfig<C> {
+    fn compile(&self, field: &FieldShape, ctx: &SampleContext) -> Result<Source, String> {
+        match self {
+            RecursiveConfig::Recursive(options) => {
+                let (max_depth, leaf_probability) = match &options.recursive {
+                    Recursion::Enabled(true) => (None, None),
+                    Recursion::Enabled(false) => {
+                        return Err(format!("'recursive' for field '{}' must be true or an object", field.name))
+                    }
+                    Recursion::Limits(limits) => (limits.max_depth, limits.leaf_probability),
+                };
+                RecursiveSpec::new(max_depth, leaf_probability, options.len, field.name).map(Source::Recursive)
+            }
+            RecursiveConfig::Config(config) => config.compile(field, ctx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::{json, Map, Value};
+
+    use super::*;
+    use crate::{Presets, SampleContext, Sampleable};
+
+    // A type of the user's crate that shares the name the typed config of
+    // `Customer` once had.
+    #[allow(dead_code)]
+    struct CustomerSampleConfig;
+
+    #[derive(Debug, PartialEq, Serialize, crate::Sampleable)]
+    enum Tier {
+        Basic,
+        Gold { discount: u32 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, crate::Sampleable)]
+    struct Customer {
+        id: i64,
+        code: String,
+        email: String,
+        balance: f64,
+        currency: String,
+        tier: Tier,
+        tags: Vec<String>,
+    }
+
+    // Implements `Sampleable` by hand and has no typed config of its own.
+    #[derive(Debug, PartialEq, Serialize)]
+    struct Celsius(i64);
+
+    impl Sampleable for Celsius {
+        fn sample_with_context(config: &Map<String, Value>, _ctx: &mut SampleContext) -> Result<Self, String> {
+            config.get("fixed").and_then(Value::as_i64).map(Celsius).ok_or_else(|| "'fixed' is missing".to_string())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, crate::Sampleable)]
+    struct Reading {
+        temperature: Celsius,
+        history: Vec<Celsius>,
+    }
+
+    #[derive(Debug, Serialize, crate::Sampleable)]
+    struct Amount {
+        ccy: String,
+        value: f64,
+    }
+
+    #[derive(Debug, Serialize, crate::Sampleable)]
+    struct Payment {
+        reference: String,
+        amount: Amount,
+    }
+
+    fn customer_config() -> Value {
+        json!({
+            "id": { "sequence": { "start": 100 } },
+            "code": { "template": "{currency}-{id}" },
+            "email": { "len": 8, "charset": "hex", "unique": true },
+            "balance": { "range": [0.0, 1000.0], "currency_field": "currency" },
+            "currency": { "generator": "currency", "minor_units": 0 },
+            "tier": { "variants": ["Gold"], "variant_data": { "Gold": { "discount": [5, 25] } } },
+            "tags": ["new", "vip"],
+            "$constraints": "id < 1000"
+        })
+    }
+
+    fn sample<T: Sampleable>(plan: &Plan, seed: u64, n: usize) -> Vec<T> {
+        let mut ctx = SampleContext::new().with_seed(seed);
+        (0..n).map(|_| T::sample_plan(plan, &mut ctx).unwrap()).collect()
+    }
+
+    #[test]
+    fn typed_configs_sample_like_their_json() {
+        let json = customer_config();
+        let typed: ConfigOf<Customer> = serde_json::from_value(json.clone()).unwrap();
+        let ctx = SampleContext::new();
+        let from_typed = ConfigSource::<Customer>::compile(&typed, &ctx).unwrap();
+        let from_json = ConfigSource::<Customer>::compile(json.as_object().unwrap(), &ctx).unwrap();
+
+        let customers: Vec<Customer> = sample(&from_typed, 7, 50);
+        assert_eq!(customers, sample::<Customer>(&from_json, 7, 50));
+        for customer in &customers {
+            assert_eq!(customer.code, format!("{}-{}", customer.currency, customer.id));
+            assert_eq!(customer.balance.fract(), 0.0);
+            assert!(matches!(customer.tier, Tier::Gold { discount: 5..=24 }));
+        }
+    }
+
+    #[test]
+    fn typed_configs_are_checked_like_json() {
+        let error = |config: Value| {
+            let typed: ConfigOf<Customer> = serde_json::from_value(config).unwrap();
+            ConfigSource::<Customer>::compile(&typed, &SampleContext::new()).unwrap_err()
+        };
+        let mut config = customer_config();
+        config["id"] = json!([5, 5]);
+        assert_eq!(error(config), "Range for field 'id' is empty");
+
+        let mut config = customer_config();
+        config["tier"] = json!({ "variants": ["Gold"] });
+        assert_eq!(error(config), "Configuration for variant 'Gold' is missing or invalid");
+
+        let mut config = customer_config();
+        config.as_object_mut().unwrap().remove("email");
+        assert_eq!(error(config), "Configuration for 'email' is missing");
+
+        let mut config = customer_config();
+        config["balance"] = json!({ "range": [0.0, 1.0], "decimals": 2, "currency_field": "currency" });
+        assert_eq!(error(config), "Field 'balance' sets both 'decimals' and 'currency_field'");
+    }
+
+    #[test]
+    fn typed_configs_reject_unknown_keys() {
+        let error = |path: &[&str], key: &str| {
+            let mut config = customer_config();
+            let mut target = &mut config;
+            for step in path {
+                target = &mut target[*step];
+            }
+            target[key] = json!(true);
+            serde_json::from_value::<ConfigOf<Customer>>(config).unwrap_err().to_string()
+        };
+        let message = error(&[], "emial");
+        assert!(message.starts_with("unknown field `emial`, expected one of "), "{}", message);
+        let message = error(&[], "$uniq");
+        assert!(message.starts_with("unknown field `$uniq`"), "{}", message);
+        let message = error(&["balance"], "decimal");
+        assert!(message.contains("unknown field `decimal`"), "{}", message);
+        let message = error(&["tier", "variant_data", "Gold"], "discont");
+        assert!(message.contains("unknown field `discont`"), "{}", message);
+        assert!(serde_json::from_value::<ConfigOf<Customer>>(customer_config()).is_ok());
+    }
+
+    #[test]
+    fn types_implementing_sampleable_by_hand_need_no_typed_config() {
+        let typed: ConfigOf<Reading> = serde_json::from_value(json!({
+            "temperature": { "fixed": 21 },
+            "history": [{ "fixed": 19 }, { "fixed": 20 }]
+        }))
+        .unwrap();
+        let reading = Reading::sample_with_config(&typed).unwrap();
+        assert_eq!(reading, Reading { temperature: Celsius(21), history: vec![Celsius(19), Celsius(20)] });
+        assert_eq!(
+            serde_json::to_value(&typed).unwrap()["history"],
+            json!([{ "fixed": 19 }, { "fixed": 20 }])
+        );
+    }
+
+    #[test]
+    fn presets_fill_what_typed_configs_leave_out() {
+        let presets = Presets::new()
+            .with_field("reference", json!({ "len": 6, "charset": "numeric" }))
+            .with_type("Amount", json!({ "ccy": "EUR", "value": [1.0, 2.0] }));
+        let ctx = SampleContext::new().with_presets(presets).with_seed(3);
+        let typed: ConfigOf<Payment> = serde_json::from_value(json!({ "amount": { "ccy": "JPY" } })).unwrap();
+        let plan = ConfigSource::<Payment>::compile(&typed, &ctx).unwrap();
+        for payment in sample::<Payment>(&plan, 3, 20) {
+            assert_eq!(payment.reference.len(), 6);
+            assert_eq!(payment.amount.ccy, "JPY");
+            assert!((1.0..2.0).contains(&payment.amount.value));
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! Cross-field constraints enforced by rejection sampling.
+//!
+//! A struct config may list constraints under `"$constraints"`:
+//!
+//! ```json
+//! {
+//!     "start_date": { "template": "2024-0{int:1..=9}-1{int:0..=9}" },
+//!     "end_date": { "template": "2024-0{int:1..=9}-1{int:0..=9}" },
+//!     "$constraints": ["end_date >= start_date", "settled_amount <= amount"],
+//!     "$max_attempts": 500
+//! }
+//! ```
+//!
+//! Each constraint compares a field with another field or a literal using
+//! `==`, `!=`, `<`, `<=`, `>` or `>=`; naming a field the struct does not
+//! have fails when the config is compiled. Values that parse as numbers compare
+//! numerically, other strings lexicographically (so ISO dates work). A
+//! constraint involving a `None` field is considered satisfied. The struct is
+//! resampled until every constraint holds, up to `$max_attempts` times
+//! ([`DEFAULT_MAX_ATTEMPTS`] by default). Unique values and sequence numbers
+//! drawn by a rejected attempt are given back. Operators inside quoted
+//! literals (`status == "a<b"`) are part of the literal.
+
+use std::cmp::Ordering;
+
+use serde_json::{Map, Value};
+
+use crate::fields::{slot_of, FieldValues, Scalar};
+use crate::strict::did_you_mean;
+
+/// Attempts made before giving up on a constrained struct.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 100;
+
+/// The constraints of one struct config.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    constraints: Vec<Constraint>,
+    max_attempts: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    source: String,
+    left: Operand,
+    op: Op,
+    right: Operand,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    /// A sibling field, read from `slot` once bound.
+    Field { name: String, slot: Option<usize> },
+    Literal(Scalar),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Constraints {
+    /// Reads `$constraints` and `$max_attempts` from a struct config.
+    pub fn parse(config: &Map<String, Value>) -> Result<Self, String> {
+        let sources: Vec<&str> = match config.get("$constraints") {
+            None => Vec::new(),
+            Some(Value::String(source)) => vec![source.as_str()],
+            Some(Value::Array(values)) => values
+                .iter()
+                .map(|v| v.as_str())
+                .collect::<Option<_>>()
+                .ok_or_else(|| "'$constraints' must contain only strings".to_string())?,
+            Some(_) => return Err("'$constraints' must be a string or an array of strings".to_string()),
+        };
+        let max_attempts = match config.get("$max_attempts") {
+            None => None,
+            Some(value) => match value.as_u64() {
+                Some(n) => Some(n.min(u64::from(u32::MAX)) as u32),
+                None => return Err("'$max_attempts' must be a positive integer".to_string()),
+            },
+        };
+        Self::new(sources, max_attempts)
+    }
+
+    /// Parses the constraint `sources`, to be met within `max_attempts`
+    /// samples ([`DEFAULT_MAX_ATTEMPTS`] if `None`).
+    pub fn new<'a>(sources: impl IntoIterator<Item = &'a str>, max_attempts: Option<u32>) -> Result<Self, String> {
+        if max_attempts == Some(0) {
+            return Err("'$max_attempts' must be a positive integer".to_string());
+        }
+        Ok(Constraints {
+            constraints: sources.into_iter().map(Constraint::parse).collect::<Result<_, _>>()?,
+            max_attempts: max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS),
+        })
+    }
+
+    /// Whether the config lists no constraints.
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// How many times the struct may be sampled.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts.max(1)
+    }
+
+    /// Binds the fields the constraints compare to their slots among
+    /// `names`, the fields of the struct in declaration order. A constraint
+    /// naming none of them is an error.
+    pub fn bind(&mut self, names: &[&str]) -> Result<(), String> {
+        for constraint in &mut self.constraints {
+            for operand in [&mut constraint.left, &mut constraint.right] {
+                if let Operand::Field { name, slot } = operand {
+                    *slot = slot_of(names, name);
+                    if slot.is_none() {
+                        let error = format!("Constraint '{}' refers to '{}', which is not a field of this struct", constraint.source, name);
+                        return Err(match did_you_mean(name, names.iter().copied()) {
+                            Some(suggestion) => format!("{}; did you mean '{}'?", error, suggestion),
+                            None => error,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The names of the fields the constraints compare.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.constraints
+            .iter()
+            .flat_map(|constraint| [&constraint.left, &constraint.right])
+            .filter_map(|operand| match operand {
+                Operand::Field { name, .. } => Some(name.as_str()),
+                Operand::Literal(_) => None,
+            })
+    }
+
+    /// Returns the first constraint that `values` violate, if any.
+    pub fn violated(&self, values: &FieldValues) -> Result<Option<&str>, String> {
+        for constraint in &self.constraints {
+            if !constraint.holds(values)? {
+                return Ok(Some(&constraint.source));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Constraint {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut comparison = None;
+        let mut quote = None;
+        let mut chars = source.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            match (quote, c) {
+                (Some(open), c) if c == open => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '=' | '!' | '<' | '>') => {
+                    let equals = chars.next_if(|(_, next)| *next == '=').is_some();
+                    let op = match (c, equals) {
+                        ('=', true) => Op::Eq,
+                        ('!', true) => Op::Ne,
+                        ('<', true) => Op::Le,
+                        ('>', true) => Op::Ge,
+                        ('<', false) => Op::Lt,
+                        ('>', false) => Op::Gt,
+                        _ => return Err(format!("Constraint '{}' has an invalid operator at '{}'", source, &source[index..])),
+                    };
+                    if comparison.is_some() {
+                        return Err(format!("Constraint '{}' must have exactly one comparison", source));
+                    }
+                    comparison = Some((index, op, if equals { 2 } else { 1 }));
+                }
+                (None, _) => {}
+            }
+        }
+        if quote.is_some() {
+            return Err(format!("Constraint '{}' has an unclosed quote", source));
+        }
+        let (index, op, len) =
+            comparison.ok_or_else(|| format!("Constraint '{}' needs a comparison such as 'end >= start'", source))?;
+        let left = Operand::parse(&source[..index], source)?;
+        let right = Operand::parse(&source[index + len..], source)?;
+        Ok(Constraint { source: source.to_string(), left, op, right })
+    }
+
+    fn holds(&self, values: &FieldValues) -> Result<bool, String> {
+        let left = self.left.resolve(values, &self.source)?;
+        let right = self.right.resolve(values, &self.source)?;
+        if *left == Scalar::Null || *right == Scalar::Null {
+            return Ok(true);
+        }
+        let ordering = compare(left, right)
+            .ok_or_else(|| format!("Constraint '{}' compares values of different kinds", self.source))?;
+        Ok(match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+        })
+    }
+}
+
+impl Operand {
+    fn parse(operand: &str, source: &str) -> Result<Self, String> {
+        let operand = operand.trim();
+        if operand.is_empty() {
+            return Err(format!("Constraint '{}' is missing an operand", source));
+        }
+        if let Some(text) = operand.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            return Ok(Operand::Literal(Scalar::String(text.to_string())));
+        }
+        if let Some(text) = operand.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+            return Ok(Operand::Literal(Scalar::String(text.to_string())));
+        }
+        if let Ok(value) = serde_json::from_str::<Value>(operand) {
+            if let Some(literal) = Scalar::from_value(&value).filter(|_| value.is_number() || value.is_boolean()) {
+                return Ok(Operand::Literal(literal));
+            }
+        }
+        let name = operand.strip_prefix("self.").unwrap_or(operand);
+        if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(format!("Constraint '{}' has an invalid operand '{}'", source, operand));
+        }
+        Ok(Operand::Field { name: name.to_string(), slot: None })
+    }
+
+    fn resolve<'a>(&'a self, values: &'a FieldValues, source: &str) -> Result<&'a Scalar, String> {
+        match self {
+            Operand::Literal(value) => Ok(value),
+            Operand::Field { name, slot } => slot.and_then(|slot| values.get(slot)).ok_or_else(|| {
+                format!("Constraint '{}' refers to '{}', which is not a primitive field of this struct", source, name)
+            }),
+        }
+    }
+}
+
+fn compare(left: &Scalar, right: &Scalar) -> Option<Ordering> {
+    if let (Some(left), Some(right)) = (left.as_number(), right.as_number()) {
+        return left.partial_cmp(&right);
+    }
+    match (left, right) {
+        (Scalar::String(left), Scalar::String(right)) => Some(left.cmp(right)),
+        (Scalar::Bool(left), Scalar::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::fields::FieldValue;
+    use crate::{SampleContext, Sampleable};
+
+    fn parse(source: &str) -> Result<(Operand, Op, Operand), String> {
+        Constraint::parse(source).map(|constraint| (constraint.left, constraint.op, constraint.right))
+    }
+
+    fn field(name: &str) -> Operand {
+        Operand::Field { name: name.to_string(), slot: None }
+    }
+
+    fn literal(value: Value) -> Operand {
+        Operand::Literal(Scalar::from_value(&value).unwrap())
+    }
+
+    #[test]
+    fn parse_reads_each_operator() {
+        for (source, op) in [
+            ("a == b", Op::Eq),
+            ("a != b", Op::Ne),
+            ("a < b", Op::Lt),
+            ("a <= b", Op::Le),
+            ("a > b", Op::Gt),
+            ("a>=b", Op::Ge),
+        ] {
+            assert_eq!(parse(source).unwrap(), (field("a"), op, field("b")), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_reads_literals_and_self_fields() {
+        assert_eq!(parse("self.end >= 10.5").unwrap(), (field("end"), Op::Ge, literal(json!(10.5))));
+        assert_eq!(parse("active == true").unwrap(), (field("active"), Op::Eq, literal(json!(true))));
+        assert_eq!(parse("'EUR' != currency").unwrap(), (literal(json!("EUR")), Op::Ne, field("currency")));
+    }
+
+    #[test]
+    fn parse_skips_operators_in_quotes() {
+        assert_eq!(parse("status == \"a<b\"").unwrap(), (field("status"), Op::Eq, literal(json!("a<b"))));
+        assert_eq!(parse("'x>=y' <= status").unwrap(), (literal(json!("x>=y")), Op::Le, field("status")));
+        assert_eq!(parse("note != \"it's\"").unwrap(), (field("note"), Op::Ne, literal(json!("it's"))));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_constraints() {
+        assert_eq!(parse("a b").unwrap_err(), "Constraint 'a b' needs a comparison such as 'end >= start'");
+        assert_eq!(parse("a = b").unwrap_err(), "Constraint 'a = b' has an invalid operator at '= b'");
+        assert_eq!(parse("a < b < c").unwrap_err(), "Constraint 'a < b < c' must have exactly one comparison");
+        assert_eq!(parse("a == \"b").unwrap_err(), "Constraint 'a == \"b' has an unclosed quote");
+        assert_eq!(parse(" >= b").unwrap_err(), "Constraint ' >= b' is missing an operand");
+        assert_eq!(parse("a-b >= c").unwrap_err(), "Constraint 'a-b >= c' has an invalid operand 'a-b'");
+    }
+
+    #[test]
+    fn constraints_compare_numbers_and_strings() {
+        let config = json!({ "$constraints": ["amount <= limit", "code > \"B\"", "end >= start"] });
+        let mut constraints = Constraints::parse(config.as_object().unwrap()).unwrap();
+        let names = ["amount", "limit", "code", "start", "end"];
+        constraints.bind(&names).unwrap();
+        let mut slots = [None, None, None, None, None];
+        let mut values = FieldValues::new(&names, &mut slots);
+        values.set(0, "9.50".to_string().to_scalar());
+        values.set(1, 10i64.to_scalar());
+        values.set(2, "C".to_string().to_scalar());
+        values.set(3, "2024-02-01".to_string().to_scalar());
+        values.set(4, None::<String>.to_scalar());
+        assert_eq!(constraints.violated(&values).unwrap(), None);
+        values.set(0, "10.5".to_string().to_scalar());
+        assert_eq!(constraints.violated(&values).unwrap(), Some("amount <= limit"));
+    }
+
+    #[derive(Debug, serde::Serialize, serde::Deserialize, crate::Sampleable)]
+    struct Ticket {
+        id: u32,
+        lane: u32,
+        number: String,
+    }
+
+    // A rejected attempt gives back its unique values and sequence numbers,
+    // so every value of a small domain can still be drawn.
+    #[test]
+    fn rejected_attempts_release_unique_values_and_sequence_numbers() {
+        let config = json!({
+            "id": [0, 8],
+            "lane": [0, 4],
+            "number": { "sequence": { "prefix": "T" } },
+            "$unique": ["id"],
+            "$constraints": ["lane == 0"],
+            "$max_attempts": 1000
+        });
+        let config = config.as_object().unwrap();
+        let check = |tickets: Vec<Ticket>, ctx: &SampleContext| {
+            let mut ids: Vec<u32> = tickets.iter().map(|ticket| ticket.id).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, (0..8).collect::<Vec<_>>());
+            assert!(tickets.iter().all(|ticket| ticket.lane == 0));
+            let numbers: Vec<&str> = tickets.iter().map(|ticket| ticket.number.as_str()).collect();
+            assert_eq!(numbers, ["T1", "T2", "T3", "T4", "T5", "T6", "T7", "T8"]);
+            assert_eq!(ctx.unique_values().count("id"), 8);
+        };
+
+        // From a compiled plan and from the JSON config.
+        let mut ctx = SampleContext::new().with_seed(11);
+        let tickets = Ticket::sample_many_with_context(config, 8, &mut ctx).unwrap();
+        check(tickets, &ctx);
+        let mut ctx = SampleContext::new().with_seed(11);
+        let tickets = (0..8).map(|_| Ticket::sample_with_context(config, &mut ctx)).collect::<Result<_, _>>().unwrap();
+        check(tickets, &ctx);
+    }
+
+    #[test]
+    fn constraints_naming_no_field_fail_to_compile() {
+        let config = json!({ "id": [0, 8], "lane": [0, 4], "number": ["T1"], "$constraints": ["lan == 0"] });
+        let config = config.as_object().unwrap();
+        let error = "Constraint 'lan == 0' refers to 'lan', which is not a field of this struct; did you mean 'lane'?";
+        assert_eq!(Ticket::compile_sampler(config).unwrap_err(), error);
+        assert_eq!(Ticket::sample_with_config(config).unwrap_err(), error);
+        let config = json!({ "id": [0, 8], "lane": [0, 4], "number": ["T1"], "$constraints": ["id < ceiling"] });
+        assert_eq!(
+            Ticket::compile_sampler(config.as_object().unwrap()).unwrap_err(),
+            "Constraint 'id < ceiling' refers to 'ceiling', which is not a field of this struct"
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! State shared by all the values sampled in one call.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::{Map, Value};
+
+use crate::dataset::{Datasets, RefSpec};
+use crate::fields::Scalar;
+use crate::plan::FieldPlan;
+use crate::presets::Presets;
+use crate::recursion::RecursiveSpec;
+use crate::unique::UniqueValues;
+
+/// Settings and state threaded through nested `sample_with_context` calls.
+///
+/// [`Sampleable::sample_with_config`](crate::Sampleable::sample_with_config)
+/// uses a fresh context; build one explicitly to set defaults for a whole
+/// document:
+///
+/// ```
+/// # use samplify_rs::{SampleContext, Sampleable};
+/// # #[derive(Sampleable)]
+/// # struct User { name: String }
+/// # let config = serde_json::json!({ "name": ["Alice", "Bob"] }).as_object().unwrap().clone();
+/// let mut ctx = SampleContext::new().with_locale("de_DE").with_seed(42);
+/// let user = User::sample_with_context(&config, &mut ctx)?;
+/// # Ok::<(), String>(())
+/// ```
+///
+/// Every random choice is drawn from the context's generator, so a context
+/// built with [`SampleContext::with_seed`] samples the same values for the
+/// same config on every run.
+#[derive(Debug, Clone)]
+pub struct SampleContext {
+    rng: StdRng,
+    locale: Option<Locale>,
+    presets: Presets,
+    path: Vec<&'static str>,
+    unique: UniqueValues,
+    sequences: HashMap<String, i64>,
+    sequence_calls: HashMap<String, u64>,
+    sequence_skips: HashMap<String, u64>,
+    datasets: Datasets,
+    max_depth: usize,
+    recursion_depth: u32,
+    strict: bool,
+    // Unique values and sequence numbers handed out by the attempts in
+    // progress, so that rejected attempts can give them back.
+    journal: Vec<Change>,
+    attempts: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Change {
+    Unique { path: String, value: Scalar },
+    Sequence { path: String, next: Option<i64>, skip: Option<u64> },
+}
+
+/// A field being sampled, for the state that a context keeps per field
+/// path: unique values, sequence counters and dataset references.
+#[derive(Debug, Clone, Copy)]
+pub enum FieldAt<'a> {
+    /// A field of a JSON config, whose path is joined from the nesting of
+    /// the context.
+    Config(&'a str),
+    /// A field of a compiled plan, whose path was worked out when the plan
+    /// was compiled.
+    Plan(&'a FieldPlan),
+}
+
+impl<'a> FieldAt<'a> {
+    /// The name of the field.
+    pub fn name(&self) -> &'a str {
+        match self {
+            FieldAt::Config(name) => name,
+            FieldAt::Plan(field) => field.name(),
+        }
+    }
+}
+
+/// A locale with the bundled locales its fake data is drawn from, worked
+/// out once when the locale is set or its `$locale` is compiled.
+#[derive(Debug, Clone)]
+pub struct Locale {
+    name: Arc<str>,
+    #[cfg(feature = "faker")]
+    chain: Result<Arc<[&'static str]>, String>,
+}
+
+impl Locale {
+    /// Resolves `name`. A locale without bundled data is only reported when
+    /// fake data is drawn in it.
+    pub fn new(name: &str) -> Self {
+        Locale {
+            name: Arc::from(name),
+            #[cfg(feature = "faker")]
+            chain: crate::fake::locale_chain(name).map(Arc::from),
+        }
+    }
+
+    /// Resolves `name`, failing for a locale without bundled data.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let locale = Locale::new(name);
+        #[cfg(feature = "faker")]
+        if let Err(e) = &locale.chain {
+            return Err(e.clone());
+        }
+        Ok(locale)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The bundled locales to draw fake data from, most specific first; see
+    /// [`locale_chain`](crate::fake::locale_chain).
+    #[cfg(feature = "faker")]
+    pub fn chain(&self) -> Result<&[&'static str], String> {
+        self.chain.as_deref().map_err(Clone::clone)
+    }
+}
+
+/// Nesting depth allowed when [`SampleContext::with_max_depth`] is not used.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+impl Default for SampleContext {
+    fn default() -> Self {
+        SampleContext {
+            rng: StdRng::from_entropy(),
+            locale: None,
+            presets: Presets::iso20022(),
+            path: Vec::new(),
+            unique: UniqueValues::default(),
+            sequences: HashMap::new(),
+            sequence_calls: HashMap::new(),
+            sequence_skips: HashMap::new(),
+            datasets: Datasets::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            recursion_depth: 0,
+            strict: false,
+            journal: Vec::new(),
+            attempts: 0,
+        }
+    }
+}
+
+impl SampleContext {
+    pub fn new() -> Self {
+        SampleContext::default()
+    }
+
+    /// Seeds the random number generator, making the samples reproducible.
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    /// Uses `rng` for every random choice.
+    pub fn with_rng(mut self, rng: StdRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// The random number generator samples are drawn from.
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// Sets the locale used by fake-data providers that do not name one.
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(Locale::new(&locale.into()));
+        self
+    }
+
+    /// The locale currently in effect, if any.
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_ref().map(Locale::name)
+    }
+
+    /// The generator and the locale in effect, borrowed together.
+    #[cfg(feature = "faker")]
+    pub(crate) fn rng_and_locale(&mut self) -> (&mut StdRng, Option<&Locale>) {
+        (&mut self.rng, self.locale.as_ref())
+    }
+
+    /// Sets the presets used for fields missing from the config, replacing
+    /// the default [`Presets::iso20022`].
+    pub fn with_presets(mut self, presets: Presets) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    /// The presets in effect.
+    pub fn presets(&self) -> &Presets {
+        &self.presets
+    }
+
+    /// Returns `config` with presets filled in for the missing keys among
+    /// `fields`, given as (field name, type name) pairs. Generated code calls
+    /// this before sampling the fields of a struct or variant.
+    #[doc(hidden)]
+    pub fn apply_presets<'a>(&self, config: &'a Map<String, Value>, fields: &[(&str, &str)]) -> Cow<'a, Map<String, Value>> {
+        if self.presets.is_empty() {
+            return Cow::Borrowed(config);
+        }
+        match self.presets.fill(config, fields) {
+            Some(filled) => Cow::Owned(filled),
+            None => Cow::Borrowed(config),
+        }
+    }
+
+    /// Limits how deeply values may be nested. Sampling deeper fails, and
+    /// recursive fields stop before reaching the limit.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// How deeply values may be nested.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Rejects config keys that name no field, variant or directive, instead
+    /// of ignoring them; see [`strict`](crate::strict).
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether unknown config keys are rejected.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Path of `field_name` from the root of the sample, e.g. `address.zip`.
+    pub fn field_path(&self, field_name: &str) -> String {
+        let mut path = self.path.join(".");
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(field_name);
+        path
+    }
+
+    /// The path of `field`. A plan field uses the path worked out when its
+    /// plan was compiled, unless the plan is sampled deeper than it was
+    /// compiled for: below a recursive field, or inside a type implementing
+    /// `Sampleable` by hand.
+    pub fn path_of<'a>(&self, field: FieldAt<'a>) -> Cow<'a, str> {
+        match field {
+            FieldAt::Plan(field) if field.depth() == self.path.len() => Cow::Borrowed(field.path()),
+            field => Cow::Owned(self.field_path(field.name())),
+        }
+    }
+
+    /// Values emitted so far by unique fields.
+    pub fn unique_values(&self) -> &UniqueValues {
+        &self.unique
+    }
+
+    /// Records `value` for the unique field `field`, returning `false` if an
+    /// earlier sample already produced it.
+    #[doc(hidden)]
+    pub fn claim_unique(&mut self, field: FieldAt, value: &Scalar) -> bool {
+        let path = self.path_of(field);
+        if !self.unique.claim(&path, value) {
+            return false;
+        }
+        if self.attempts > 0 && *value != Scalar::Null {
+            self.journal.push(Change::Unique { path: path.into_owned(), value: value.clone() });
+        }
+        true
+    }
+
+    /// Returns the next number of the sequence of field `field`: `start` the
+    /// first time, then the previous number plus `step`.
+    #[doc(hidden)]
+    pub fn next_in_sequence(&mut self, field: FieldAt, start: i64, step: i64) -> i64 {
+        let path = self.path_of(field);
+        let skip = self.sequence_skips.remove(&*path);
+        if self.attempts > 0 {
+            let next = self.sequences.get(&*path).copied();
+            self.journal.push(Change::Sequence { path: path.to_string(), next, skip });
+        }
+        *entry(&mut self.sequence_calls, &path, 0) += 1;
+        let next = entry(&mut self.sequences, &path, start);
+        let skip = i64::try_from(skip.unwrap_or(0)).unwrap_or(i64::MAX);
+        let current = next.saturating_add(step.saturating_mul(skip));
+        *next = current.saturating_add(step);
+        current
+    }
+
+    /// Runs `f`, one attempt at a value that a constraint may reject. When
+    /// `guarded` and `f` returns a rejection (`Ok(Err(_))`) or fails, the
+    /// unique values and sequence numbers it drew are given back, so the
+    /// next attempt can draw them again. Generated code wraps every attempt
+    /// at a constrained struct in this.
+    #[doc(hidden)]
+    pub fn attempt<T, E>(
+        &mut self,
+        guarded: bool,
+        f: impl FnOnce(&mut Self) -> Result<Result<T, E>, String>,
+    ) -> Result<Result<T, E>, String> {
+        if !guarded {
+            return f(self);
+        }
+        let mark = self.journal.len();
+        self.attempts += 1;
+        let result = f(self);
+        self.attempts -= 1;
+        if !matches!(result, Ok(Ok(_))) {
+            self.undo(mark);
+        }
+        // Outer attempts may still be rejected and need the changes.
+        if self.attempts == 0 {
+            self.journal.clear();
+        }
+        result
+    }
+
+    // Reverts the changes recorded after the first `mark`, newest first.
+    fn undo(&mut self, mark: usize) {
+        while self.journal.len() > mark {
+            match self.journal.pop().expect("the journal is longer than the mark") {
+                Change::Unique { path, value } => self.unique.release(&path, &value),
+                Change::Sequence { path, next, skip } => {
+                    if let Some(calls) = self.sequence_calls.get_mut(&path) {
+                        *calls -= 1;
+                        if *calls == 0 {
+                            self.sequence_calls.remove(&path);
+                        }
+                    }
+                    if let Some(skip) = skip {
+                        self.sequence_skips.insert(path.clone(), skip);
+                    }
+                    match next {
+                        Some(next) => self.sequences.insert(path, next),
+                        None => self.sequences.remove(&path),
+                    };
+                }
+            }
+        }
+    }
+
+    /// A context with the settings, datasets and sequence counters of this
+    /// one, but no unique values, drawing from `rng`. Parallel batches
+    /// sample each chunk with a fork.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn fork(&self, rng: StdRng) -> SampleContext {
+        SampleContext {
+            rng,
+            locale: self.locale.clone(),
+            presets: self.presets.clone(),
+            path: Vec::new(),
+            unique: UniqueValues::default(),
+            sequences: self.sequences.clone(),
+            sequence_calls: HashMap::new(),
+            sequence_skips: HashMap::new(),
+            datasets: self.datasets.clone(),
+            max_depth: self.max_depth,
+            recursion_depth: 0,
+            strict: self.strict,
+            journal: Vec::new(),
+            attempts: 0,
+        }
+    }
+
+    /// How many numbers each sequence handed out, by field path, since the
+    /// context was forked.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn sequence_calls(&self) -> &HashMap<String, u64> {
+        &self.sequence_calls
+    }
+
+    /// Makes the sequence at `path` skip `count` numbers before the next one.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn skip_in_sequence(&mut self, path: &str, count: u64) {
+        if count > 0 {
+            self.sequence_skips.insert(path.to_string(), count);
+        }
+    }
+
+    /// Takes over the counters of the sequences that `fork` used.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn absorb_sequences(&mut self, fork: &SampleContext) {
+        for path in fork.sequence_calls.keys() {
+            if let Some(next) = fork.sequences.get(path) {
+                self.sequences.insert(path.clone(), *next);
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    pub(crate) fn unique_values_mut(&mut self) -> &mut UniqueValues {
+        &mut self.unique
+    }
+
+    /// Registers `samples` as dataset `name`, so fields sampled later with
+    /// this context can refer to them with `{"ref": "name.field"}`. Samples
+    /// are stored in their serialized form.
+    pub fn register<T: serde::Serialize>(&mut self, name: &str, samples: &[T]) -> Result<(), String> {
+        let rows = samples
+            .iter()
+            .map(|sample| serde_json::to_value(sample).map_err(|e| format!("Cannot register dataset '{}': {}", name, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.datasets.insert(name, rows);
+        Ok(())
+    }
+
+    /// The registered datasets.
+    pub fn datasets(&self) -> &Datasets {
+        &self.datasets
+    }
+
+    /// Draws the value referenced by field `field_name` if its config is a
+    /// `ref`, or returns `None` for any other config.
+    #[doc(hidden)]
+    pub fn resolve_ref(&mut self, config: &Map<String, Value>, field_name: &str) -> Result<Option<Value>, String> {
+        let map = match config.get(field_name) {
+            Some(Value::Object(map)) if map.contains_key("ref") => map,
+            _ => return Ok(None),
+        };
+        let spec = RefSpec::parse(map, field_name)?;
+        self.draw_ref(&spec, FieldAt::Config(field_name)).map(Some)
+    }
+
+    /// Draws the value that `spec` refers to for field `field`.
+    #[doc(hidden)]
+    pub fn draw_ref(&mut self, spec: &RefSpec, field: FieldAt) -> Result<Value, String> {
+        let path = self.path_of(field);
+        self.datasets.draw(&mut self.rng, spec, &path)
+    }
+
+    /// Runs `f` for the nested value of field `field_name`. Generated code
+    /// wraps nested structs, enums and `Vec` items in this to keep track of
+    /// the field path.
+    #[doc(hidden)]
+    pub fn nested<T>(&mut self, field_name: &'static str, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        if self.path.len() >= self.max_depth {
+            return Err(format!(
+                "Field '{}' is nested more than {} levels deep",
+                self.field_path(field_name),
+                self.max_depth
+            ));
+        }
+        self.path.push(field_name);
+        let result = f(self);
+        self.path.pop();
+        result
+    }
+
+    /// Whether a recursive field should stop at the current level.
+    #[doc(hidden)]
+    pub fn recursion_ends(&mut self, spec: &RecursiveSpec) -> bool {
+        self.recursion_depth >= spec.max_depth()
+            || self.path.len() + 1 >= self.max_depth
+            || spec.random_leaf(&mut self.rng)
+    }
+
+    /// Runs `f` one level deeper in a recursive field.
+    #[doc(hidden)]
+    pub fn recurse<T>(&mut self, field_name: &'static str, f: impl FnOnce(&mut Self) -> Result<T, String>) -> Result<T, String> {
+        self.recursion_depth += 1;
+        let result = self.nested(field_name, f);
+        self.recursion_depth -= 1;
+        result
+    }
+
+    /// Runs `f` with `locale`, the `$locale` read when the plan was
+    /// compiled, in effect, restoring the previous locale afterwards.
+    /// Generated code wraps every struct and enum in this, so a `"$locale"`
+    /// key applies to the object it appears in and everything nested below
+    /// it.
+    #[doc(hidden)]
+    pub fn scoped_locale<T>(
+        &mut self,
+        locale: Option<&Locale>,
+        f: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let previous_locale = match locale {
+            Some(locale) => self.locale.replace(locale.clone()),
+            None => return f(self),
+        };
+        let result = f(self);
+        self.locale = previous_locale;
+        result
+    }
+}
+
+// The entry of `path` in `map`, inserting `default` first for a new path. The
+// key is only allocated for a new path.
+fn entry<'a, V>(map: &'a mut HashMap<String, V>, path: &str, default: V) -> &'a mut V {
+    if !map.contains_key(path) {
+        map.insert(path.to_string(), default);
+    }
+    map.get_mut(path).expect("the entry was just inserted")
+}
+
+/// The `$locale` of `config`, checked against the bundled locales.
+pub(crate) fn locale_directive(config: &Map<String, Value>) -> Result<Option<Locale>, String> {
+    match config.get("$locale") {
+        Some(Value::String(locale)) => Locale::parse(locale).map(Some),
+        Some(_) => Err("'$locale' must be a string".to_string()),
+        None => Ok(None),
+    }
+}
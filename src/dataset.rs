@@ -0,0 +1,275 @@
+//! Samples registered for other fields to refer to.
+//!
+//! After sampling one type, register the samples on the
+//! [`SampleContext`](crate::SampleContext) under a name; fields sampled later
+//! with that context can draw from them:
+//!
+//! ```json
+//! { "customer_id": { "ref": "Customer.id", "cardinality": [0, 5] } }
+//! ```
+//!
+//! The part after the dataset name is a dot-separated path into the
+//! serialized sample. `cardinality` bounds how often each registered sample is
+//! referenced by this field: samples referenced fewer than the minimum number
+//! of times are drawn first, and none is referenced more than the maximum.
+//! When fewer values are drawn than the minimums add up to, the samples left
+//! below it are not reported; once every sample is at the maximum, drawing
+//! fails.
+
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+
+/// A parsed `ref` field configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefSpec {
+    dataset: String,
+    path: Vec<String>,
+    min: u32,
+    max: Option<u32>,
+}
+
+impl RefSpec {
+    /// Parses `{"ref": "Dataset.path", "cardinality": [min, max]}`.
+    pub fn parse(map: &Map<String, Value>, field_name: &str) -> Result<Self, String> {
+        let reference = map
+            .get("ref")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("'ref' for field '{}' must be a string such as \"Customer.id\"", field_name))?;
+        let cardinality = match map.get("cardinality") {
+            None => None,
+            Some(Value::Array(bounds)) if bounds.len() == 2 => {
+                let min = bounds[0].as_u64();
+                let max = if bounds[1].is_null() { Some(None) } else { bounds[1].as_u64().map(Some) };
+                let (min, max) = match (min, max) {
+                    (Some(min), Some(max)) if max.is_none_or(|max| min <= max) => (min, max),
+                    _ => return Err(format!("Cardinality for field '{}' must be [min, max] with min <= max", field_name)),
+                };
+                let too_large = |_| format!("Cardinality for field '{}' must not exceed {}", field_name, u32::MAX);
+                Some((u32::try_from(min).map_err(too_large)?, max.map(u32::try_from).transpose().map_err(too_large)?))
+            }
+            Some(_) => return Err(format!("Cardinality for field '{}' must be an array [min, max]", field_name)),
+        };
+        Self::new(reference, cardinality, field_name)
+    }
+
+    /// Refers to `reference`, such as `"Customer.id"`, with an optional
+    /// `(min, max)` cardinality.
+    pub fn new(reference: &str, cardinality: Option<(u32, Option<u32>)>, field_name: &str) -> Result<Self, String> {
+        let mut segments = reference.split('.').map(str::to_string);
+        let dataset = segments.next().unwrap_or_default();
+        let path: Vec<String> = segments.collect();
+        if dataset.is_empty() || path.is_empty() || path.iter().any(String::is_empty) {
+            return Err(format!("'ref' for field '{}' must name a dataset and a field, e.g. \"Customer.id\"", field_name));
+        }
+        let (min, max) = cardinality.unwrap_or((0, None));
+        if max == Some(0) {
+            return Err(format!("Cardinality for field '{}' must allow at least one reference; its max is 0", field_name));
+        }
+        if max.is_some_and(|max| min > max) {
+            return Err(format!("Cardinality for field '{}' must be [min, max] with min <= max", field_name));
+        }
+        Ok(RefSpec { dataset, path, min, max })
+    }
+}
+
+impl RefSpec {
+    /// Whether `cardinality` limits how often a sample may be referenced.
+    pub fn is_limited(&self) -> bool {
+        self.min > 0 || self.max.is_some()
+    }
+}
+
+/// Registered samples, and how often each was referenced.
+#[derive(Debug, Clone, Default)]
+pub struct Datasets {
+    rows: HashMap<String, Vec<Value>>,
+    // How often each sample was referenced, by dataset and then by the path
+    // of the referencing field.
+    uses: HashMap<String, HashMap<String, Uses>>,
+}
+
+// How often each sample of a dataset was referenced by one field, with the
+// samples still below `min` and below `max` kept in pools, so that a draw
+// does not scan the dataset.
+#[derive(Debug, Clone)]
+struct Uses {
+    cardinality: (u32, Option<u32>),
+    counts: Vec<u32>,
+    below_min: Pool,
+    below_max: Pool,
+}
+
+// A set of sample indices with constant-time draws and removals.
+#[derive(Debug, Clone)]
+struct Pool {
+    indices: Vec<usize>,
+    // The position of each sample in `indices`, if it is in the pool.
+    positions: Vec<Option<usize>>,
+}
+
+impl Uses {
+    // The pools for `spec` of samples referenced `counts` times so far.
+    fn new(counts: Vec<u32>, spec: &RefSpec) -> Self {
+        let below_min = Pool::new((0..counts.len()).filter(|i| counts[*i] < spec.min), counts.len());
+        let below_max = Pool::new((0..counts.len()).filter(|i| spec.max.is_none_or(|max| counts[*i] < max)), counts.len());
+        Uses { cardinality: (spec.min, spec.max), counts, below_min, below_max }
+    }
+
+    // Picks a sample below `min` if any is left, else one below `max`.
+    fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<usize> {
+        let index = match self.below_min.choose(rng) {
+            Some(index) => index,
+            None => self.below_max.choose(rng)?,
+        };
+        self.counts[index] += 1;
+        let (min, max) = self.cardinality;
+        if self.counts[index] == min {
+            self.below_min.remove(index);
+        }
+        if Some(self.counts[index]) == max {
+            self.below_max.remove(index);
+        }
+        Some(index)
+    }
+}
+
+impl Pool {
+    // A pool of `indices` among `rows` samples.
+    fn new(indices: impl Iterator<Item = usize>, rows: usize) -> Self {
+        let indices: Vec<usize> = indices.collect();
+        let mut positions = vec![None; rows];
+        for (position, index) in indices.iter().enumerate() {
+            positions[*index] = Some(position);
+        }
+        Pool { indices, positions }
+    }
+
+    fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        self.indices.choose(rng).copied()
+    }
+
+    fn remove(&mut self, index: usize) {
+        if let Some(position) = self.positions[index].take() {
+            self.indices.swap_remove(position);
+            if let Some(moved) = self.indices.get(position) {
+                self.positions[*moved] = Some(position);
+            }
+        }
+    }
+}
+
+impl Datasets {
+    /// Registers `rows` under `name`, replacing an earlier dataset with the
+    /// same name.
+    pub fn insert(&mut self, name: &str, rows: Vec<Value>) {
+        self.uses.remove(name);
+        self.rows.insert(name.to_string(), rows);
+    }
+
+    /// The samples registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&[Value]> {
+        self.rows.get(name).map(Vec::as_slice)
+    }
+
+    /// Draws the referenced value for the field at `field_path`.
+    pub fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R, spec: &RefSpec, field_path: &str) -> Result<Value, String> {
+        let rows = self
+            .rows
+            .get(&spec.dataset)
+            .filter(|rows| !rows.is_empty())
+            .ok_or_else(|| format!("Field '{}' refers to dataset '{}', which is not registered", field_path, spec.dataset))?;
+        // Keys are only allocated the first time a field refers to a dataset.
+        if !self.uses.contains_key(&spec.dataset) {
+            self.uses.insert(spec.dataset.clone(), HashMap::new());
+        }
+        let by_field = self.uses.get_mut(&spec.dataset).expect("the dataset was just inserted");
+        if !by_field.contains_key(field_path) {
+            by_field.insert(field_path.to_string(), Uses::new(vec![0; rows.len()], spec));
+        }
+        let uses = by_field.get_mut(field_path).expect("the field was just inserted");
+        // Another config may refer from the same field with other bounds.
+        if uses.cardinality != (spec.min, spec.max) {
+            *uses = Uses::new(std::mem::take(&mut uses.counts), spec);
+        }
+
+        let index = uses.draw(rng).ok_or_else(|| {
+            format!("Every sample of dataset '{}' is already referenced the maximum number of times by '{}'", spec.dataset, field_path)
+        })?;
+
+        let mut value = &rows[index];
+        for segment in &spec.path {
+            value = value.get(segment).ok_or_else(|| {
+                format!("Samples of dataset '{}' have no field '{}' (referenced by '{}')", spec.dataset, spec.path.join("."), field_path)
+            })?;
+        }
+        Ok(value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::{SampleContext, Sampleable};
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Payment {
+        customer_id: u32,
+    }
+
+    // Draws `n` payments referring to 10 customers with `cardinality`, and
+    // counts the payments of each customer.
+    fn draw(cardinality: Value, n: usize) -> Result<Vec<u32>, String> {
+        let mut ctx = SampleContext::new().with_seed(4);
+        let customers: Vec<Value> = (0..10).map(|id| json!({ "id": id })).collect();
+        ctx.register("Customer", &customers)?;
+        let config = json!({ "customer_id": { "ref": "Customer.id", "cardinality": cardinality } });
+        let payments = Payment::sample_many_with_context(config.as_object().unwrap(), n, &mut ctx)?;
+        let mut counts = vec![0; 10];
+        for payment in payments {
+            counts[payment.customer_id as usize] += 1;
+        }
+        Ok(counts)
+    }
+
+    #[test]
+    fn every_sample_is_referenced_between_min_and_max_times() {
+        for n in [20, 25, 30] {
+            let counts = draw(json!([2, 3]), n).unwrap();
+            assert!(counts.iter().all(|count| (2..=3).contains(count)), "{:?}", counts);
+        }
+        let counts = draw(json!([1, null]), 100).unwrap();
+        assert!(counts.iter().all(|count| *count >= 1) && counts.iter().sum::<u32>() == 100);
+    }
+
+    #[test]
+    fn samples_below_min_are_drawn_first() {
+        // 15 draws cannot give each of 10 customers 2 payments. That is not
+        // reported, but no customer gets a third while one has fewer than two.
+        let counts = draw(json!([2, null]), 15).unwrap();
+        assert!(counts.iter().all(|count| *count <= 2) && counts.iter().sum::<u32>() == 15, "{:?}", counts);
+        let counts = draw(json!([2, null]), 21).unwrap();
+        assert_eq!(counts.iter().filter(|count| **count == 3).count(), 1, "{:?}", counts);
+    }
+
+    #[test]
+    fn drawing_past_every_max_fails() {
+        assert_eq!(draw(json!([0, 1]), 10).unwrap(), vec![1; 10]);
+        assert_eq!(
+            draw(json!([0, 1]), 11).unwrap_err(),
+            "Every sample of dataset 'Customer' is already referenced the maximum number of times by 'customer_id'"
+        );
+    }
+
+    #[test]
+    fn cardinalities_must_allow_a_reference() {
+        assert_eq!(
+            draw(json!([0, 0]), 1).unwrap_err(),
+            "Cardinality for field 'customer_id' must allow at least one reference; its max is 0"
+        );
+        assert_eq!(draw(json!([3, 2]), 1).unwrap_err(), "Cardinality for field 'customer_id' must be [min, max] with min <= max");
+    }
+}
@@ -0,0 +1,526 @@
+//! Exact sampling for decimal fields.
+//!
+//! Bounds are read as decimal strings (`["0.01", "99999.99"]`) and turned into
+//! integer mantissas at a fixed scale, so every sampled value is an exact
+//! multiple of `10^-scale` and never goes through binary floating point unless
+//! a non-uniform distribution is requested.
+//!
+//! Instead of a fixed `scale`, an amount can take its scale from a sibling
+//! currency field, as a float amount does:
+//!
+//! ```json
+//! { "range": ["1", "1000"], "currency_field": "currency" }
+//! ```
+//!
+//! The range is then narrowed to whole minor units of the sampled currency,
+//! so a `JPY` amount has scale 0 and a `KWD` amount scale 3.
+
+use rand::Rng;
+use serde_json::Value;
+
+use crate::config;
+use crate::currency::CurrencyField;
+use crate::fields::FieldValues;
+
+/// A decimal type that can be built from an integer mantissa and a scale.
+pub trait DecimalValue: Sized {
+    fn from_scaled(mantissa: i128, scale: u32) -> Result<Self, String>;
+}
+
+#[cfg(feature = "rust_decimal")]
+impl DecimalValue for rust_decimal::Decimal {
+    fn from_scaled(mantissa: i128, scale: u32) -> Result<Self, String> {
+        rust_decimal::Decimal::try_from_i128_with_scale(mantissa, scale).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl DecimalValue for bigdecimal::BigDecimal {
+    fn from_scaled(mantissa: i128, scale: u32) -> Result<Self, String> {
+        Ok(bigdecimal::BigDecimal::new(mantissa.into(), i64::from(scale)))
+    }
+}
+
+/// How values are spread between the bounds of a decimal range.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distribution {
+    /// Every representable value in the range is equally likely.
+    Uniform,
+    /// The logarithm of the value is uniform; useful for amounts spanning
+    /// several orders of magnitude. Both bounds must be positive.
+    LogUniform,
+    /// Normal distribution, resampled until it falls inside the range.
+    /// Sampling fails if 100 draws in a row fall outside.
+    Normal { mean: f64, std_dev: f64 },
+}
+
+/// A parsed decimal field configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecimalSpec {
+    low: i128,
+    high: i128,
+    scale: u32,
+    distribution: Distribution,
+    // `currency_field`, whose minor units replace `scale` when sampling.
+    currency: Option<CurrencyField>,
+}
+
+impl DecimalSpec {
+    /// Parses a decimal field configuration.
+    ///
+    /// Accepted shapes are a literal (`"12.50"`), a range (`["0.01", "99.99"]`)
+    /// or an object `{"range": [...], "scale": 2, "distribution": "uniform"}`.
+    /// `distribution` is one of `uniform`, `log_uniform` or `normal`; the
+    /// latter optionally takes `mean` and `std_dev`. `currency_field` may
+    /// replace `scale`.
+    pub fn parse(value: &Value, field_name: &str) -> Result<Self, String> {
+        match value {
+            Value::String(_) | Value::Number(_) => {
+                let literal = bound_text(value, field_name)?;
+                Self::range(&literal, &literal, None, field_name)
+            }
+            Value::Array(range_array) => {
+                let (low, high) = range_bounds(range_array, field_name)?;
+                Self::range(&low, &high, None, field_name)
+            }
+            Value::Object(map) => {
+                let range_array = match map.get("range") {
+                    Some(Value::Array(range_array)) => range_array,
+                    Some(_) => return Err(format!("Range for field '{}' must be an array", field_name)),
+                    None => return Err(format!("Configuration for field '{}' is missing 'range'", field_name)),
+                };
+                let scale = match map.get("scale") {
+                    Some(scale) => Some(
+                        scale
+                            .as_u64()
+                            .and_then(|scale| u32::try_from(scale).ok())
+                            .ok_or_else(|| format!("Scale for field '{}' must be an integer between 0 and 28", field_name))?,
+                    ),
+                    None => None,
+                };
+                let (low, high) = range_bounds(range_array, field_name)?;
+                let distribution = match map.get("distribution").map(|d| d.as_str()) {
+                    None | Some(Some("uniform")) => config::Distribution::Uniform,
+                    Some(Some("log_uniform")) => config::Distribution::LogUniform,
+                    Some(Some("normal")) => config::Distribution::Normal,
+                    _ => {
+                        return Err(format!(
+                            "Distribution for field '{}' must be one of 'uniform', 'log_uniform' or 'normal'",
+                            field_name
+                        ))
+                    }
+                };
+                let mean = map.get("mean").map(|mean| bound_text(mean, field_name)).transpose()?;
+                let std_dev = map.get("std_dev").map(|std_dev| bound_text(std_dev, field_name)).transpose()?;
+                let currency_field = match map.get("currency_field") {
+                    Some(Value::String(name)) => Some(name.clone()),
+                    Some(_) => return Err(format!("'currency_field' for field '{}' must be a string", field_name)),
+                    None => None,
+                };
+                let currency = Self::currency(scale, currency_field, field_name)?;
+                Ok(Self::range(&low, &high, scale, field_name)?
+                    .with_distribution(distribution, mean.as_deref(), std_dev.as_deref(), field_name)?
+                    .with_currency(currency))
+            }
+            _ => Err(format!("Configuration for field '{}' must be a string, an array or an object", field_name)),
+        }
+    }
+
+    /// The uniform range between the decimal literals `low` and `high`, at
+    /// `scale` or else the larger scale of the two.
+    pub fn range(low: &str, high: &str, scale: Option<u32>, field_name: &str) -> Result<Self, String> {
+        if scale.is_some_and(|scale| scale > 28) {
+            return Err(format!("Scale for field '{}' must be an integer between 0 and 28", field_name));
+        }
+        let (low, low_scale) = parse_bound(low, field_name)?;
+        let (high, high_scale) = parse_bound(high, field_name)?;
+        let scale = scale.unwrap_or(low_scale.max(high_scale));
+        let low = rescale(low, low_scale, scale, field_name)?;
+        let high = rescale(high, high_scale, scale, field_name)?;
+        if low > high {
+            return Err(format!("Range for field '{}' is empty", field_name));
+        }
+        Ok(DecimalSpec { low, high, scale, distribution: Distribution::Uniform, currency: None })
+    }
+
+    /// Combines the `scale` and `currency_field` options, which exclude each
+    /// other.
+    pub fn currency(scale: Option<u32>, currency_field: Option<String>, field_name: &str) -> Result<Option<CurrencyField>, String> {
+        match (scale, currency_field) {
+            (Some(_), Some(_)) => Err(format!("Field '{}' sets both 'scale' and 'currency_field'", field_name)),
+            (_, currency_field) => Ok(currency_field.map(CurrencyField::new)),
+        }
+    }
+
+    /// Takes the scale of sampled values from `currency`, if any.
+    pub fn with_currency(mut self, currency: Option<CurrencyField>) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Binds `currency_field` to its slot among `names`, the sibling fields
+    /// in declaration order.
+    pub fn bind(&mut self, names: &[&str]) {
+        if let Some(currency) = &mut self.currency {
+            currency.bind(names);
+        }
+    }
+
+    /// The slot `currency_field` is bound to, if any.
+    pub fn currency_slot(&self) -> Option<usize> {
+        self.currency.as_ref().and_then(CurrencyField::slot)
+    }
+
+    /// Spreads values by `distribution`. A normal distribution centres on
+    /// `mean` (default the middle of the range) with `std_dev` (default a
+    /// sixth of the range).
+    pub fn with_distribution(mut self, distribution: config::Distribution, mean: Option<&str>, std_dev: Option<&str>, field_name: &str) -> Result<Self, String> {
+        self.distribution = match distribution {
+            config::Distribution::Uniform => Distribution::Uniform,
+            config::Distribution::LogUniform => {
+                if self.low <= 0 {
+                    return Err(format!("Log-uniform range for field '{}' must be positive", field_name));
+                }
+                Distribution::LogUniform
+            }
+            config::Distribution::Normal => {
+                let low = self.to_f64(self.low);
+                let high = self.to_f64(self.high);
+                let mean = match mean {
+                    Some(mean) => bound_to_f64(mean, field_name)?,
+                    None => (low + high) / 2.0,
+                };
+                let std_dev = match std_dev {
+                    Some(std_dev) => bound_to_f64(std_dev, field_name)?,
+                    None => (high - low) / 6.0,
+                };
+                if !mean.is_finite() {
+                    return Err(format!("Mean for field '{}' must be a finite number", field_name));
+                }
+                if !(std_dev.is_finite() && std_dev > 0.0) && self.low != self.high {
+                    return Err(format!("Standard deviation for field '{}' must be a positive number", field_name));
+                }
+                Distribution::Normal { mean, std_dev }
+            }
+        };
+        Ok(self)
+    }
+
+    fn to_f64(&self, mantissa: i128) -> f64 {
+        mantissa as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    fn mantissa_of(&self, value: f64) -> i128 {
+        ((value * 10f64.powi(self.scale as i32)).round() as i128).clamp(self.low, self.high)
+    }
+
+    /// Samples a value according to this specification. `values` holds the
+    /// sibling fields sampled so far, for `currency_field`.
+    pub fn sample<D: DecimalValue, R: Rng + ?Sized>(&self, rng: &mut R, values: &FieldValues, field_name: &str) -> Result<D, String> {
+        match &self.currency {
+            None => self.sample_at_scale(rng, field_name),
+            Some(currency) => {
+                let scale = currency.minor_units(values, field_name)?;
+                self.at_scale(scale, field_name)?.sample_at_scale(rng, field_name)
+            }
+        }
+    }
+
+    // The range narrowed to the multiples of `10^-scale` inside it.
+    fn at_scale(&self, scale: u32, field_name: &str) -> Result<DecimalSpec, String> {
+        let (low, high) = if scale >= self.scale {
+            (rescale(self.low, self.scale, scale, field_name)?, rescale(self.high, self.scale, scale, field_name)?)
+        } else {
+            let factor = 10i128.pow(self.scale - scale);
+            (-(-self.low).div_euclid(factor), self.high.div_euclid(factor))
+        };
+        if low > high {
+            return Err(format!("Range for field '{}' contains no value with {} decimals", field_name, scale));
+        }
+        Ok(DecimalSpec { low, high, scale, distribution: self.distribution.clone(), currency: None })
+    }
+
+    fn sample_at_scale<D: DecimalValue, R: Rng + ?Sized>(&self, rng: &mut R, field_name: &str) -> Result<D, String> {
+        let mantissa = match &self.distribution {
+            Distribution::Uniform => rng.gen_range(self.low..=self.high),
+            Distribution::LogUniform => {
+                let (low, high) = (self.to_f64(self.low).ln(), self.to_f64(self.high).ln());
+                self.mantissa_of(rng.gen_range(low..=high).exp())
+            }
+            Distribution::Normal { mean, std_dev } => {
+                let (low, high) = (self.to_f64(self.low), self.to_f64(self.high));
+                let drawn = (0..100).find_map(|_| {
+                    // Box-Muller transform; `1.0 - gen` keeps the logarithm finite.
+                    let u1: f64 = 1.0 - rng.gen::<f64>();
+                    let u2: f64 = rng.gen();
+                    let value = mean + std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                    (low..=high).contains(&value).then_some(value)
+                });
+                match drawn {
+                    Some(value) => self.mantissa_of(value),
+                    None => {
+                        return Err(format!(
+                            "Normal distribution for field '{}' drew 100 values outside its range; move 'mean' into the range or widen 'std_dev'",
+                            field_name
+                        ))
+                    }
+                }
+            }
+        };
+        D::from_scaled(mantissa, self.scale)
+    }
+}
+
+/// Parses `value` and samples a decimal from it in one step.
+pub fn sample_decimal<D: DecimalValue, R: Rng + ?Sized>(rng: &mut R, value: &Value, field_name: &str, values: &FieldValues) -> Result<D, String> {
+    let mut spec = DecimalSpec::parse(value, field_name)?;
+    spec.bind(values.names());
+    spec.sample(rng, values, field_name)
+}
+
+// The text of a decimal written as a string or a number.
+fn bound_text(value: &Value, field_name: &str) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        _ => Err(format!("Invalid decimal value {} for field '{}'", value, field_name)),
+    }
+}
+
+fn range_bounds(range_array: &[Value], field_name: &str) -> Result<(String, String), String> {
+    if range_array.len() != 2 {
+        return Err(format!("Range array for field '{}' must have exactly two elements", field_name));
+    }
+    Ok((bound_text(&range_array[0], field_name)?, bound_text(&range_array[1], field_name)?))
+}
+
+fn parse_bound(text: &str, field_name: &str) -> Result<(i128, u32), String> {
+    parse_decimal_str(text).ok_or_else(|| format!("Invalid decimal value '{}' for field '{}'", text, field_name))
+}
+
+fn bound_to_f64(text: &str, field_name: &str) -> Result<f64, String> {
+    let (mantissa, scale) = parse_bound(text, field_name)?;
+    Ok(mantissa as f64 / 10f64.powi(scale as i32))
+}
+
+/// Splits a plain decimal literal such as `-12.50` into mantissa and scale.
+fn parse_decimal_str(s: &str) -> Option<(i128, u32)> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+    if int_part.is_empty() && frac_part.is_empty() {
+        return None;
+    }
+    let mut mantissa: i128 = 0;
+    for c in int_part.chars().chain(frac_part.chars()) {
+        let digit = c.to_digit(10)?;
+        mantissa = mantissa.checked_mul(10)?.checked_add(i128::from(digit))?;
+    }
+    let scale = u32::try_from(frac_part.len()).ok()?;
+    Some((if negative { -mantissa } else { mantissa }, scale))
+}
+
+fn rescale(mantissa: i128, from: u32, to: u32, field_name: &str) -> Result<i128, String> {
+    if to >= from {
+        10i128
+            .checked_pow(to - from)
+            .and_then(|factor| mantissa.checked_mul(factor))
+            .ok_or_else(|| format!("Decimal value for field '{}' is out of range", field_name))
+    } else {
+        let factor = 10i128.pow(from - to);
+        if mantissa % factor != 0 {
+            return Err(format!("Value for field '{}' has more decimal places than scale {}", field_name, to));
+        }
+        Ok(mantissa / factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn decimal_literals_split_into_mantissa_and_scale() {
+        assert_eq!(parse_decimal_str("12.50"), Some((1250, 2)));
+        assert_eq!(parse_decimal_str("-0.5"), Some((-5, 1)));
+        assert_eq!(parse_decimal_str("+7"), Some((7, 0)));
+        assert_eq!(parse_decimal_str(" .25 "), Some((25, 2)));
+        assert_eq!(parse_decimal_str("3."), Some((3, 0)));
+        assert_eq!(parse_decimal_str("."), None);
+        assert_eq!(parse_decimal_str("1e5"), None);
+        assert_eq!(parse_decimal_str("1.2.3"), None);
+        assert_eq!(parse_decimal_str(&"9".repeat(40)), None);
+    }
+
+    #[test]
+    fn rescaling_keeps_the_value_exact() {
+        assert_eq!(rescale(125, 1, 3, "x"), Ok(12500));
+        assert_eq!(rescale(12500, 3, 1, "x"), Ok(125));
+        assert_eq!(rescale(-40, 1, 0, "x"), Ok(-4));
+        assert_eq!(rescale(125, 2, 1, "x").unwrap_err(), "Value for field 'x' has more decimal places than scale 1");
+        assert_eq!(rescale(1, 0, 40, "x").unwrap_err(), "Decimal value for field 'x' is out of range");
+        assert_eq!(rescale(i128::MAX / 5, 0, 1, "x").unwrap_err(), "Decimal value for field 'x' is out of range");
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn normal_distributions_need_a_positive_standard_deviation() {
+        let normal = |std_dev: Value| {
+            DecimalSpec::parse(&json!({ "range": ["0", "10"], "distribution": "normal", "std_dev": std_dev }), "x")
+        };
+        assert!(normal(json!("1.5")).is_ok());
+        for std_dev in [json!("0"), json!("-1"), json!(0)] {
+            assert_eq!(normal(std_dev).unwrap_err(), "Standard deviation for field 'x' must be a positive number");
+        }
+        let single = DecimalSpec::parse(&json!({ "range": ["5", "5"], "distribution": "normal" }), "x").unwrap();
+        let value: rust_decimal::Decimal = single.sample(&mut StdRng::seed_from_u64(1), &FieldValues::new(&[], &mut []), "x").unwrap();
+        assert_eq!(value.to_string(), "5");
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn normal_draws_far_outside_the_range_fail() {
+        let spec = DecimalSpec::parse(
+            &json!({ "range": ["0", "1"], "distribution": "normal", "mean": "1000", "std_dev": "1" }),
+            "x",
+        )
+        .unwrap();
+        let error = spec.sample::<rust_decimal::Decimal, _>(&mut StdRng::seed_from_u64(1), &FieldValues::new(&[], &mut []), "x").unwrap_err();
+        assert!(error.starts_with("Normal distribution for field 'x' drew 100 values outside its range"), "{}", error);
+    }
+
+    #[cfg(feature = "bigdecimal")]
+    #[test]
+    fn big_decimals_are_exact_multiples_of_the_scale() {
+        let spec = DecimalSpec::parse(&json!({ "range": ["0.01", "99999.99"], "distribution": "log_uniform" }), "x").unwrap();
+        let mut rng = StdRng::seed_from_u64(3);
+        let low: bigdecimal::BigDecimal = "0.01".parse().unwrap();
+        let high: bigdecimal::BigDecimal = "99999.99".parse().unwrap();
+        for _ in 0..100 {
+            let value: bigdecimal::BigDecimal = spec.sample(&mut rng, &FieldValues::new(&[], &mut []), "x").unwrap();
+            assert!(low <= value && value <= high, "{}", value);
+            assert_eq!(value.fractional_digit_count(), 2);
+        }
+        let literal: bigdecimal::BigDecimal = sample_decimal(&mut rng, &json!("-12.50"), "x", &FieldValues::new(&[], &mut [])).unwrap();
+        assert_eq!(literal.to_string(), "-12.50");
+    }
+
+    #[cfg(feature = "bigdecimal")]
+    #[test]
+    fn big_decimals_keep_mantissas_beyond_rust_decimal() {
+        let huge = format!("1{}", "0".repeat(30));
+        let value: bigdecimal::BigDecimal = sample_decimal(&mut StdRng::seed_from_u64(1), &json!([huge, huge]), "x", &FieldValues::new(&[], &mut [])).unwrap();
+        assert_eq!(value.to_string(), huge);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[derive(Debug, crate::Sampleable)]
+    struct Payment {
+        amount: rust_decimal::Decimal,
+        fee: Option<rust_decimal::Decimal>,
+        rates: Vec<rust_decimal::Decimal>,
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn derived_decimal_fields_sample_exactly() {
+        let config = json!({
+            "amount": { "range": ["10.00", "20.00"], "scale": 2 },
+            "fee": "0.25",
+            "rates": ["1.5", "2.25"]
+        });
+        let config = config.as_object().unwrap();
+        let mut ctx = crate::SampleContext::new().with_seed(9);
+        for _ in 0..20 {
+            let payment = <Payment as crate::Sampleable>::sample_with_context(config, &mut ctx).unwrap();
+            assert!(payment.amount >= rust_decimal::Decimal::new(1000, 2) && payment.amount <= rust_decimal::Decimal::new(2000, 2));
+            assert_eq!(payment.amount.scale(), 2);
+            assert_eq!(payment.fee, Some(rust_decimal::Decimal::new(25, 2)));
+            assert!(!payment.rates.is_empty());
+            assert!(payment.rates.iter().all(|rate| ["1.5", "2.25"].contains(&rate.to_string().as_str())));
+        }
+        let error = <Payment as crate::Sampleable>::sample_with_config(&json!({ "amount": ["2", "1"] }).as_object().unwrap().clone()).unwrap_err();
+        assert_eq!(error, "Range for field 'amount' is empty");
+    }
+
+    #[test]
+    fn currency_scales_narrow_the_range_inwards() {
+        let spec = DecimalSpec::range("-1.55", "2.45", None, "x").unwrap();
+        let narrowed = spec.at_scale(1, "x").unwrap();
+        assert_eq!((narrowed.low, narrowed.high, narrowed.scale), (-15, 24, 1));
+        let widened = spec.at_scale(3, "x").unwrap();
+        assert_eq!((widened.low, widened.high, widened.scale), (-1550, 2450, 3));
+        let spec = DecimalSpec::range("0.1", "0.9", None, "x").unwrap();
+        assert_eq!(spec.at_scale(0, "x").unwrap_err(), "Range for field 'x' contains no value with 0 decimals");
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[derive(Debug, crate::Sampleable)]
+    struct Invoice {
+        amount: rust_decimal::Decimal,
+        currency: String,
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    fn invoices(config: Value) -> Result<Vec<Invoice>, String> {
+        let mut ctx = crate::SampleContext::new().with_seed(4);
+        <Invoice as crate::Sampleable>::sample_many_with_context(config.as_object().unwrap(), 60, &mut ctx)
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn decimal_amounts_take_their_scale_from_a_currency_field() {
+        let invoices = invoices(json!({
+            "amount": { "range": ["0.5", "1000"], "currency_field": "currency" },
+            "currency": ["JPY", "EUR", "KWD"]
+        }))
+        .unwrap();
+        let (low, high) = (rust_decimal::Decimal::new(5, 1), rust_decimal::Decimal::new(1000, 0));
+        for invoice in &invoices {
+            let scale = crate::currency::minor_units(&invoice.currency).unwrap();
+            assert_eq!(invoice.amount.scale(), scale, "{:?}", invoice);
+            assert!(low <= invoice.amount && invoice.amount <= high, "{:?}", invoice);
+        }
+        for currency in ["JPY", "EUR", "KWD"] {
+            assert!(invoices.iter().any(|invoice| invoice.currency == currency));
+        }
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn decimal_currency_fields_are_checked() {
+        let error = |amount: Value, currency: Value| invoices(json!({ "amount": amount, "currency": currency })).unwrap_err();
+        assert_eq!(
+            error(json!({ "range": ["1", "9"], "scale": 2, "currency_field": "currency" }), json!(["EUR"])),
+            "Field 'amount' sets both 'scale' and 'currency_field'"
+        );
+        assert_eq!(
+            error(json!({ "range": ["1", "9"], "currency_field": "currency" }), json!(["EURO"])),
+            "Field 'amount' refers to 'currency', whose value 'EURO' is not an ISO 4217 currency"
+        );
+        assert_eq!(
+            error(json!({ "range": ["0.1", "0.9"], "currency_field": "currency" }), json!(["JPY"])),
+            "Range for field 'amount' contains no value with 0 decimals"
+        );
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn typed_decimal_configs_take_a_currency_field() {
+        let typed: crate::config::ConfigOf<Invoice> = serde_json::from_value(json!({
+            "amount": { "range": ["1", "1000"], "currency_field": "currency" },
+            "currency": ["KWD"]
+        }))
+        .unwrap();
+        let invoice = <Invoice as crate::Sampleable>::sample_with_config(&typed).unwrap();
+        assert_eq!(invoice.currency, "KWD");
+        assert_eq!(invoice.amount.scale(), 3);
+    }
+}
@@ -0,0 +1,39 @@
+use super::LocaleData;
+
+pub(super) const DE_DE: LocaleData = LocaleData {
+    first_names: &[
+        "Ben", "Emma", "Paul", "Mia", "Leon", "Hannah", "Finn", "Sophia", "Elias", "Emilia",
+        "Jonas", "Lina", "Luis", "Marie", "Noah", "Lea", "Felix", "Anna", "Lukas", "Clara",
+        "Maximilian", "Lena", "Jürgen", "Ursula", "Klaus", "Sabine", "Wolfgang", "Monika", "Stefan", "Katrin",
+    ],
+    last_names: &[
+        "Müller", "Schmidt", "Schneider", "Fischer", "Weber", "Meyer", "Wagner", "Becker", "Schulz", "Hoffmann",
+        "Schäfer", "Koch", "Bauer", "Richter", "Klein", "Wolf", "Schröder", "Neumann", "Schwarz", "Zimmermann",
+        "Braun", "Krüger", "Hofmann", "Hartmann", "Lange", "Schmitt", "Werner", "Schmitz", "Krause", "Meier",
+    ],
+    full_name_format: "{first} {last}",
+    street_names: &[
+        "Haupt", "Bahnhof", "Garten", "Schul", "Dorf", "Berg", "Kirch", "Wald", "Ring", "Linden",
+        "Birken", "Wiesen", "Goethe", "Schiller", "Mozart", "Friedrich", "Post", "Markt", "Feld", "Rosen",
+    ],
+    street_suffixes: &["straße", "weg", "allee", "gasse", "platz", "ring"],
+    street_formats: &["{name}{suffix} %", "{name}{suffix} %#", "{name}{suffix} %#?"],
+    street_name_format: "{name}{suffix}",
+    cities: &[
+        "Berlin", "Hamburg", "München", "Köln", "Frankfurt am Main", "Stuttgart", "Düsseldorf", "Leipzig",
+        "Dortmund", "Essen", "Bremen", "Dresden", "Hannover", "Nürnberg", "Duisburg", "Bochum", "Wuppertal",
+        "Bielefeld", "Bonn", "Münster", "Mannheim", "Karlsruhe", "Augsburg", "Wiesbaden", "Freiburg im Breisgau",
+    ],
+    states: &[
+        "Baden-Württemberg", "Bayern", "Berlin", "Brandenburg", "Bremen", "Hamburg", "Hessen",
+        "Mecklenburg-Vorpommern", "Niedersachsen", "Nordrhein-Westfalen", "Rheinland-Pfalz", "Saarland",
+        "Sachsen", "Sachsen-Anhalt", "Schleswig-Holstein", "Thüringen",
+    ],
+    country: "Deutschland",
+    postcode_formats: &["%####"],
+    phone_country_code: "49",
+    phone_national_format: "15%########",
+    company_suffixes: &["GmbH", "AG", "KG", "GmbH & Co. KG", "OHG", "e.K."],
+    email_domains: &["gmail.com", "web.de", "gmx.de", "t-online.de", "outlook.de", "freenet.de"],
+    domain_suffixes: &["de", "com", "net", "eu"],
+};
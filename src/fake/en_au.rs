@@ -0,0 +1,37 @@
+use super::LocaleData;
+
+pub(super) const EN_AU: LocaleData = LocaleData {
+    first_names: &[
+        "Oliver", "Charlotte", "Noah", "Amelia", "Jack", "Isla", "William", "Olivia", "Leo", "Mia",
+        "Henry", "Ava", "Thomas", "Grace", "Lucas", "Matilda", "James", "Chloe", "Hudson", "Zoe",
+        "Liam", "Ella", "Ethan", "Sophie", "Charlie", "Ruby", "Harrison", "Willow", "Cooper", "Harper",
+    ],
+    last_names: &[
+        "Smith", "Jones", "Williams", "Brown", "Wilson", "Taylor", "Johnson", "White", "Martin", "Anderson",
+        "Thompson", "Nguyen", "Thomas", "Walker", "Harris", "Lee", "Ryan", "Robinson", "Kelly", "King",
+        "Davis", "Wright", "Evans", "Roberts", "Green", "Hall", "Wood", "Jackson", "Clarke", "Patel",
+    ],
+    full_name_format: "{first} {last}",
+    street_names: &[
+        "George", "King", "Queen", "Elizabeth", "Victoria", "William", "Collins", "Bourke", "Flinders", "Pitt",
+        "Hunter", "Macquarie", "Swanston", "Adelaide", "Ann", "Hay", "Murray", "Wattle", "Banksia", "Bay",
+    ],
+    street_suffixes: &["Street", "Road", "Avenue", "Parade", "Crescent", "Drive", "Close", "Place", "Terrace", "Highway"],
+    street_formats: &["%# {name} {suffix}", "% {name} {suffix}", "%## {name} {suffix}", "Unit % / %# {name} {suffix}"],
+    street_name_format: "{name} {suffix}",
+    cities: &[
+        "Sydney", "Melbourne", "Brisbane", "Perth", "Adelaide", "Gold Coast", "Canberra", "Newcastle", "Wollongong",
+        "Hobart", "Geelong", "Townsville", "Cairns", "Darwin", "Toowoomba", "Ballarat", "Bendigo", "Launceston",
+    ],
+    states: &[
+        "New South Wales", "Victoria", "Queensland", "Western Australia", "South Australia", "Tasmania",
+        "Australian Capital Territory", "Northern Territory",
+    ],
+    country: "Australia",
+    postcode_formats: &["%###"],
+    phone_country_code: "61",
+    phone_national_format: "4########",
+    company_suffixes: &["Pty Ltd", "Ltd", "Group", "Holdings"],
+    email_domains: &["gmail.com", "bigpond.com", "outlook.com", "yahoo.com.au", "icloud.com", "optusnet.com.au"],
+    domain_suffixes: &["com.au", "net.au", "org.au", "com"],
+};
@@ -0,0 +1,38 @@
+use super::LocaleData;
+
+pub(super) const EN_GB: LocaleData = LocaleData {
+    first_names: &[
+        "Oliver", "Amelia", "George", "Isla", "Harry", "Ava", "Noah", "Mia", "Jack", "Ivy",
+        "Leo", "Lily", "Arthur", "Isabella", "Muhammad", "Rosie", "Oscar", "Sophia", "Charlie", "Grace",
+        "Jacob", "Freya", "Thomas", "Florence", "Henry", "Willow", "William", "Emily", "Alfie", "Poppy",
+    ],
+    last_names: &[
+        "Smith", "Jones", "Taylor", "Brown", "Williams", "Wilson", "Johnson", "Davies", "Patel", "Robinson",
+        "Wright", "Thompson", "Evans", "Walker", "White", "Roberts", "Green", "Hall", "Wood", "Jackson",
+        "Clarke", "Hughes", "Edwards", "Turner", "Hill", "Moore", "Cooper", "Ward", "Morris", "King",
+    ],
+    full_name_format: "{first} {last}",
+    street_names: &[
+        "High", "Station", "Church", "Park", "Victoria", "Green", "Manor", "Kings", "Queens", "Mill",
+        "School", "London", "Alexandra", "York", "Grange", "Springfield", "Windsor", "Albert", "Chapel", "Orchard",
+    ],
+    street_suffixes: &["Street", "Road", "Lane", "Close", "Avenue", "Drive", "Gardens", "Crescent", "Way", "Terrace"],
+    street_formats: &["%# {name} {suffix}", "% {name} {suffix}", "%## {name} {suffix}"],
+    street_name_format: "{name} {suffix}",
+    cities: &[
+        "London", "Birmingham", "Manchester", "Leeds", "Glasgow", "Liverpool", "Bristol", "Sheffield", "Edinburgh",
+        "Cardiff", "Leicester", "Nottingham", "Coventry", "Bradford", "Belfast", "Newcastle upon Tyne", "Brighton",
+        "Southampton", "Portsmouth", "Plymouth", "Oxford", "Cambridge", "York", "Aberdeen", "Norwich",
+    ],
+    states: &[
+        "Greater London", "West Midlands", "Greater Manchester", "West Yorkshire", "Kent", "Essex", "Hampshire",
+        "Surrey", "Lancashire", "Merseyside", "Devon", "Norfolk", "Somerset", "Cornwall", "Oxfordshire",
+    ],
+    country: "United Kingdom",
+    postcode_formats: &["?# #??", "?## #??", "??# #??", "??## #??"],
+    phone_country_code: "44",
+    phone_national_format: "7%########",
+    company_suffixes: &["Ltd", "PLC", "LLP", "& Co", "Group", "Holdings"],
+    email_domains: &["gmail.com", "hotmail.co.uk", "outlook.com", "yahoo.co.uk", "btinternet.com", "icloud.com"],
+    domain_suffixes: &["co.uk", "com", "org.uk", "uk", "net"],
+};
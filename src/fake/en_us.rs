@@ -0,0 +1,56 @@
+use super::LocaleData;
+
+pub(super) const EN_US: LocaleData = LocaleData {
+    first_names: &[
+        "James", "Mary", "Robert", "Patricia", "John", "Jennifer", "Michael", "Linda", "David", "Elizabeth",
+        "William", "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah", "Charles", "Karen",
+        "Christopher", "Lisa", "Daniel", "Nancy", "Matthew", "Betty", "Anthony", "Sandra", "Mark", "Margaret",
+        "Donald", "Ashley", "Steven", "Kimberly", "Andrew", "Emily", "Paul", "Donna", "Joshua", "Michelle",
+        "Kenneth", "Carol", "Kevin", "Amanda", "Brian", "Melissa", "George", "Deborah", "Timothy", "Stephanie",
+    ],
+    last_names: &[
+        "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez", "Martinez",
+        "Hernandez", "Lopez", "Gonzalez", "Wilson", "Anderson", "Thomas", "Taylor", "Moore", "Jackson", "Martin",
+        "Lee", "Perez", "Thompson", "White", "Harris", "Sanchez", "Clark", "Ramirez", "Lewis", "Robinson",
+        "Walker", "Young", "Allen", "King", "Wright", "Scott", "Torres", "Nguyen", "Hill", "Flores",
+        "Green", "Adams", "Nelson", "Baker", "Hall", "Rivera", "Campbell", "Mitchell", "Carter", "Roberts",
+    ],
+    full_name_format: "{first} {last}",
+    street_names: &[
+        "Main", "Oak", "Pine", "Maple", "Cedar", "Elm", "Washington", "Lake", "Hill", "Park",
+        "Walnut", "Sunset", "Highland", "Jefferson", "Lincoln", "Madison", "Franklin", "Chestnut", "Spring", "Ridge",
+        "Church", "River", "Meadow", "Forest", "Willow", "Adams", "Jackson", "Center", "Union", "Mill",
+    ],
+    street_suffixes: &["Street", "Avenue", "Road", "Boulevard", "Lane", "Drive", "Court", "Place", "Way", "Terrace"],
+    street_formats: &["%## {name} {suffix}", "%### {name} {suffix}", "%#### {name} {suffix}"],
+    street_name_format: "{name} {suffix}",
+    cities: &[
+        "New York", "Los Angeles", "Chicago", "Houston", "Phoenix", "Philadelphia", "San Antonio", "San Diego",
+        "Dallas", "San Jose", "Austin", "Jacksonville", "Fort Worth", "Columbus", "Charlotte", "Indianapolis",
+        "San Francisco", "Seattle", "Denver", "Nashville", "Oklahoma City", "Boston", "Portland", "Las Vegas",
+        "Detroit", "Memphis", "Louisville", "Baltimore", "Milwaukee", "Albuquerque",
+    ],
+    states: &[
+        "Alabama", "Alaska", "Arizona", "California", "Colorado", "Connecticut", "Florida", "Georgia", "Illinois",
+        "Indiana", "Iowa", "Kansas", "Kentucky", "Maryland", "Massachusetts", "Michigan", "Minnesota", "Missouri",
+        "Nevada", "New Jersey", "New York", "North Carolina", "Ohio", "Oregon", "Pennsylvania", "Tennessee",
+        "Texas", "Utah", "Virginia", "Washington",
+    ],
+    country: "United States",
+    postcode_formats: &["#####"],
+    phone_country_code: "1",
+    phone_national_format: "%##%######",
+    company_suffixes: &["Inc", "LLC", "Group", "Corp", "and Sons", "Holdings"],
+    email_domains: &["gmail.com", "yahoo.com", "hotmail.com", "outlook.com", "aol.com", "icloud.com"],
+    domain_suffixes: &["com", "net", "org", "io", "biz"],
+};
+
+pub(super) const LOREM_WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim",
+    "ad", "minim", "veniam", "quis", "nostrud", "exercitation", "ullamco", "laboris", "nisi", "aliquip",
+    "ex", "ea", "commodo", "consequat", "duis", "aute", "irure", "in", "reprehenderit", "voluptate",
+    "velit", "esse", "cillum", "eu", "fugiat", "nulla", "pariatur", "excepteur", "sint", "occaecat",
+    "cupidatat", "non", "proident", "sunt", "culpa", "qui", "officia", "deserunt", "mollit", "anim",
+    "id", "est", "laborum",
+];
@@ -0,0 +1,40 @@
+use super::LocaleData;
+
+pub(super) const FR_FR: LocaleData = LocaleData {
+    first_names: &[
+        "Gabriel", "Louise", "Léo", "Jade", "Raphaël", "Emma", "Arthur", "Ambre", "Louis", "Alice",
+        "Jules", "Rose", "Adam", "Anna", "Maël", "Mia", "Lucas", "Léa", "Hugo", "Chloé",
+        "Nathan", "Camille", "Thomas", "Manon", "Nicolas", "Julie", "Antoine", "Céline", "Pierre", "Sophie",
+    ],
+    last_names: &[
+        "Martin", "Bernard", "Thomas", "Petit", "Robert", "Richard", "Durand", "Dubois", "Moreau", "Laurent",
+        "Simon", "Michel", "Lefèvre", "Leroy", "Roux", "David", "Bertrand", "Morel", "Fournier", "Girard",
+        "Bonnet", "Dupont", "Lambert", "Fontaine", "Rousseau", "Vincent", "Muller", "Lefebvre", "Faure", "André",
+    ],
+    full_name_format: "{first} {last}",
+    street_names: &[
+        "de la Paix", "Victor Hugo", "Jean Jaurès", "de la République", "du Général de Gaulle", "Pasteur",
+        "de la Gare", "des Écoles", "du Moulin", "de l'Église", "Gambetta", "Voltaire", "des Lilas",
+        "Nationale", "du Château", "Émile Zola", "des Tilleuls", "de Verdun", "Carnot", "du Marché",
+    ],
+    street_suffixes: &["rue", "avenue", "boulevard", "place", "impasse", "allée", "chemin", "quai"],
+    street_formats: &["% {suffix} {name}", "%# {suffix} {name}", "%## {suffix} {name}", "% bis {suffix} {name}"],
+    street_name_format: "{suffix} {name}",
+    cities: &[
+        "Paris", "Marseille", "Lyon", "Toulouse", "Nice", "Nantes", "Montpellier", "Strasbourg", "Bordeaux",
+        "Lille", "Rennes", "Reims", "Toulon", "Saint-Étienne", "Le Havre", "Grenoble", "Dijon", "Angers",
+        "Nîmes", "Clermont-Ferrand", "Le Mans", "Aix-en-Provence", "Brest", "Tours", "Amiens",
+    ],
+    states: &[
+        "Auvergne-Rhône-Alpes", "Bourgogne-Franche-Comté", "Bretagne", "Centre-Val de Loire", "Corse",
+        "Grand Est", "Hauts-de-France", "Île-de-France", "Normandie", "Nouvelle-Aquitaine", "Occitanie",
+        "Pays de la Loire", "Provence-Alpes-Côte d'Azur",
+    ],
+    country: "France",
+    postcode_formats: &["%####"],
+    phone_country_code: "33",
+    phone_national_format: "6########",
+    company_suffixes: &["SA", "SARL", "SAS", "SNC", "et Fils"],
+    email_domains: &["gmail.com", "orange.fr", "free.fr", "laposte.net", "sfr.fr", "hotmail.fr"],
+    domain_suffixes: &["fr", "com", "net", "eu"],
+};
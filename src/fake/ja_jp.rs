@@ -0,0 +1,36 @@
+use super::LocaleData;
+
+// Japanese names are written in kanji, so there are no email domains here:
+// `internet.*` providers fall back to a locale with ASCII names. Company names
+// also fall back, since the legal form precedes the name (株式会社…). A street
+// name is the district alone: `丁目` only follows a block number.
+pub(super) const JA_JP: LocaleData = LocaleData {
+    first_names: &[
+        "蓮", "陽葵", "湊", "凛", "蒼", "詩", "樹", "結菜", "大翔", "陽菜",
+        "悠真", "芽依", "翔", "葵", "颯", "紬", "健太", "美咲", "翔太", "さくら",
+    ],
+    last_names: &[
+        "佐藤", "鈴木", "高橋", "田中", "伊藤", "渡辺", "山本", "中村", "小林", "加藤",
+        "吉田", "山田", "佐々木", "山口", "松本", "井上", "木村", "林", "斎藤", "清水",
+    ],
+    full_name_format: "{last}{first}",
+    street_names: &["銀座", "新宿", "渋谷", "本町", "中央", "栄", "梅田", "天神", "大通", "元町", "桜木町", "緑町"],
+    street_suffixes: &["丁目"],
+    street_formats: &["{name}%{suffix}%-%#", "{name}%{suffix}%#-%"],
+    street_name_format: "{name}",
+    cities: &[
+        "東京", "横浜", "大阪", "名古屋", "札幌", "福岡", "神戸", "川崎", "京都", "さいたま",
+        "広島", "仙台", "千葉", "北九州", "堺", "浜松", "新潟", "熊本", "相模原", "岡山",
+    ],
+    states: &[
+        "北海道", "青森県", "宮城県", "東京都", "神奈川県", "千葉県", "埼玉県", "愛知県", "静岡県", "京都府",
+        "大阪府", "兵庫県", "広島県", "福岡県", "熊本県", "沖縄県",
+    ],
+    country: "日本",
+    postcode_formats: &["###-####"],
+    phone_country_code: "81",
+    phone_national_format: "90########",
+    company_suffixes: &[],
+    email_domains: &[],
+    domain_suffixes: &[],
+};
@@ -0,0 +1,507 @@
+//! Offline fake-data providers, enabled by the `faker` feature.
+//!
+//! A `String` field configured with `{"fake": "name.first"}` is filled by the
+//! named provider from word lists bundled with the crate, so no network access
+//! or external data files are needed.
+//!
+//! Data is localized. The locale comes from the field (`"locale": "de_DE"`),
+//! else from the nearest enclosing `"$locale"` key or the [`SampleContext`],
+//! else `en_US`. When a locale lacks the data a provider needs, the provider
+//! falls back to another locale of the same language and then to `en_US`, so
+//! the same config always resolves to the same word lists.
+//!
+//! [`SampleContext`]: crate::SampleContext
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+
+use crate::context::Locale;
+
+mod de_de;
+mod en_au;
+mod en_gb;
+mod en_us;
+mod fr_fr;
+mod ja_jp;
+
+// Word lists and formats for one locale. An empty list means the locale has
+// no data for the providers that need it.
+//
+// Formats use `#` for a digit, `%` for a non-zero digit and `?` for an
+// uppercase letter.
+pub(crate) struct LocaleData {
+    first_names: &'static [&'static str],
+    last_names: &'static [&'static str],
+    // `{first}` and `{last}` are replaced.
+    full_name_format: &'static str,
+    street_names: &'static [&'static str],
+    street_suffixes: &'static [&'static str],
+    // `{name}` and `{suffix}` are replaced after the format is filled.
+    street_formats: &'static [&'static str],
+    // The street without the house number, with `{name}` and `{suffix}`.
+    street_name_format: &'static str,
+    cities: &'static [&'static str],
+    states: &'static [&'static str],
+    country: &'static str,
+    postcode_formats: &'static [&'static str],
+    phone_country_code: &'static str,
+    phone_national_format: &'static str,
+    company_suffixes: &'static [&'static str],
+    email_domains: &'static [&'static str],
+    domain_suffixes: &'static [&'static str],
+}
+
+/// A fake-data provider, addressed in configs as `category.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    NameFirst,
+    NameLast,
+    NameFull,
+    AddressStreet,
+    AddressStreetName,
+    AddressCity,
+    AddressState,
+    AddressPostcode,
+    AddressCountry,
+    InternetEmail,
+    InternetUsername,
+    InternetDomain,
+    PhoneE164,
+    CompanyName,
+    LoremWord,
+    LoremSentence,
+    LoremParagraph,
+}
+
+/// Locales with bundled data. The first one is the final fallback.
+pub const LOCALES: &[&str] = &["en_US", "en_GB", "en_AU", "de_DE", "fr_FR", "ja_JP"];
+
+fn locale_data(code: &str) -> &'static LocaleData {
+    match code {
+        "en_GB" => &en_gb::EN_GB,
+        "en_AU" => &en_au::EN_AU,
+        "de_DE" => &de_de::DE_DE,
+        "fr_FR" => &fr_fr::FR_FR,
+        "ja_JP" => &ja_jp::JA_JP,
+        _ => &en_us::EN_US,
+    }
+}
+
+/// Returns the bundled locales to try for `locale`, most specific first:
+/// the exact locale, the other locales of its language, then `en_US`.
+///
+/// `de-de` and `de_DE` are equivalent. A locale whose language has no data
+/// at all (`xx_YY`) is an error rather than a silent switch to English.
+pub fn locale_chain(locale: &str) -> Result<Vec<&'static str>, String> {
+    let normalized = locale.replace('-', "_");
+    let language = normalized.split('_').next().unwrap_or_default().to_lowercase();
+    let mut chain: Vec<&'static str> = LOCALES
+        .iter()
+        .copied()
+        .filter(|code| code.eq_ignore_ascii_case(&normalized))
+        .collect();
+    for code in LOCALES {
+        if code.starts_with(&format!("{}_", language)) && !chain.contains(code) {
+            chain.push(code);
+        }
+    }
+    if chain.is_empty() {
+        return Err(format!("Unknown locale '{}'; available locales are {}", locale, LOCALES.join(", ")));
+    }
+    if !chain.contains(&LOCALES[0]) {
+        chain.push(LOCALES[0]);
+    }
+    Ok(chain)
+}
+
+const PROVIDERS: &[(&str, Provider)] = &[
+    ("name.first", Provider::NameFirst),
+    ("name.last", Provider::NameLast),
+    ("name.full", Provider::NameFull),
+    ("address.street", Provider::AddressStreet),
+    ("address.street_name", Provider::AddressStreetName),
+    ("address.city", Provider::AddressCity),
+    ("address.state", Provider::AddressState),
+    ("address.postcode", Provider::AddressPostcode),
+    ("address.country", Provider::AddressCountry),
+    ("internet.email", Provider::InternetEmail),
+    ("internet.username", Provider::InternetUsername),
+    ("internet.domain", Provider::InternetDomain),
+    ("phone.e164", Provider::PhoneE164),
+    ("company.name", Provider::CompanyName),
+    ("lorem.word", Provider::LoremWord),
+    ("lorem.sentence", Provider::LoremSentence),
+    ("lorem.paragraph", Provider::LoremParagraph),
+];
+
+impl Provider {
+    /// Looks up a provider by its config name, e.g. `"address.city"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        PROVIDERS.iter().find(|(n, _)| *n == name).map(|(_, provider)| *provider)
+    }
+
+    /// The config names of all providers.
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        PROVIDERS.iter().map(|(name, _)| *name)
+    }
+
+    /// The config name of this provider.
+    pub fn name(&self) -> &'static str {
+        PROVIDERS.iter().find(|(_, p)| p == self).map(|(n, _)| *n).unwrap()
+    }
+
+    // Whether `data` has everything this provider needs.
+    fn is_provided_by(&self, data: &LocaleData) -> bool {
+        match self {
+            Provider::NameFirst => !data.first_names.is_empty(),
+            Provider::NameLast => !data.last_names.is_empty(),
+            Provider::NameFull => !data.first_names.is_empty() && !data.last_names.is_empty(),
+            Provider::AddressStreet => {
+                !data.street_names.is_empty() && !data.street_suffixes.is_empty() && !data.street_formats.is_empty()
+            }
+            Provider::AddressStreetName => {
+                !data.street_names.is_empty() && !data.street_suffixes.is_empty() && !data.street_name_format.is_empty()
+            }
+            Provider::AddressCity => !data.cities.is_empty(),
+            Provider::AddressState => !data.states.is_empty(),
+            Provider::AddressPostcode => !data.postcode_formats.is_empty(),
+            Provider::AddressCountry => !data.country.is_empty(),
+            Provider::InternetEmail | Provider::InternetUsername => {
+                !data.first_names.is_empty() && !data.last_names.is_empty() && !data.email_domains.is_empty()
+            }
+            Provider::InternetDomain => !data.last_names.is_empty() && !data.domain_suffixes.is_empty(),
+            Provider::PhoneE164 => !data.phone_national_format.is_empty(),
+            Provider::CompanyName => !data.last_names.is_empty() && !data.company_suffixes.is_empty(),
+            Provider::LoremWord | Provider::LoremSentence | Provider::LoremParagraph => true,
+        }
+    }
+
+    /// Generates a value using the first locale in `chain` that provides it.
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R, chain: &[&str]) -> String {
+        let data = chain
+            .iter()
+            .map(|code| locale_data(code))
+            .find(|data| self.is_provided_by(data))
+            .unwrap_or(&en_us::EN_US);
+        match self {
+            Provider::NameFirst => pick(rng, data.first_names),
+            Provider::NameLast => pick(rng, data.last_names),
+            Provider::NameFull => data
+                .full_name_format
+                .replace("{first}", &pick(rng, data.first_names))
+                .replace("{last}", &pick(rng, data.last_names)),
+            Provider::AddressStreet => {
+                let format = pick(rng, data.street_formats);
+                fill_format(rng, &format)
+                    .replace("{name}", &pick(rng, data.street_names))
+                    .replace("{suffix}", &pick(rng, data.street_suffixes))
+            }
+            Provider::AddressStreetName => data
+                .street_name_format
+                .replace("{name}", &pick(rng, data.street_names))
+                .replace("{suffix}", &pick(rng, data.street_suffixes)),
+            Provider::AddressCity => pick(rng, data.cities),
+            Provider::AddressState => pick(rng, data.states),
+            Provider::AddressPostcode => {
+                let format = pick(rng, data.postcode_formats);
+                fill_format(rng, &format)
+            }
+            Provider::AddressCountry => data.country.to_string(),
+            Provider::InternetEmail => format!(
+                "{}.{}@{}",
+                ascii_slug(&pick(rng, data.first_names)),
+                ascii_slug(&pick(rng, data.last_names)),
+                pick(rng, data.email_domains)
+            ),
+            Provider::InternetUsername => format!(
+                "{}{}{}",
+                ascii_slug(&pick(rng, data.first_names)),
+                ascii_slug(&pick(rng, data.last_names)).chars().next().unwrap_or('x'),
+                rng.gen_range(1..100)
+            ),
+            Provider::InternetDomain => format!("{}.{}", ascii_slug(&pick(rng, data.last_names)), pick(rng, data.domain_suffixes)),
+            Provider::PhoneE164 => format!("+{}{}", data.phone_country_code, fill_format(rng, data.phone_national_format)),
+            Provider::CompanyName => match rng.gen_range(0..3) {
+                0 => format!("{} {}", pick(rng, data.last_names), pick(rng, data.company_suffixes)),
+                1 => format!("{}-{}", pick(rng, data.last_names), pick(rng, data.last_names)),
+                _ => format!(
+                    "{}, {} {}",
+                    pick(rng, data.last_names),
+                    pick(rng, data.last_names),
+                    pick(rng, data.company_suffixes)
+                ),
+            },
+            Provider::LoremWord => pick(rng, en_us::LOREM_WORDS),
+            Provider::LoremSentence => lorem_sentence(rng),
+            Provider::LoremParagraph => {
+                let count = rng.gen_range(3..=6);
+                (0..count).map(|_| lorem_sentence(rng)).collect::<Vec<_>>().join(" ")
+            }
+        }
+    }
+}
+
+/// A parsed `{"fake": "...", "locale": "..."}` field configuration.
+#[derive(Debug, Clone)]
+pub struct FakeSpec {
+    provider: Provider,
+    locale_chain: Option<Vec<&'static str>>,
+}
+
+impl FakeSpec {
+    pub fn parse(map: &Map<String, Value>, field_name: &str) -> Result<Self, String> {
+        let name = map
+            .get("fake")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("'fake' for field '{}' must be a string", field_name))?;
+        let locale = match map.get("locale") {
+            Some(Value::String(locale)) => Some(locale.as_str()),
+            Some(_) => return Err(format!("'locale' for field '{}' must be a string", field_name)),
+            None => None,
+        };
+        Self::new(name, locale, field_name)
+    }
+
+    /// Draws from the provider called `name`, such as `internet.email`, in
+    /// `locale` or else the locale in effect when sampling.
+    pub fn new(name: &str, locale: Option<&str>, field_name: &str) -> Result<Self, String> {
+        let provider = Provider::from_name(name).ok_or_else(|| {
+            let known: Vec<&str> = PROVIDERS.iter().map(|(n, _)| *n).collect();
+            format!("Unknown fake provider '{}' for field '{}'; expected one of {}", name, field_name, known.join(", "))
+        })?;
+        let locale_chain = match locale {
+            Some(locale) => Some(locale_chain(locale).map_err(|e| format!("{} (field '{}')", e, field_name))?),
+            None => None,
+        };
+        Ok(FakeSpec { provider, locale_chain })
+    }
+
+    /// Generates a value. `default_locale`, whose chain was resolved when it
+    /// was set, applies when the field sets none.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, default_locale: Option<&Locale>) -> Result<String, String> {
+        match (&self.locale_chain, default_locale) {
+            (Some(chain), _) => Ok(self.provider.generate(rng, chain)),
+            (None, Some(locale)) => Ok(self.provider.generate(rng, locale.chain()?)),
+            (None, None) => Ok(self.provider.generate(rng, &LOCALES[..1])),
+        }
+    }
+}
+
+fn pick<R: Rng + ?Sized>(rng: &mut R, words: &[&str]) -> String {
+    words.choose(rng).copied().unwrap_or_default().to_string()
+}
+
+fn fill_format<R: Rng + ?Sized>(rng: &mut R, format: &str) -> String {
+    format
+        .chars()
+        .map(|c| match c {
+            '#' => char::from(b'0' + rng.gen_range(0..10u8)),
+            '%' => char::from(b'0' + rng.gen_range(1..10u8)),
+            '?' => char::from(b'A' + rng.gen_range(0..26u8)),
+            c => c,
+        })
+        .collect()
+}
+
+fn lorem_sentence<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let count = rng.gen_range(6..=12);
+    let words: Vec<String> = (0..count).map(|_| pick(rng, en_us::LOREM_WORDS)).collect();
+    let sentence = words.join(" ");
+    let mut chars = sentence.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+        None => String::new(),
+    }
+}
+
+// Lowercase ASCII form of a name for use in emails and domains.
+fn ascii_slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        match c {
+            'a'..='z' | '0'..='9' => slug.push(c),
+            'ä' => slug.push_str("ae"),
+            'ö' => slug.push_str("oe"),
+            'ü' => slug.push_str("ue"),
+            'ß' => slug.push_str("ss"),
+            'à' | 'á' | 'â' | 'ã' | 'å' => slug.push('a'),
+            'ç' => slug.push('c'),
+            'è' | 'é' | 'ê' | 'ë' => slug.push('e'),
+            'ì' | 'í' | 'î' | 'ï' => slug.push('i'),
+            'ñ' => slug.push('n'),
+            'ò' | 'ó' | 'ô' | 'õ' | 'ø' => slug.push('o'),
+            'ù' | 'ú' | 'û' => slug.push('u'),
+            _ => {}
+        }
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{SampleContext, Sampleable};
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Contact {
+        name: String,
+        street: String,
+        street_name: String,
+        email: String,
+    }
+
+    fn contacts(locale: &str) -> Vec<Contact> {
+        contacts_with_seed(locale, 5)
+    }
+
+    fn contacts_with_seed(locale: &str, seed: u64) -> Vec<Contact> {
+        let config = json!({
+            "name": { "fake": "name.full" },
+            "street": { "fake": "address.street" },
+            "street_name": { "fake": "address.street_name" },
+            "email": { "fake": "internet.email" },
+            "$locale": locale
+        });
+        let mut ctx = SampleContext::new().with_seed(seed);
+        Contact::sample_many_with_context(config.as_object().unwrap(), 50, &mut ctx).unwrap()
+    }
+
+    #[test]
+    fn names_and_streets_follow_the_locale() {
+        for contact in contacts("en_US") {
+            let (first, last) = contact.name.split_once(' ').unwrap();
+            assert!(en_us::EN_US.first_names.contains(&first) && en_us::EN_US.last_names.contains(&last));
+            let (name, suffix) = contact.street_name.split_once(' ').unwrap();
+            assert!(en_us::EN_US.street_names.contains(&name) && en_us::EN_US.street_suffixes.contains(&suffix));
+            assert!(contact.street.starts_with(|c: char| c.is_ascii_digit()), "{}", contact.street);
+        }
+        for contact in contacts("de_DE") {
+            assert!(de_de::DE_DE.street_suffixes.iter().any(|suffix| contact.street_name.ends_with(suffix)));
+            assert!(!contact.street_name.contains(' '));
+            assert!(de_de::DE_DE.street_suffixes.iter().any(|suffix| contact.street.contains(&format!("{} ", suffix))));
+        }
+        for contact in contacts("fr_FR") {
+            let (suffix, name) = contact.street_name.split_once(' ').unwrap();
+            assert!(fr_fr::FR_FR.street_suffixes.contains(&suffix) && fr_fr::FR_FR.street_names.contains(&name));
+        }
+        for contact in contacts("ja_JP") {
+            assert!(!contact.name.contains(' '));
+            assert!(ja_jp::JA_JP.last_names.iter().any(|last| contact.name.starts_with(last)));
+            assert!(ja_jp::JA_JP.street_names.contains(&contact.street_name.as_str()), "{}", contact.street_name);
+            assert!(ja_jp::JA_JP.street_names.iter().any(|name| contact.street.starts_with(name)) && contact.street.contains("丁目"));
+        }
+    }
+
+    #[test]
+    fn missing_providers_fall_back_to_en_us() {
+        // ja_JP has no email domains and no company suffixes.
+        assert_eq!(locale_chain("ja_JP").unwrap(), ["ja_JP", "en_US"]);
+        for contact in contacts("ja_JP") {
+            let (_, domain) = contact.email.split_once('@').unwrap();
+            assert!(en_us::EN_US.email_domains.contains(&domain), "{}", contact.email);
+        }
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..20 {
+            let company = Provider::CompanyName.generate(&mut rng, &["ja_JP", "en_US"]);
+            assert!(en_us::EN_US.last_names.iter().any(|last| company.starts_with(last)), "{}", company);
+        }
+        // Locales of the same language come before en_US.
+        assert_eq!(locale_chain("en-au").unwrap(), ["en_AU", "en_US", "en_GB"]);
+        assert_eq!(locale_chain("de").unwrap(), ["de_DE", "en_US"]);
+    }
+
+    fn parse(config: serde_json::Value) -> Result<FakeSpec, String> {
+        FakeSpec::parse(config.as_object().unwrap(), "name")
+    }
+
+    #[test]
+    fn parse_rejects_invalid_configs() {
+        assert!(parse(json!({ "fake": "name.first", "locale": "de-de" })).is_ok());
+        let cases = [
+            (json!({ "fake": 3 }), "'fake' for field 'name' must be a string".to_string()),
+            (
+                json!({ "fake": "name.middle" }),
+                format!("Unknown fake provider 'name.middle' for field 'name'; expected one of {}", Provider::names().collect::<Vec<_>>().join(", ")),
+            ),
+            (json!({ "fake": "name.first", "locale": 5 }), "'locale' for field 'name' must be a string".to_string()),
+            (
+                json!({ "fake": "name.first", "locale": "xx_YY" }),
+                format!("Unknown locale 'xx_YY'; available locales are {} (field 'name')", LOCALES.join(", ")),
+            ),
+        ];
+        for (config, error) in cases {
+            assert_eq!(parse(config).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn every_provider_generates_in_every_locale() {
+        let mut rng = StdRng::seed_from_u64(9);
+        for name in Provider::names() {
+            let provider = Provider::from_name(name).unwrap();
+            assert_eq!(provider.name(), name);
+            for locale in LOCALES {
+                let value = provider.generate(&mut rng, &locale_chain(locale).unwrap());
+                assert!(!value.trim().is_empty(), "{} in {}", name, locale);
+                let ok = match provider {
+                    Provider::InternetEmail => value.contains('@') && value.is_ascii(),
+                    Provider::InternetUsername | Provider::InternetDomain => value.is_ascii() && !value.contains(' '),
+                    Provider::PhoneE164 => value.starts_with('+') && value[1..].chars().all(|c| c.is_ascii_digit()),
+                    Provider::AddressCountry => value == locale_data(locale).country,
+                    Provider::AddressPostcode => value.chars().any(|c| c.is_ascii_digit()),
+                    Provider::LoremSentence => value.ends_with('.') && value.starts_with(char::is_uppercase),
+                    Provider::LoremParagraph => value.matches(". ").count() >= 2,
+                    Provider::LoremWord => en_us::LOREM_WORDS.contains(&value.as_str()),
+                    Provider::NameFirst => locale_data(locale).first_names.contains(&value.as_str()),
+                    Provider::NameLast => locale_data(locale).last_names.contains(&value.as_str()),
+                    Provider::AddressCity => locale_data(locale).cities.contains(&value.as_str()),
+                    Provider::AddressState => locale_data(locale).states.contains(&value.as_str()),
+                    Provider::NameFull | Provider::AddressStreet | Provider::AddressStreetName | Provider::CompanyName => true,
+                };
+                assert!(ok, "{} in {} gave '{}'", name, locale, value);
+            }
+        }
+        assert_eq!(Provider::from_name("name"), None);
+    }
+
+    #[test]
+    fn ascii_slug_transliterates_and_drops_other_characters() {
+        assert_eq!(ascii_slug("Jürgen"), "juergen");
+        assert_eq!(ascii_slug("Schäfer-Groß"), "schaefergross");
+        assert_eq!(ascii_slug("Élodie Garçon"), "elodiegarcon");
+        assert_eq!(ascii_slug("O'Brien 2"), "obrien2");
+        assert_eq!(ascii_slug("佐藤"), "");
+    }
+
+    #[test]
+    fn seeded_contexts_repeat_fake_data() {
+        assert_eq!(contacts_with_seed("de_DE", 1), contacts_with_seed("de_DE", 1));
+        assert_ne!(contacts_with_seed("de_DE", 1), contacts_with_seed("de_DE", 2));
+    }
+
+    #[test]
+    fn locales_are_resolved_before_sampling() {
+        let config = json!({
+            "name": { "fake": "name.full" },
+            "street": { "fake": "address.street" },
+            "street_name": { "fake": "address.street_name" },
+            "email": { "fake": "internet.email" },
+            "$locale": "xx_YY"
+        });
+        let error = format!("Unknown locale 'xx_YY'; available locales are {}", LOCALES.join(", "));
+        assert_eq!(Contact::compile_sampler(config.as_object().unwrap()).unwrap_err(), error);
+
+        // The locale of a context is only needed once fake data is drawn.
+        let mut config = config.as_object().cloned().unwrap();
+        config.remove("$locale");
+        let sampler = Contact::compile_sampler(&config).unwrap();
+        assert_eq!(sampler.sample_with_context(&mut SampleContext::new().with_locale("xx_YY")).unwrap_err(), error);
+        let contact = sampler.sample_with_context(&mut SampleContext::new().with_locale("de-DE")).unwrap();
+        assert!(de_de::DE_DE.street_suffixes.iter().any(|suffix| contact.street_name.ends_with(suffix)));
+    }
+}
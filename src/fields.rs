@@ -0,0 +1,407 @@
+//! Values of already-sampled fields, and the order in which fields are sampled.
+//!
+//! Fields whose config refers to siblings (for example a `template` that
+//! interpolates `{account}`, or an amount with a `currency_field`) must be
+//! sampled after those siblings. The derive asks [`field_order`] for an order
+//! at runtime, samples the fields in that order and records each primitive
+//! result in a [`FieldValues`] so later fields can read it.
+//!
+//! `FieldValues` is a fixed-size buffer with a slot per field, in declaration
+//! order. Templates, `currency_field` and constraints are bound to the slots
+//! they read (see [`slot_of`]), so reading a sibling is an index, not a
+//! lookup by name.
+
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+
+use crate::template::Template;
+
+/// The sampled value of a primitive field, as sibling fields read it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    /// A `None`, or a float that is not finite.
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Float(f64),
+    /// A string, or a decimal in its text form.
+    String(String),
+}
+
+impl Scalar {
+    /// The value as a number, if it is one or is a string holding one
+    /// (decimal fields are recorded as strings).
+    pub(crate) fn as_number(&self) -> Option<f64> {
+        match self {
+            Scalar::Integer(n) => Some(*n as f64),
+            Scalar::Float(n) => Some(*n),
+            Scalar::String(s) => s.parse().ok(),
+            Scalar::Null | Scalar::Bool(_) => None,
+        }
+    }
+
+    /// Converts a JSON value, if it is a primitive.
+    pub(crate) fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(Scalar::Null),
+            Value::Bool(b) => Some(Scalar::Bool(*b)),
+            Value::Number(n) => Some(match (n.as_i64(), n.as_u64()) {
+                (Some(n), _) => Scalar::Integer(n.into()),
+                (None, Some(n)) => Scalar::Integer(n.into()),
+                _ => n.as_f64().map_or(Scalar::Null, Scalar::Float),
+            }),
+            Value::String(s) => Some(Scalar::String(s.clone())),
+            Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+}
+
+/// Formats the value like its JSON form, with strings unquoted and `Null`
+/// empty.
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scalar::Null => Ok(()),
+            Scalar::Bool(b) => write!(f, "{}", b),
+            Scalar::Integer(n) => write!(f, "{}", n),
+            Scalar::Float(n) => match Number::from_f64(*n) {
+                Some(n) => write!(f, "{}", n),
+                None => Ok(()),
+            },
+            Scalar::String(s) => f.write_str(s),
+        }
+    }
+}
+
+/// Sampled values of the primitive fields of the struct being built, in a
+/// buffer with a slot per field.
+#[derive(Debug)]
+pub struct FieldValues<'a> {
+    names: &'a [&'a str],
+    slots: &'a mut [Option<Scalar>],
+}
+
+impl<'a> FieldValues<'a> {
+    /// Values of the fields `names`, kept in `slots`, which has a slot per
+    /// name. Generated code passes a buffer on the stack.
+    pub fn new(names: &'a [&'a str], slots: &'a mut [Option<Scalar>]) -> Self {
+        debug_assert_eq!(names.len(), slots.len());
+        FieldValues { names, slots }
+    }
+
+    /// Records the value of the field in slot `index`.
+    pub fn set(&mut self, index: usize, value: Scalar) {
+        self.slots[index] = Some(value);
+    }
+
+    /// Returns the recorded value of the field in slot `index`, if it was
+    /// sampled already.
+    pub fn get(&self, index: usize) -> Option<&Scalar> {
+        self.slots.get(index).and_then(Option::as_ref)
+    }
+
+    /// The names of the fields, by slot.
+    pub fn names(&self) -> &[&str] {
+        self.names
+    }
+}
+
+/// The slot of the field `name` among `names`, the fields of a struct or
+/// variant in declaration order.
+pub fn slot_of(names: &[&str], name: &str) -> Option<usize> {
+    names.iter().position(|other| *other == name)
+}
+
+/// A field type whose sampled value can be referenced by sibling fields, and
+/// which can be read back from a referenced dataset value or a picked value.
+pub trait FieldValue: Sized {
+    fn to_scalar(&self) -> Scalar;
+
+    /// Converts a JSON value into the field type, if it fits.
+    fn from_field_value(value: &Value) -> Option<Self>;
+
+    /// Converts a value of a compiled pick list into the field type, if it
+    /// fits.
+    fn from_scalar(value: &Scalar) -> Option<Self>;
+}
+
+impl FieldValue for String {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::String(self.clone())
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        match value {
+            Scalar::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for bool {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::Bool(*self)
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        match value {
+            Scalar::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_integer_field_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn to_scalar(&self) -> Scalar {
+                    Scalar::Integer(*self as i128)
+                }
+
+                fn from_field_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(n) => n
+                            .as_i64()
+                            .and_then(|n| <$ty>::try_from(n).ok())
+                            .or_else(|| n.as_u64().and_then(|n| <$ty>::try_from(n).ok())),
+                        Value::String(s) => s.parse().ok(),
+                        _ => None,
+                    }
+                }
+
+                fn from_scalar(value: &Scalar) -> Option<Self> {
+                    match value {
+                        Scalar::Integer(n) => <$ty>::try_from(*n).ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_field_value!(i32, i64, u32, u64, usize, isize);
+
+macro_rules! impl_float_field_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn to_scalar(&self) -> Scalar {
+                    let value = *self as f64;
+                    if value.is_finite() { Scalar::Float(value) } else { Scalar::Null }
+                }
+
+                fn from_field_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(n) => n.as_f64().map(|n| n as $ty),
+                        Value::String(s) => s.parse().ok(),
+                        _ => None,
+                    }
+                }
+
+                fn from_scalar(value: &Scalar) -> Option<Self> {
+                    match value {
+                        Scalar::Float(n) => Some(*n as $ty),
+                        Scalar::Integer(n) => Some(*n as $ty),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float_field_value!(f32, f64);
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn to_scalar(&self) -> Scalar {
+        self.as_ref().map_or(Scalar::Null, FieldValue::to_scalar)
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            value => T::from_field_value(value).map(Some),
+        }
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        match value {
+            Scalar::Null => Some(None),
+            value => T::from_scalar(value).map(Some),
+        }
+    }
+}
+
+impl<T: FieldValue> FieldValue for Box<T> {
+    fn to_scalar(&self) -> Scalar {
+        (**self).to_scalar()
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        T::from_field_value(value).map(Box::new)
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        T::from_scalar(value).map(Box::new)
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl FieldValue for rust_decimal::Decimal {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::String(self.to_string())
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => n.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        match value {
+            Scalar::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl FieldValue for bigdecimal::BigDecimal {
+    fn to_scalar(&self) -> Scalar {
+        Scalar::String(self.to_string())
+    }
+
+    fn from_field_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => n.to_string().parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn from_scalar(value: &Scalar) -> Option<Self> {
+        match value {
+            Scalar::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the sibling fields that the config of a field depends on.
+pub fn dependencies(value: &Value) -> Vec<String> {
+    let mut names = match value.get("template").and_then(|t| t.as_str()).map(Template::parse) {
+        Some(Ok(template)) => template.placeholders().map(|name| name.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    if let Some(currency_field) = value.get("currency_field").and_then(|c| c.as_str()) {
+        names.push(currency_field.to_string());
+    }
+    names
+}
+
+/// Returns the indices of `field_names` in an order where every field comes
+/// after the fields it depends on. Independent fields keep declaration order.
+pub fn field_order(config: &Map<String, Value>, field_names: &[&str]) -> Result<Vec<usize>, String> {
+    order_by_dependencies(field_names, |i| config.get(field_names[i]).map(dependencies).unwrap_or_default())
+}
+
+/// Like [`field_order`], with the dependencies of the field at index `i`
+/// given by `dependencies_of(i)`.
+pub fn order_by_dependencies(field_names: &[&str], dependencies_of: impl Fn(usize) -> Vec<String>) -> Result<Vec<usize>, String> {
+    let depends_on: Vec<Vec<usize>> = (0..field_names.len())
+        .map(|i| {
+            dependencies_of(i)
+                .iter()
+                .filter_map(|dep| field_names.iter().position(|other| other == dep))
+                .filter(|dep| *dep != i)
+                .collect()
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(field_names.len());
+    let mut placed = vec![false; field_names.len()];
+    while order.len() < field_names.len() {
+        let next = (0..field_names.len())
+            .find(|i| !placed[*i] && depends_on[*i].iter().all(|dep| placed[*dep]));
+        match next {
+            Some(i) => {
+                placed[i] = true;
+                order.push(i);
+            }
+            None => {
+                let cycle: Vec<&str> = (0..field_names.len())
+                    .filter(|i| !placed[*i])
+                    .map(|i| field_names[i])
+                    .collect();
+                return Err(format!("Fields {} depend on each other", cycle.join(", ")));
+            }
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn dependencies_come_from_templates_and_currency_fields() {
+        assert_eq!(dependencies(&json!({ "template": "{first}.{last}@{domain}" })), ["first", "last", "domain"]);
+        assert_eq!(dependencies(&json!({ "range": [1.0, 9.0], "currency_field": "currency" })), ["currency"]);
+        assert!(dependencies(&json!([1, 9])).is_empty());
+    }
+
+    #[test]
+    fn fields_come_after_their_dependencies() {
+        let config = config(json!({
+            "email": { "template": "{name}@{domain}" },
+            "name": ["ann"],
+            "amount": { "range": [1.0, 9.0], "currency_field": "currency" },
+            "currency": ["EUR"],
+            "domain": { "template": "{uuid}.com" }
+        }));
+        let order = field_order(&config, &["email", "name", "amount", "currency", "domain"]).unwrap();
+        assert_eq!(order, [1, 3, 2, 4, 0]);
+    }
+
+    #[test]
+    fn fields_may_refer_to_themselves() {
+        let config = config(json!({ "name": { "template": "{name}" } }));
+        assert_eq!(field_order(&config, &["name"]).unwrap(), [0]);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let config = config(json!({
+            "id": [1, 9],
+            "a": { "template": "{b}" },
+            "b": { "template": "{c}" },
+            "c": { "template": "{a}-{id}" }
+        }));
+        assert_eq!(field_order(&config, &["id", "a", "b", "c"]).unwrap_err(), "Fields a, b, c depend on each other");
+    }
+}
@@ -0,0 +1,148 @@
+//! Sampling for `f32` and `f64` fields.
+//!
+//! A two-element array `[low, high]` samples uniformly from the half-open
+//! range. The object form can round the result to a number of decimals,
+//! fixed or taken from a sibling currency field:
+//!
+//! ```json
+//! { "range": [10.0, 1000.0], "currency_field": "currency" }
+//! ```
+//!
+//! With decimals the range is inclusive and every value is a whole number of
+//! minor units, so a `JPY` amount never has a fractional part.
+
+use rand::Rng;
+use serde_json::Value;
+
+use crate::currency::CurrencyField;
+use crate::fields::FieldValues;
+
+/// A parsed floating-point field configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatSpec {
+    low: f64,
+    high: f64,
+    decimals: Option<Decimals>,
+}
+
+/// Where the number of decimals of a sampled float comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decimals {
+    /// `"decimals": 2`
+    Fixed(u32),
+    /// `"currency_field": "currency"`: the minor units of the ISO 4217 code
+    /// held by that sibling field.
+    Currency(CurrencyField),
+}
+
+impl FloatSpec {
+    /// Parses a floating-point field configuration.
+    pub fn parse(value: &Value, field_name: &str) -> Result<Self, String> {
+        match value {
+            Value::Array(range_array) => Self::from_range(range_array, None, field_name),
+            Value::Object(map) => {
+                let range_array = match map.get("range") {
+                    Some(Value::Array(range_array)) => range_array,
+                    Some(_) => return Err(format!("Range for field '{}' must be an array", field_name)),
+                    None => return Err(format!("Configuration for field '{}' is missing 'range'", field_name)),
+                };
+                let decimals = match map.get("decimals") {
+                    Some(decimals) => Some(
+                        decimals
+                            .as_u64()
+                            .and_then(|decimals| u32::try_from(decimals).ok())
+                            .ok_or_else(|| format!("Decimals for field '{}' must be an integer between 0 and 15", field_name))?,
+                    ),
+                    None => None,
+                };
+                let currency_field = match map.get("currency_field") {
+                    Some(Value::String(name)) => Some(name.clone()),
+                    Some(_) => return Err(format!("'currency_field' for field '{}' must be a string", field_name)),
+                    None => None,
+                };
+                Self::from_range(range_array, Self::decimals(decimals, currency_field, field_name)?, field_name)
+            }
+            _ => Err(format!("Configuration for field '{}' must be an array or an object", field_name)),
+        }
+    }
+
+    fn from_range(range_array: &[Value], decimals: Option<Decimals>, field_name: &str) -> Result<Self, String> {
+        if range_array.len() != 2 {
+            return Err(format!("Range array for field '{}' must have exactly two elements", field_name));
+        }
+        match (range_array[0].as_f64(), range_array[1].as_f64()) {
+            (Some(low), Some(high)) => Self::new(low, high, decimals, field_name),
+            _ => Err(format!("Invalid range values for field '{}'", field_name)),
+        }
+    }
+
+    /// The range `[low, high)`, or `[low, high]` rounded to `decimals`.
+    pub fn new(low: f64, high: f64, decimals: Option<Decimals>, field_name: &str) -> Result<Self, String> {
+        if !(low < high || (decimals.is_some() && low == high)) {
+            return Err(format!("Range for field '{}' is empty", field_name));
+        }
+        Ok(FloatSpec { low, high, decimals })
+    }
+
+    /// Combines the `decimals` and `currency_field` options, which exclude
+    /// each other.
+    pub fn decimals(decimals: Option<u32>, currency_field: Option<String>, field_name: &str) -> Result<Option<Decimals>, String> {
+        match (decimals, currency_field) {
+            (Some(_), Some(_)) => Err(format!("Field '{}' sets both 'decimals' and 'currency_field'", field_name)),
+            (Some(decimals), None) if decimals <= 15 => Ok(Some(Decimals::Fixed(decimals))),
+            (Some(_), None) => Err(format!("Decimals for field '{}' must be an integer between 0 and 15", field_name)),
+            (None, Some(field)) => Ok(Some(Decimals::Currency(CurrencyField::new(field)))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Checks that the range lies within `-max..=max`, the bounds of the
+    /// field's type.
+    pub fn check_bounds(&self, max: f64, field_name: &str) -> Result<(), String> {
+        if self.low < -max || self.high > max {
+            return Err(format!("Range for field '{}' must lie between {} and {}, the bounds of its type", field_name, -max, max));
+        }
+        Ok(())
+    }
+
+    /// Binds `currency_field` to its slot among `names`, the sibling fields
+    /// in declaration order.
+    pub fn bind(&mut self, names: &[&str]) {
+        if let Some(Decimals::Currency(currency)) = &mut self.decimals {
+            currency.bind(names);
+        }
+    }
+
+    /// The slot `currency_field` is bound to, if any.
+    pub fn currency_slot(&self) -> Option<usize> {
+        match &self.decimals {
+            Some(Decimals::Currency(currency)) => currency.slot(),
+            _ => None,
+        }
+    }
+
+    /// Samples a value. `values` holds the sibling fields sampled so far, for
+    /// `currency_field`.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, values: &FieldValues, field_name: &str) -> Result<f64, String> {
+        let decimals = match &self.decimals {
+            None => return Ok(rng.gen_range(self.low..self.high)),
+            Some(Decimals::Fixed(decimals)) => *decimals,
+            Some(Decimals::Currency(currency)) => currency.minor_units(values, field_name)?,
+        };
+        let factor = 10f64.powi(decimals as i32);
+        // Nudge the bounds so that values like 0.1 * 100 still land on 10.
+        let low = (self.low * factor - 1e-6).ceil() as i64;
+        let high = (self.high * factor + 1e-6).floor() as i64;
+        if low > high {
+            return Err(format!("Range for field '{}' contains no value with {} decimals", field_name, decimals));
+        }
+        Ok(rng.gen_range(low..=high) as f64 / factor)
+    }
+}
+
+/// Parses `value` and samples a float from it in one step.
+pub fn sample_float<R: Rng + ?Sized>(rng: &mut R, value: &Value, field_name: &str, values: &FieldValues) -> Result<f64, String> {
+    let mut spec = FloatSpec::parse(value, field_name)?;
+    spec.bind(values.names());
+    spec.sample(rng, values, field_name)
+}
@@ -0,0 +1,134 @@
+//! Sampling for integer fields.
+
+use rand::Rng;
+use serde_json::Value;
+
+use crate::context::FieldAt;
+use crate::sequence::SequenceSpec;
+use crate::SampleContext;
+
+/// A parsed integer field configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntSpec {
+    /// `[low, high]`: uniform over the half-open range.
+    Range(i64, i64),
+    /// `{"sequence": {"start": 1, "step": 1}}`: counts up across samples.
+    Sequence(SequenceSpec),
+}
+
+impl IntSpec {
+    /// Parses an integer field configuration.
+    pub fn parse(value: &Value, field_name: &str) -> Result<Self, String> {
+        match value {
+            Value::Array(range_array) => {
+                if range_array.len() != 2 {
+                    return Err(format!("Range array for field '{}' must have exactly two elements", field_name));
+                }
+                match (range_array[0].as_i64(), range_array[1].as_i64()) {
+                    (Some(start), Some(end)) => Self::range(start, end, field_name),
+                    _ => Err(format!("Invalid range values for field '{}'", field_name)),
+                }
+            }
+            Value::Object(map) if map.contains_key("sequence") => {
+                SequenceSpec::parse(&map["sequence"], field_name).map(IntSpec::Sequence)
+            }
+            _ => Err(format!("Configuration for field '{}' must be an array", field_name)),
+        }
+    }
+
+    /// The half-open range `[start, end)`.
+    pub fn range(start: i64, end: i64, field_name: &str) -> Result<Self, String> {
+        if start >= end {
+            return Err(format!("Range for field '{}' is empty", field_name));
+        }
+        Ok(IntSpec::Range(start, end))
+    }
+
+    /// Checks that the range, or the start of the sequence, lies between
+    /// `min` and `max`, the bounds of the field's type.
+    pub fn check_bounds(&self, min: i128, max: i128, field_name: &str) -> Result<(), String> {
+        let (low, high) = match self {
+            IntSpec::Range(start, end) => (*start, *end - 1),
+            IntSpec::Sequence(sequence) => (sequence.start(), sequence.start()),
+        };
+        if i128::from(low) < min || i128::from(high) > max {
+            return Err(format!("Range for field '{}' must lie between {} and {}, the bounds of its type", field_name, min, max));
+        }
+        Ok(())
+    }
+
+    /// Samples a value from the generator of `ctx`; sequences advance the
+    /// counter kept in it.
+    pub fn sample(&self, ctx: &mut SampleContext, field: FieldAt) -> i64 {
+        match self {
+            IntSpec::Range(start, end) => ctx.rng().gen_range(*start..*end),
+            IntSpec::Sequence(sequence) => sequence.next(ctx, field),
+        }
+    }
+}
+
+/// Converts a sampled integer to the field type. Fails when a sequence has
+/// counted past the bounds of the type.
+pub fn fit<T: TryFrom<i64>>(value: i64, field_name: &str) -> Result<T, String> {
+    T::try_from(value).map_err(|_| format!("Value {} of field '{}' does not fit its type", value, field_name))
+}
+
+/// Parses `value` and samples an integer from it in one step.
+pub fn sample_int(value: &Value, field_name: &str, ctx: &mut SampleContext) -> Result<i64, String> {
+    Ok(IntSpec::parse(value, field_name)?.sample(ctx, FieldAt::Config(field_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map, Value};
+
+    use crate::Sampleable;
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Reading {
+        count: u32,
+        level: i32,
+        ratio: f32,
+        codes: Vec<u32>,
+    }
+
+    fn config(changes: Value) -> Map<String, Value> {
+        let mut config = json!({ "count": [0, 10], "level": [-5, 5], "ratio": [0.0, 1.0], "codes": [1, 2] });
+        config.as_object_mut().unwrap().extend(changes.as_object().cloned().unwrap());
+        config.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn ranges_must_fit_the_field_type() {
+        for reading in Reading::sample_many(&config(json!({})), 20).unwrap() {
+            assert!(reading.count < 10 && (-5..5).contains(&reading.level) && reading.ratio < 1.0);
+            assert!(reading.codes.iter().all(|code| [1, 2].contains(code)));
+        }
+        let cases = [
+            (json!({ "count": [-1, 10] }), "Range for field 'count' must lie between 0 and 4294967295, the bounds of its type"),
+            (
+                json!({ "level": { "sequence": { "start": 3_000_000_000_i64 } } }),
+                "Range for field 'level' must lie between -2147483648 and 2147483647, the bounds of its type",
+            ),
+            (
+                json!({ "ratio": [0.0, 1e40] }),
+                "Range for field 'ratio' must lie between -340282346638528860000000000000000000000 and \
+                 340282346638528860000000000000000000000, the bounds of its type",
+            ),
+        ];
+        for (changes, error) in cases {
+            let config = config(changes);
+            assert_eq!(Reading::sample_many(&config, 1).unwrap_err(), error);
+            assert_eq!(Reading::sample_with_config(&config).unwrap_err(), error);
+        }
+        let error = Reading::sample_many(&config(json!({ "codes": [1, -1] })), 1).unwrap_err();
+        assert_eq!(error, "Value -1 for field 'codes' does not fit its type");
+    }
+
+    #[test]
+    fn sequences_fail_once_they_pass_the_bounds_of_the_type() {
+        let config = config(json!({ "level": { "sequence": { "start": 2147483646 } } }));
+        let error = Reading::sample_many(&config, 3).unwrap_err();
+        assert_eq!(error, "Value 2147483648 of field 'level' does not fit its type");
+    }
+}
@@ -0,0 +1,345 @@
+//! Reading configs from JSON, TOML and YAML files.
+//!
+//! Configs are JSON maps; TOML (with the `toml` feature) and YAML (with the
+//! `yaml` feature) documents are converted to the same map, so a config means
+//! the same whatever format it is written in:
+//!
+//! ```
+//! # use samplify_rs::Sampleable;
+//! # #[derive(Sampleable)]
+//! # enum Status { Active, Suspended { reason: String } }
+//! # #[derive(Sampleable)]
+//! # struct Address { street: String, city: String }
+//! # #[derive(Sampleable)]
+//! # struct User { name: String, age: u32, email: String, active: bool, address: Address, status: Status }
+//! # #[cfg(feature = "yaml")] {
+//! let config = samplify_rs::load::from_path("examples/configs/user.yaml")?;
+//! let user = User::sample_with_config(&config)?;
+//! # }
+//! # Ok::<(), String>(())
+//! ```
+//!
+//! [`from_path`] picks the format from the file extension. Parse errors name
+//! the file, line and column:
+//!
+//! ```text
+//! examples/configs/user.yaml, line 3, column 7: mapping values are not allowed in this context
+//! ```
+//!
+//! TOML dates and times become strings. Numbers that JSON cannot hold, such as
+//! `inf` or `nan`, are rejected with the key path that holds them. YAML merge
+//! keys (`<<: *base`) are applied; YAML tags are not supported.
+
+use std::fmt;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+/// A config file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    /// The format of a file with extension `.json`, `.toml`, `.yaml` or `.yml`.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        match extension.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(format!(
+                "Cannot tell the config format of '{}'; expected a .json, .toml, .yaml or .yml file",
+                path.display()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+        })
+    }
+}
+
+/// Reads the config in the file at `path`, in the format given by its
+/// extension.
+pub fn from_path(path: impl AsRef<Path>) -> Result<Map<String, Value>, String> {
+    let path = path.as_ref();
+    let format = Format::from_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read config file '{}': {}", path.display(), e))?;
+    parse_from(&text, format, &path.display().to_string())
+}
+
+/// Parses a config written in `format`.
+pub fn parse(text: &str, format: Format) -> Result<Map<String, Value>, String> {
+    parse_from(text, format, &format!("{} config", format))
+}
+
+// Parses `text`, naming `origin` in errors.
+fn parse_from(text: &str, format: Format, origin: &str) -> Result<Map<String, Value>, String> {
+    let value = match format {
+        Format::Json => parse_json(text, origin)?,
+        Format::Toml => parse_toml(text, origin)?,
+        Format::Yaml => parse_yaml(text, origin)?,
+    };
+    match value {
+        Value::Object(config) => Ok(config),
+        _ => Err(format!("{}: the config must be an object at the top level", origin)),
+    }
+}
+
+fn located(origin: &str, line: usize, column: usize, message: &str) -> String {
+    format!("{}, line {}, column {}: {}", origin, line, column, message)
+}
+
+fn parse_json(text: &str, origin: &str) -> Result<Value, String> {
+    serde_json::from_str(text).map_err(|e| {
+        let position = format!(" at line {} column {}", e.line(), e.column());
+        let message = e.to_string();
+        let message = message.strip_suffix(&position).unwrap_or(&message);
+        located(origin, e.line(), e.column(), message)
+    })
+}
+
+#[cfg(feature = "toml")]
+fn parse_toml(text: &str, origin: &str) -> Result<Value, String> {
+    let table: toml::Table = toml::from_str(text).map_err(|e| {
+        let message = e.message().trim().replace('\n', "; ");
+        match e.span() {
+            Some(span) => {
+                let before = &text[..span.start];
+                let line = before.matches('\n').count() + 1;
+                let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+                located(origin, line, column, &message)
+            }
+            None => format!("{}: {}", origin, message),
+        }
+    })?;
+    let mut path = Vec::new();
+    from_toml(toml::Value::Table(table), &mut path).map_err(|e| format!("{}: {}", origin, e))
+}
+
+#[cfg(not(feature = "toml"))]
+fn parse_toml(_text: &str, origin: &str) -> Result<Value, String> {
+    Err(format!("{}: TOML configs need the 'toml' feature of samplify-rs", origin))
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml(text: &str, origin: &str) -> Result<Value, String> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| match e.location() {
+        Some(location) => {
+            let position = format!(" at line {} column {}", location.line(), location.column());
+            let message = e.to_string();
+            let message = message.strip_suffix(&position).unwrap_or(&message).to_string();
+            located(origin, location.line(), location.column(), &message)
+        }
+        None => format!("{}: {}", origin, e),
+    })?;
+    value.apply_merge().map_err(|e| format!("{}: {}", origin, e))?;
+    let mut path = Vec::new();
+    from_yaml(value, &mut path).map_err(|e| format!("{}: {}", origin, e))
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml(_text: &str, origin: &str) -> Result<Value, String> {
+    Err(format!("{}: YAML configs need the 'yaml' feature of samplify-rs", origin))
+}
+
+// The dotted key path of the value being converted, for errors.
+#[cfg(any(feature = "toml", feature = "yaml"))]
+fn key_path(path: &[String]) -> String {
+    if path.is_empty() {
+        "the top level".to_string()
+    } else {
+        format!("'{}'", path.join("."))
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "yaml"))]
+fn from_float(float: f64, path: &[String]) -> Result<Value, String> {
+    serde_json::Number::from_f64(float)
+        .map(Value::Number)
+        .ok_or_else(|| format!("the number {} at {} cannot be used in a config", float, key_path(path)))
+}
+
+#[cfg(feature = "toml")]
+fn from_toml(value: toml::Value, path: &mut Vec<String>) -> Result<Value, String> {
+    Ok(match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => Value::from(integer),
+        toml::Value::Float(float) => from_float(float, path)?,
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    path.push(index.to_string());
+                    let item = from_toml(item, path);
+                    path.pop();
+                    item
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        toml::Value::Table(table) => {
+            let mut map = Map::new();
+            for (key, value) in table {
+                path.push(key.clone());
+                let value = from_toml(value, path)?;
+                path.pop();
+                map.insert(key, value);
+            }
+            Value::Object(map)
+        }
+    })
+}
+
+#[cfg(feature = "yaml")]
+fn from_yaml(value: serde_yaml::Value, path: &mut Vec<String>) -> Result<Value, String> {
+    use serde_yaml::Value as Yaml;
+
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(boolean) => Value::Bool(boolean),
+        Yaml::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(integer), _, _) => Value::from(integer),
+            (None, Some(integer), _) => Value::from(integer),
+            (None, None, Some(float)) => from_float(float, path)?,
+            (None, None, None) => unreachable!("YAML numbers are integers or floats"),
+        },
+        Yaml::String(string) => Value::String(string),
+        Yaml::Sequence(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    path.push(index.to_string());
+                    let item = from_yaml(item, path);
+                    path.pop();
+                    item
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    Yaml::String(key) => key,
+                    Yaml::Bool(key) => key.to_string(),
+                    Yaml::Number(key) => key.to_string(),
+                    _ => return Err(format!("keys at {} must be strings", key_path(path))),
+                };
+                path.push(key.clone());
+                let value = from_yaml(value, path)?;
+                path.pop();
+                map.insert(key, value);
+            }
+            Value::Object(map)
+        }
+        Yaml::Tagged(tagged) => return Err(format!("the YAML tag {} at {} is not supported", tagged.tag, key_path(path))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "toml", feature = "yaml"))]
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn formats_follow_the_extension() {
+        assert_eq!(Format::from_path(Path::new("a/user.JSON")), Ok(Format::Json));
+        assert_eq!(Format::from_path(Path::new("user.toml")), Ok(Format::Toml));
+        assert_eq!(Format::from_path(Path::new("user.yml")), Ok(Format::Yaml));
+        assert_eq!(
+            Format::from_path(Path::new("user.ini")).unwrap_err(),
+            "Cannot tell the config format of 'user.ini'; expected a .json, .toml, .yaml or .yml file"
+        );
+        let error = from_path("examples/configs/missing.json").unwrap_err();
+        assert!(error.starts_with("Failed to read config file 'examples/configs/missing.json': "), "{}", error);
+    }
+
+    #[test]
+    fn json_errors_name_the_position() {
+        assert_eq!(
+            parse("{\n  \"age\": [1, 2\n}", Format::Json).unwrap_err(),
+            "JSON config, line 3, column 1: expected `,` or `]`"
+        );
+        assert_eq!(parse("[1, 2]", Format::Json).unwrap_err(), "JSON config: the config must be an object at the top level");
+    }
+
+    #[cfg(all(feature = "toml", feature = "yaml"))]
+    #[test]
+    fn every_format_loads_the_same_config() {
+        let json = from_path("examples/configs/user.json").unwrap();
+        assert_eq!(from_path("examples/configs/user.toml").unwrap(), json);
+        assert_eq!(from_path("examples/configs/user.yaml").unwrap(), json);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_round_trips_and_reports_positions() {
+        let config = json!({
+            "name": ["Alice", "Bob"],
+            "age": [18, 65],
+            "score": { "range": [0.5, 9.5], "decimals": 1 },
+            "address": { "city": ["Berlin"], "$locale": "de_DE" }
+        });
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(Value::Object(parse(&text, Format::Toml).unwrap()), config);
+
+        let config = parse("created = 2024-05-01T12:00:00Z", Format::Toml).unwrap();
+        assert_eq!(config["created"], json!("2024-05-01T12:00:00Z"));
+        assert_eq!(
+            parse("score = { range = [0.0, inf] }", Format::Toml).unwrap_err(),
+            "TOML config: the number inf at 'score.range.1' cannot be used in a config"
+        );
+        let error = parse("name = [\"Alice\"]\nage = [18, \n", Format::Toml).unwrap_err();
+        assert!(error.starts_with("TOML config, line 3, column 1: "), "{}", error);
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_round_trips_and_reports_positions() {
+        let config = json!({
+            "name": ["Alice", "Bob"],
+            "age": [18, 65],
+            "nickname": null,
+            "score": { "range": [0.5, 9.5], "decimals": 1 },
+            "status": { "variants": ["Active"], "variant_data": {} }
+        });
+        let text = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(Value::Object(parse(&text, Format::Yaml).unwrap()), config);
+
+        let merged = parse("base: &base { city: [Paris] }\naddress:\n  <<: *base\n  street: [Main St]\n", Format::Yaml).unwrap();
+        assert_eq!(merged["address"], json!({ "city": ["Paris"], "street": ["Main St"] }));
+        assert_eq!(
+            parse("score:\n  range: [0.0, .nan]\n", Format::Yaml).unwrap_err(),
+            "YAML config: the number NaN at 'score.range.1' cannot be used in a config"
+        );
+        assert_eq!(
+            parse("name: !choice [Alice]\n", Format::Yaml).unwrap_err(),
+            "YAML config: the YAML tag !choice at 'name' is not supported"
+        );
+        assert_eq!(
+            parse("name: [Alice]\nage: [18\n", Format::Yaml).unwrap_err().split(':').next(),
+            Some("YAML config, line 3, column 1")
+        );
+    }
+
+    #[cfg(not(feature = "yaml"))]
+    #[test]
+    fn yaml_needs_the_feature() {
+        assert_eq!(parse("name: [Alice]", Format::Yaml).unwrap_err(), "YAML config: YAML configs need the 'yaml' feature of samplify-rs");
+    }
+}
@@ -0,0 +1,326 @@
+//! Overriding single values of a config from environment variables and
+//! command-line arguments.
+//!
+//! An [`Override`] replaces the value at a dot-separated path of a config,
+//! such as `user.age`. Overrides come from `key.path=value` strings
+//! ([`Override::parse`]) or from environment variables named after the path,
+//! with `__` between the prefix and each key ([`Override::from_env`]):
+//!
+//! ```text
+//! SAMPLIFY__user__age=[30,40]
+//! ```
+//!
+//! Values are read as JSON, and as a string when they are not valid JSON, so
+//! `name=Alice` needs no quotes. Each override is checked against the
+//! [`Shape`] of the type being sampled: the path must name fields, variants
+//! and directives that exist (misspellings get a suggestion as in
+//! [strict mode](crate::strict)) and the field it changes must still compile.
+//! When a value read as JSON does not fit but its text does, as with
+//! `zipcode=10001` for a `String` field, the text is used.
+//!
+//! [`ConfigLoader`](crate::compose::ConfigLoader) applies overrides after
+//! merging profiles.
+
+use serde_json::{Map, Value};
+
+use crate::compose::set_path;
+use crate::plan::check_field;
+use crate::shape::{FieldKind, FieldShape, Shape};
+use crate::strict::{unknown, ENUM_KEYS, STRUCT_DIRECTIVES, VARIANT_DIRECTIVES};
+use crate::SampleContext;
+
+/// A value to set at a path of a config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    path: String,
+    value: Value,
+    // The text the value was read from, used as a string when the value
+    // does not fit.
+    text: Option<String>,
+    // Where the override came from, for errors.
+    origin: Option<String>,
+}
+
+impl Override {
+    /// Sets `path` to `value`.
+    pub fn new(path: &str, value: Value) -> Self {
+        Override { path: path.to_string(), value, text: None, origin: None }
+    }
+
+    /// Reads a `key.path=value` string.
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        match arg.split_once('=') {
+            Some((path, text)) if !path.trim().is_empty() => Ok(Override::from_text(path.trim(), text)),
+            _ => Err(format!("Override '{}' must have the form 'key.path=value'", arg)),
+        }
+    }
+
+    /// The overrides in the environment variables whose names start with
+    /// `prefix` followed by `__`, sorted by name.
+    pub fn from_env(prefix: &str) -> Vec<Self> {
+        Override::from_vars(prefix, std::env::vars())
+    }
+
+    /// The overrides in `vars` whose names start with `prefix` followed by
+    /// `__`, sorted by name.
+    pub fn from_vars(prefix: &str, vars: impl IntoIterator<Item = (String, String)>) -> Vec<Self> {
+        let prefix = format!("{}__", prefix);
+        let mut vars: Vec<(String, String)> = vars.into_iter().filter(|(name, _)| name.starts_with(&prefix)).collect();
+        vars.sort();
+        vars.into_iter()
+            .map(|(name, text)| {
+                let mut value = Override::from_text(&name[prefix.len()..].replace("__", "."), &text);
+                value.origin = Some(format!("environment variable '{}'", name));
+                value
+            })
+            .collect()
+    }
+
+    fn from_text(path: &str, text: &str) -> Self {
+        let value = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+        Override { path: path.to_string(), value, text: Some(text.to_string()), origin: None }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Sets the value in `config`, a config of `shape`, and checks the
+    /// result. `config` is left unchanged when the override is invalid.
+    pub fn apply(&self, shape: &Shape, config: &mut Map<String, Value>) -> Result<(), String> {
+        apply_all(std::slice::from_ref(self), shape, config)
+    }
+
+    fn error(&self, e: String) -> String {
+        match &self.origin {
+            Some(origin) => format!("Invalid override of '{}' from {}: {}", self.path, origin, e),
+            None => format!("Invalid override of '{}': {}", self.path, e),
+        }
+    }
+}
+
+/// Sets every override in `config`, a config of `shape`, in order, then
+/// checks them, so `status.variants` may name a variant whose data a later
+/// override sets. `config` is left unchanged when an override is invalid.
+pub fn apply_all(overrides: &[Override], shape: &Shape, config: &mut Map<String, Value>) -> Result<(), String> {
+    let mut changed = config.clone();
+    for value in overrides {
+        set_path(&mut changed, &value.path, value.value.clone()).map_err(|e| value.error(e))?;
+    }
+    for (index, value) in overrides.iter().enumerate() {
+        // A later override of the same path replaced this one.
+        if overrides[index + 1..].iter().any(|later| later.path == value.path) {
+            continue;
+        }
+        let segments: Vec<&str> = value.path.split('.').collect();
+        if let Err(e) = check(shape, &changed, &segments) {
+            match &value.text {
+                Some(text) if !value.value.is_string() => {
+                    set_path(&mut changed, &value.path, Value::String(text.clone())).map_err(|e| value.error(e))?;
+                    check(shape, &changed, &segments).map_err(|_| value.error(e))?;
+                }
+                _ => return Err(value.error(e)),
+            }
+        }
+    }
+    *config = changed;
+    Ok(())
+}
+
+// Checks the names along `segments` and compiles the config of the deepest
+// field they reach.
+fn check(shape: &Shape, config: &Map<String, Value>, segments: &[&str]) -> Result<(), String> {
+    if !check_within(shape, config, segments)? {
+        shape.validate(config, &SampleContext::new())?;
+    }
+    Ok(())
+}
+
+// Whether the path was checked; `false` when it ends above any field of
+// `shape`, so the config holding `config` must be compiled instead.
+fn check_within(shape: &Shape, config: &Map<String, Value>, segments: &[&str]) -> Result<bool, String> {
+    match shape {
+        Shape::Struct(shape) => check_fields(&shape.fields, STRUCT_DIRECTIVES, config, segments, shape.name),
+        Shape::Enum(shape) => {
+            let key = segments[0];
+            if !ENUM_KEYS.contains(&key) {
+                return Err(unknown("key", key, ENUM_KEYS.iter().copied(), shape.name));
+            }
+            if let ("variant_data", Some(name)) = (key, segments.get(1)) {
+                let variant = shape
+                    .variants
+                    .iter()
+                    .find(|variant| variant.name == *name)
+                    .ok_or_else(|| unknown("variant", name, shape.variants.iter().map(|variant| variant.name), shape.name))?;
+                if segments.len() > 2 {
+                    let fields = variant
+                        .fields
+                        .as_ref()
+                        .ok_or_else(|| format!("Variant '{}' of '{}' takes no config", name, shape.name))?;
+                    if let Some(Value::Object(data)) = config.get("variant_data").and_then(|data| data.get(*name)) {
+                        let owner = format!("{}::{}", shape.name, name);
+                        return check_fields(fields, VARIANT_DIRECTIVES, data, &segments[2..], &owner);
+                    }
+                }
+            }
+            Ok(false)
+        }
+        Shape::Opaque => Ok(true),
+    }
+}
+
+fn check_fields(fields: &[FieldShape], directives: &[&str], config: &Map<String, Value>, segments: &[&str], owner: &str) -> Result<bool, String> {
+    let (name, rest) = segments.split_first().expect("paths have a segment");
+    if name.starts_with('$') {
+        if !directives.contains(name) {
+            return Err(unknown("directive", name, directives.iter().copied(), owner));
+        }
+        return Ok(true);
+    }
+    let field = fields.iter().find(|field| field.name == *name).ok_or_else(|| {
+        let names = fields.iter().filter(|field| !field.computed).map(|field| field.name);
+        unknown("field", name, names, owner)
+    })?;
+    if field.computed {
+        return Err(format!("Field '{}' of '{}' is computed and takes no config", name, owner));
+    }
+    if !rest.is_empty() && !field.recursive {
+        if let (FieldKind::Nested(nested), Some(Value::Object(inner))) = (&field.kind, config.get(field.name)) {
+            if check_within(&nested(), inner, rest)? {
+                return Ok(true);
+            }
+        }
+    }
+    check_field(field, config)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::shape::{EnumShape, StructShape, VariantShape};
+
+    fn status() -> Shape {
+        Shape::Enum(EnumShape {
+            name: "Status",
+            module: "tests",
+            variants: vec![
+                VariantShape { name: "Active", fields: None },
+                VariantShape { name: "Suspended", fields: Some(vec![FieldShape::required("reason", FieldKind::String)]) },
+            ],
+        })
+    }
+
+    fn user() -> Shape {
+        let mut total = FieldShape::required("total", FieldKind::Integer { min: 0, max: 1000 });
+        total.computed = true;
+        Shape::Struct(StructShape {
+            name: "User",
+            module: "tests",
+            fields: vec![
+                FieldShape::required("age", FieldKind::Integer { min: 0, max: 1000 }),
+                FieldShape::required("zipcode", FieldKind::String),
+                FieldShape::required("status", FieldKind::Nested(status)),
+                total,
+            ],
+        })
+    }
+
+    fn config() -> Map<String, Value> {
+        json!({ "age": [18, 65], "zipcode": ["75001"], "status": { "variants": ["Active"] } }).as_object().cloned().unwrap()
+    }
+
+    fn apply(args: &[&str]) -> Result<Map<String, Value>, String> {
+        let overrides: Vec<Override> = args.iter().map(|arg| Override::parse(arg)).collect::<Result<_, _>>()?;
+        let mut config = config();
+        apply_all(&overrides, &user(), &mut config)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn values_are_read_as_json_or_text() {
+        let config = apply(&["age=[30, 40]", "zipcode=10001"]).unwrap();
+        assert_eq!(config["age"], json!([30, 40]));
+        assert_eq!(config["zipcode"], json!("10001"));
+    }
+
+    #[test]
+    fn values_must_fit_the_field() {
+        assert_eq!(
+            apply(&["age=true"]).unwrap_err(),
+            "Invalid override of 'age': Configuration for field 'age' must be an array"
+        );
+        assert_eq!(apply(&["age=[40, 30]"]).unwrap_err(), "Invalid override of 'age': Range for field 'age' is empty");
+    }
+
+    #[test]
+    fn paths_must_name_fields_variants_and_directives() {
+        assert_eq!(
+            apply(&["agee=[1, 2]"]).unwrap_err(),
+            "Invalid override of 'agee': Unknown field 'agee' in the config of 'User'; did you mean 'age'?"
+        );
+        assert_eq!(
+            apply(&["$lcoale=de_DE"]).unwrap_err(),
+            "Invalid override of '$lcoale': Unknown directive '$lcoale' in the config of 'User'; did you mean '$locale'?"
+        );
+        assert_eq!(
+            apply(&["status.variant_data.Suspend.reason=fraud"]).unwrap_err(),
+            "Invalid override of 'status.variant_data.Suspend.reason': Unknown variant 'Suspend' in the config of 'Status'; did you mean 'Suspended'?"
+        );
+        assert_eq!(
+            apply(&["total=[1, 2]"]).unwrap_err(),
+            "Invalid override of 'total': Field 'total' of 'User' is computed and takes no config"
+        );
+    }
+
+    #[test]
+    fn overrides_are_checked_together() {
+        let config = apply(&["status.variants=[\"Suspended\"]", "status.variant_data.Suspended.reason=fraud"]).unwrap();
+        assert_eq!(config["status"], json!({ "variants": ["Suspended"], "variant_data": { "Suspended": { "reason": "fraud" } } }));
+        assert!(apply(&["status.variants=[\"Suspended\"]"]).is_err());
+    }
+
+    #[test]
+    fn later_overrides_replace_earlier_ones() {
+        let config = apply(&["age=true", "age=[1, 2]"]).unwrap();
+        assert_eq!(config["age"], json!([1, 2]));
+    }
+
+    #[test]
+    fn invalid_overrides_leave_the_config_unchanged() {
+        let mut config = config();
+        let overrides = [Override::new("age", json!([30, 40])), Override::new("zipcode", json!(true))];
+        assert!(apply_all(&overrides, &user(), &mut config).is_err());
+        assert_eq!(config, self::config());
+    }
+
+    #[test]
+    fn environment_variables_name_paths_with_double_underscores() {
+        let vars = [
+            ("SAMPLIFY__zipcode".to_string(), "10001".to_string()),
+            ("SAMPLIFY__age".to_string(), "true".to_string()),
+            ("OTHER__age".to_string(), "[1, 2]".to_string()),
+            ("SAMPLIFY__status__variants".to_string(), "[\"Active\"]".to_string()),
+        ];
+        let overrides = Override::from_vars("SAMPLIFY", vars);
+        let paths: Vec<&str> = overrides.iter().map(Override::path).collect();
+        assert_eq!(paths, ["age", "status.variants", "zipcode"]);
+        assert_eq!(
+            apply_all(&overrides, &user(), &mut config()).unwrap_err(),
+            "Invalid override of 'age' from environment variable 'SAMPLIFY__age': Configuration for field 'age' must be an array"
+        );
+    }
+
+    #[test]
+    fn arguments_need_a_path_and_a_value() {
+        assert!(Override::parse("age").is_err());
+        assert!(Override::parse("=5").is_err());
+        assert_eq!(Override::parse("age=").unwrap().value(), &json!(""));
+    }
+}
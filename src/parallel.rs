@@ -0,0 +1,230 @@
+//! Parallel batches, enabled by the `rayon` feature.
+//!
+//! A batch is cut into chunks of [`CHUNK_SIZE`] samples that are sampled on
+//! the rayon thread pool. Sample `i` draws from its own generator, seeded
+//! from the batch seed and `i`, and chunk boundaries do not depend on the
+//! number of threads, so a seed gives the same batch on any machine.
+//!
+//! State shared by the batch is reconciled in chunk order afterwards:
+//!
+//! - Every sequence of a chunk skips the numbers handed out by the chunks
+//!   before, so the numbering matches a sequential batch. When each sample
+//!   draws the same count of numbers, which the plan tells unless an enum,
+//!   a recursive field or a hand-written type is in the way, the counts are
+//!   known up front. Otherwise, if any chunk used a sequence, the chunks are
+//!   sampled once to count them and then again.
+//! - Each chunk is then checked against the chunks before it, as finally
+//!   sampled. A chunk is sampled again on its own, with the values seen so
+//!   far, when it repeats a unique value or when the chunks before it handed
+//!   out a different count of sequence numbers than it skipped, which happens
+//!   when a chunk before it was sampled again.
+//!
+//! Fields referring to a dataset with a `cardinality` cannot be sampled in
+//! parallel, since each chunk would count the references separately.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::plan::{Plan, Source};
+use crate::unique::UniqueValues;
+use crate::{SampleContext, Sampleable};
+
+/// Number of samples drawn sequentially by one task.
+pub const CHUNK_SIZE: usize = 1024;
+
+/// Samples `n` values from `plan` in parallel. The batch seed is drawn from
+/// `ctx`, which also receives the sequence counters and unique values of
+/// the batch.
+pub(crate) fn sample_many_par<T: Sampleable + Send>(plan: &Plan, n: usize, ctx: &mut SampleContext) -> Result<Vec<T>, String> {
+    if plan.any_source(&|source| matches!(source, Source::Ref(spec) if spec.is_limited())) {
+        return Err("Fields referring to a dataset with a cardinality cannot be sampled in parallel; use sample_many".to_string());
+    }
+    let seed: u64 = ctx.rng().gen();
+    let base = ctx.fork(sample_rng(seed, 0));
+    let chunks: Vec<Range<usize>> = (0..n).step_by(CHUNK_SIZE).map(|start| start..n.min(start + CHUNK_SIZE)).collect();
+    let sample_chunk = |index: usize, skips: &HashMap<String, u64>, unique: UniqueValues| {
+        let mut chunk_ctx = base.fork(sample_rng(seed, chunks[index].start));
+        *chunk_ctx.unique_values_mut() = unique;
+        for (path, count) in skips {
+            chunk_ctx.skip_in_sequence(path, *count);
+        }
+        let mut samples = Vec::with_capacity(chunks[index].len());
+        for i in chunks[index].clone() {
+            *chunk_ctx.rng() = sample_rng(seed, i);
+            samples.push(T::sample_plan(plan, &mut chunk_ctx)?);
+        }
+        Ok::<_, String>((samples, chunk_ctx))
+    };
+
+    let sample_chunks = |skips: &[HashMap<String, u64>]| {
+        (0..chunks.len())
+            .into_par_iter()
+            .map(|index| sample_chunk(index, &skips[index], UniqueValues::default()))
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let (skips, mut results) = match plan.sequence_draws() {
+        Some(draws) => {
+            let skips: Vec<HashMap<String, u64>> = chunks
+                .iter()
+                .map(|chunk| {
+                    let before = chunk.start as u64;
+                    draws.iter().filter(|_| before > 0).map(|(path, count)| (path.clone(), count * before)).collect()
+                })
+                .collect();
+            let results = sample_chunks(&skips)?;
+            (skips, results)
+        }
+        None => {
+            let no_skips = vec![HashMap::new(); chunks.len()];
+            let results = sample_chunks(&no_skips)?;
+            if results.iter().all(|(_, chunk_ctx)| chunk_ctx.sequence_calls().is_empty()) {
+                (no_skips, results)
+            } else {
+                let skips = handed_out_before(&results);
+                let results = sample_chunks(&skips)?;
+                (skips, results)
+            }
+        }
+    };
+
+    let mut seen = std::mem::take(ctx.unique_values_mut());
+    let mut handed_out: HashMap<String, u64> = HashMap::new();
+    for index in 0..results.len() {
+        if skips[index] != handed_out || !seen.merge(results[index].1.unique_values()) {
+            let (samples, mut chunk_ctx) = sample_chunk(index, &handed_out, seen)?;
+            seen = std::mem::take(chunk_ctx.unique_values_mut());
+            results[index] = (samples, chunk_ctx);
+        }
+        for (path, calls) in results[index].1.sequence_calls() {
+            *handed_out.entry(path.clone()).or_insert(0) += calls;
+        }
+        ctx.absorb_sequences(&results[index].1);
+    }
+    *ctx.unique_values_mut() = seen;
+
+    Ok(results.into_iter().flat_map(|(samples, _)| samples).collect())
+}
+
+// The sequence numbers handed out by the chunks before each chunk, by field
+// path.
+fn handed_out_before<T>(results: &[(Vec<T>, SampleContext)]) -> Vec<HashMap<String, u64>> {
+    let mut handed_out: HashMap<String, u64> = HashMap::new();
+    results
+        .iter()
+        .map(|(_, chunk_ctx)| {
+            let before = handed_out.clone();
+            for (path, calls) in chunk_ctx.sequence_calls() {
+                *handed_out.entry(path.clone()).or_insert(0) += calls;
+            }
+            before
+        })
+        .collect()
+}
+
+// The generator of sample `index` in the batch seeded with `seed`.
+fn sample_rng(seed: u64, index: usize) -> StdRng {
+    let mut key = [0u8; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8..16].copy_from_slice(&(index as u64).to_le_bytes());
+    StdRng::from_seed(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use serde_json::json;
+
+    use crate::plan::Plan;
+    use crate::{SampleContext, Sampleable};
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Entry {
+        id: u32,
+        kind: Kind,
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    enum Kind {
+        Numbered { number: i64 },
+        Plain,
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Order {
+        number: u64,
+        reference: String,
+        id: u32,
+        line: Line,
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Line {
+        position: u32,
+    }
+
+    fn batches<T: Sampleable + Send>(config: &serde_json::Value, n: usize) -> Vec<Vec<T>> {
+        [1, 2, 4, 7]
+            .into_iter()
+            .map(|threads| {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+                pool.install(|| T::sample_many_par(config.as_object().unwrap(), n, 7)).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fixed_sequence_draws_are_counted_from_the_plan() {
+        let config = json!({
+            "number": { "sequence": { "start": 100 } },
+            "reference": { "template": "ORD-{seq:06}" },
+            "id": [0, 20000],
+            "line": { "position": { "sequence": true } },
+            "$unique": ["id"]
+        });
+        let plan = Plan::compile(&Order::config_shape(), config.as_object().unwrap(), &SampleContext::new()).unwrap();
+        let draws = plan.sequence_draws().unwrap();
+        assert_eq!(draws.len(), 3);
+        assert!(["number", "reference", "line.position"].iter().all(|path| draws[*path] == 1));
+
+        let batches = batches::<Order>(&config, 5000);
+        assert!(batches.windows(2).all(|pair| pair[0] == pair[1]));
+        let orders = &batches[0];
+        assert_eq!(orders.iter().map(|order| order.id).collect::<HashSet<_>>().len(), orders.len());
+        for (i, order) in orders.iter().enumerate() {
+            assert_eq!(order.number, 100 + i as u64);
+            assert_eq!(order.reference, format!("ORD-{:06}", i + 1));
+            assert_eq!(order.line.position, i as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn a_seed_gives_the_same_batch_on_any_number_of_threads() {
+        // Ids collide across chunks, so chunks are sampled again with the
+        // ids seen so far; their retries change which entries are numbered.
+        let config = json!({
+            "id": [0, 20000],
+            "kind": { "variants": ["Numbered", "Plain"], "variant_data": { "Numbered": { "number": { "sequence": true } } } },
+            "$unique": ["id"]
+        });
+        let plan = Plan::compile(&Entry::config_shape(), config.as_object().unwrap(), &SampleContext::new()).unwrap();
+        assert_eq!(plan.sequence_draws(), None);
+        let batches = batches::<Entry>(&config, 5000);
+        assert!(batches.windows(2).all(|pair| pair[0] == pair[1]));
+
+        let entries = &batches[0];
+        assert_eq!(entries.iter().map(|entry| entry.id).collect::<HashSet<_>>().len(), entries.len());
+        let numbers: Vec<i64> = entries
+            .iter()
+            .filter_map(|entry| match entry.kind {
+                Kind::Numbered { number } => Some(number),
+                Kind::Plain => None,
+            })
+            .collect();
+        assert!(numbers.iter().copied().eq(1..=numbers.len() as i64), "numbering has gaps or repeats");
+    }
+}
@@ -0,0 +1,207 @@
+//! Regex-driven string generation, enabled by the `regex` feature.
+//!
+//! Patterns are often copied from validators and anchored, like
+//! `^[A-Z]{2}$`. Generated strings always match the whole pattern, so anchors
+//! at its start and end are dropped; other anchors and word boundaries are
+//! rejected.
+
+use rand::Rng;
+use regex_syntax::hir::{Capture, Class, ClassUnicode, ClassUnicodeRange, Hir, HirKind, Look, Repetition};
+use serde_json::{Map, Value};
+
+/// How many repetitions `*`, `+` and `{n,}` may add beyond their minimum
+/// when the config does not set `max_repeat`.
+pub const DEFAULT_MAX_REPEAT: u32 = 32;
+
+/// A compiled generator for strings matching a regular expression.
+#[derive(Debug, Clone)]
+pub struct RegexSpec {
+    generator: rand_regex::Regex,
+}
+
+impl RegexSpec {
+    /// Builds a generator from a `{"regex": "...", "max_repeat": 32, "unicode": false}` config.
+    ///
+    /// Unless `unicode` is set, character classes such as `\d`, `\w` and `.`
+    /// are narrowed to printable ASCII, which is what reference numbers and
+    /// codes usually need.
+    pub fn parse(map: &Map<String, Value>, field_name: &str) -> Result<Self, String> {
+        let pattern = map
+            .get("regex")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("Regex for field '{}' must be a string", field_name))?;
+        let max_repeat = match map.get("max_repeat") {
+            Some(value) => value
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .ok_or_else(|| format!("'max_repeat' for field '{}' must be a positive integer", field_name))?,
+            None => DEFAULT_MAX_REPEAT,
+        };
+        let unicode = match map.get("unicode") {
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| format!("'unicode' for field '{}' must be a boolean", field_name))?,
+            None => false,
+        };
+        Self::compile(pattern, max_repeat, unicode, field_name)
+    }
+
+    /// Compiles `pattern` into a generator.
+    pub fn compile(pattern: &str, max_repeat: u32, unicode: bool, field_name: &str) -> Result<Self, String> {
+        if max_repeat == 0 {
+            return Err(format!("'max_repeat' for field '{}' must be a positive integer", field_name));
+        }
+        let hir = regex_syntax::Parser::new()
+            .parse(pattern)
+            .map_err(|e| format!("Invalid regex for field '{}': {}", field_name, e))?;
+        let hir = strip_anchors(hir, true, true);
+        if has_look(&hir) {
+            return Err(format!("Invalid regex for field '{}': anchors are only supported at the start and end", field_name));
+        }
+        let hir = if unicode { hir } else { restrict_to_ascii(hir) };
+        let generator = rand_regex::Regex::with_hir(hir, max_repeat)
+            .map_err(|e| format!("Invalid regex for field '{}': {}", field_name, e))?;
+        Ok(RegexSpec { generator })
+    }
+
+    /// Generates a string matching the pattern.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        rng.sample(&self.generator)
+    }
+}
+
+// Drops the `^`/`\A` anchors at the start of `hir` if `start` is set and the
+// `$`/`\z` anchors at its end if `end` is, looking into the groups and
+// alternation branches that begin or end the pattern.
+fn strip_anchors(hir: Hir, start: bool, end: bool) -> Hir {
+    match hir.into_kind() {
+        HirKind::Look(Look::Start) if start => Hir::empty(),
+        HirKind::Look(Look::End) if end => Hir::empty(),
+        HirKind::Concat(mut subs) => {
+            if start {
+                let leading = subs.iter().take_while(|sub| matches!(sub.kind(), HirKind::Look(Look::Start))).count();
+                subs.drain(..leading);
+            }
+            while end && matches!(subs.last().map(Hir::kind), Some(HirKind::Look(Look::End))) {
+                subs.pop();
+            }
+            let last = subs.len().saturating_sub(1);
+            Hir::concat(subs.into_iter().enumerate().map(|(i, sub)| strip_anchors(sub, start && i == 0, end && i == last)).collect())
+        }
+        HirKind::Alternation(subs) => Hir::alternation(subs.into_iter().map(|sub| strip_anchors(sub, start, end)).collect()),
+        HirKind::Capture(cap) => Hir::capture(Capture { sub: Box::new(strip_anchors(*cap.sub, start, end)), ..cap }),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Repetition(rep) => Hir::repetition(rep),
+        HirKind::Class(class) => Hir::class(class),
+        HirKind::Literal(literal) => Hir::literal(literal.0),
+        HirKind::Empty => Hir::empty(),
+    }
+}
+
+// Whether an anchor or word boundary is left anywhere in `hir`.
+fn has_look(hir: &Hir) -> bool {
+    match hir.kind() {
+        HirKind::Look(_) => true,
+        HirKind::Repetition(rep) => has_look(&rep.sub),
+        HirKind::Capture(cap) => has_look(&cap.sub),
+        HirKind::Concat(subs) | HirKind::Alternation(subs) => subs.iter().any(has_look),
+        HirKind::Empty | HirKind::Literal(_) | HirKind::Class(_) => false,
+    }
+}
+
+// Intersects every Unicode class with printable ASCII. Classes that only
+// contain non-ASCII characters (e.g. `\p{Greek}`) are left untouched.
+fn restrict_to_ascii(hir: Hir) -> Hir {
+    match hir.into_kind() {
+        HirKind::Class(Class::Unicode(class)) => {
+            let mut narrowed = class.clone();
+            narrowed.intersect(&ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]));
+            if narrowed.ranges().is_empty() {
+                Hir::class(Class::Unicode(class))
+            } else {
+                Hir::class(Class::Unicode(narrowed))
+            }
+        }
+        HirKind::Class(class) => Hir::class(class),
+        HirKind::Empty => Hir::empty(),
+        HirKind::Literal(literal) => Hir::literal(literal.0),
+        HirKind::Look(look) => Hir::look(look),
+        HirKind::Repetition(rep) => Hir::repetition(Repetition { sub: Box::new(restrict_to_ascii(*rep.sub)), ..rep }),
+        HirKind::Capture(cap) => Hir::capture(Capture { sub: Box::new(restrict_to_ascii(*cap.sub)), ..cap }),
+        HirKind::Concat(subs) => Hir::concat(subs.into_iter().map(restrict_to_ascii).collect()),
+        HirKind::Alternation(subs) => Hir::alternation(subs.into_iter().map(restrict_to_ascii).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+    use crate::Sampleable;
+
+    fn samples(pattern: &str, max_repeat: u32, unicode: bool) -> Vec<String> {
+        let spec = RegexSpec::compile(pattern, max_repeat, unicode, "code").unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        (0..50).map(|_| spec.sample(&mut rng)).collect()
+    }
+
+    #[test]
+    fn anchors_at_the_edges_are_dropped() {
+        for code in samples("^[A-Z]{2}$", DEFAULT_MAX_REPEAT, false) {
+            assert!(code.len() == 2 && code.chars().all(|c| c.is_ascii_uppercase()), "{}", code);
+        }
+        for code in samples(r"\A(?:GB|DE)\d{2}\z|^X$", DEFAULT_MAX_REPEAT, false) {
+            assert!(code == "X" || (code.len() == 4 && ["GB", "DE"].contains(&&code[..2])), "{}", code);
+        }
+        for pattern in ["a^b", "^a$b", r"\bword", "(?m)^a$"] {
+            let error = RegexSpec::compile(pattern, DEFAULT_MAX_REPEAT, false, "code").unwrap_err();
+            assert_eq!(error, "Invalid regex for field 'code': anchors are only supported at the start and end", "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn max_repeat_caps_open_repetitions() {
+        for code in samples("a+", 3, false) {
+            assert!((1..=4).contains(&code.len()), "{}", code);
+        }
+        assert!(samples("a{0,}b", DEFAULT_MAX_REPEAT, false).iter().all(|code| code.len() <= 33));
+        let parse = |config: serde_json::Value| RegexSpec::parse(config.as_object().unwrap(), "code");
+        assert!(parse(json!({ "regex": "x*", "max_repeat": 1 })).is_ok());
+        for max_repeat in [json!(0), json!(-1), json!("3")] {
+            let error = parse(json!({ "regex": "x*", "max_repeat": max_repeat })).unwrap_err();
+            assert_eq!(error, "'max_repeat' for field 'code' must be a positive integer");
+        }
+    }
+
+    #[test]
+    fn classes_are_narrowed_to_printable_ascii_unless_unicode() {
+        for pattern in [r"\w{40}", r"\d{40}", ".{40}", r"\S{40}"] {
+            for code in samples(pattern, DEFAULT_MAX_REPEAT, false) {
+                assert!(code.chars().all(|c| (' '..='~').contains(&c)), "{}: {}", pattern, code);
+            }
+        }
+        let greek = samples(r"\p{Greek}{5}", DEFAULT_MAX_REPEAT, false);
+        assert!(greek.iter().flat_map(|code| code.chars()).all(|c| !c.is_ascii()));
+        let unicode = samples(r"\w{40}", DEFAULT_MAX_REPEAT, true);
+        assert!(unicode.iter().flat_map(|code| code.chars()).any(|c| !c.is_ascii()));
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Account {
+        #[sample(regex = "^[A-Z]{2}[0-9]{4}$")]
+        reference: String,
+    }
+
+    #[test]
+    fn regex_attributes_fill_fields_the_config_leaves_out() {
+        let config = json!({}).as_object().cloned().unwrap();
+        for account in Account::sample_many(&config, 20).unwrap() {
+            assert_eq!(account.reference.len(), 6);
+            assert!(account.reference[..2].chars().all(|c| c.is_ascii_uppercase()));
+            assert!(account.reference[2..].chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+}
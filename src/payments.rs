@@ -0,0 +1,473 @@
+//! Generators for payment identifiers with valid check digits.
+//!
+//! Selected from a `String` field config with `{"generator": "..."}`:
+//!
+//! - `iban`: `"country"` (code or list of codes), `"formatted"` (groups of four)
+//! - `bic`: `"country"`, `"length"` (8 or 11)
+//! - `aba_routing`: US ABA routing number
+//! - `uk_sort_code`: `"formatted"` (`12-34-56`, default) or six digits
+//! - `card_pan`: `"scheme"` (`visa`, `mastercard`, `amex`, `discover`);
+//!   numbers start with published test BINs and pass the Luhn check
+//! - `lei`: ISO 17442 Legal Entity Identifier
+//! - `currency`: ISO 4217 code; `"minor_units"` (number or list) restricts
+//!   the draw, e.g. `0` for currencies without decimals such as `JPY`
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+
+use crate::currency::CURRENCIES;
+
+// IBAN length and BBAN structure per country, in the notation of the SWIFT
+// IBAN registry: `n` digits, `a` uppercase letters, `c` alphanumerics.
+const IBAN_FORMATS: &[(&str, usize, &str)] = &[
+    ("AD", 24, "4n4n12c"),
+    ("AE", 23, "3n16n"),
+    ("AT", 20, "5n11n"),
+    ("BE", 16, "3n7n2n"),
+    ("BG", 22, "4a4n2n8c"),
+    ("BR", 29, "8n5n10n1a1c"),
+    ("CH", 21, "5n12c"),
+    ("CY", 28, "3n5n16c"),
+    ("CZ", 24, "4n6n10n"),
+    ("DE", 22, "8n10n"),
+    ("DK", 18, "4n9n1n"),
+    ("EE", 20, "2n2n11n1n"),
+    ("ES", 24, "4n4n1n1n10n"),
+    ("FI", 18, "3n11n"),
+    ("FR", 27, "5n5n11c2n"),
+    ("GB", 22, "4a6n8n"),
+    ("GR", 27, "3n4n16c"),
+    ("HR", 21, "7n10n"),
+    ("HU", 28, "3n4n1n15n1n"),
+    ("IE", 22, "4a6n8n"),
+    ("IL", 23, "3n3n13n"),
+    ("IS", 26, "4n2n6n10n"),
+    ("IT", 27, "1a5n5n12c"),
+    ("LI", 21, "5n12c"),
+    ("LT", 20, "5n11n"),
+    ("LU", 20, "3n13c"),
+    ("LV", 21, "4a13c"),
+    ("MC", 27, "5n5n11c2n"),
+    ("MT", 31, "4a5n18c"),
+    ("NL", 18, "4a10n"),
+    ("NO", 15, "4n6n1n"),
+    ("PL", 28, "8n16n"),
+    ("PT", 25, "4n4n11n2n"),
+    ("QA", 29, "4a21c"),
+    ("RO", 24, "4a16c"),
+    ("SA", 24, "2n18c"),
+    ("SE", 24, "3n16n1n"),
+    ("SI", 19, "5n8n2n"),
+    ("SK", 24, "4n6n10n"),
+    ("SM", 27, "1a5n5n12c"),
+    ("TR", 26, "5n1n16c"),
+];
+
+// Countries used for BICs when no country is configured.
+const BIC_COUNTRIES: &[&str] = &[
+    "AT", "AU", "BE", "CA", "CH", "CN", "DE", "DK", "ES", "FI", "FR", "GB", "HK", "IE", "IN", "IT", "JP", "LU",
+    "NL", "NO", "PL", "PT", "SE", "SG", "US",
+];
+
+// Test BIN prefixes published by card schemes and payment processors, with
+// the card number length.
+const CARD_SCHEMES: &[(&str, &[&str], usize)] = &[
+    ("visa", &["411111", "424242", "400005", "401288"], 16),
+    ("mastercard", &["555555", "510510", "520082", "222300"], 16),
+    ("amex", &["378282", "371449", "378734"], 15),
+    ("discover", &["601111", "601100", "644564"], 16),
+];
+
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// The options of a payment generator. Which ones apply depends on the
+/// generator; see the [module docs](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaymentOptions {
+    pub country: Option<Vec<String>>,
+    pub formatted: Option<bool>,
+    pub length: Option<u64>,
+    pub scheme: Option<Vec<String>>,
+    pub minor_units: Option<Vec<u64>>,
+}
+
+/// A payment identifier generator.
+#[derive(Debug, Clone)]
+pub enum PaymentGenerator {
+    Iban { countries: Vec<&'static str>, formatted: bool },
+    Bic { countries: Vec<String>, length: Option<usize> },
+    AbaRouting,
+    UkSortCode { formatted: bool },
+    CardPan { schemes: Vec<&'static str> },
+    Lei,
+    Currency { codes: Vec<&'static str> },
+}
+
+impl PaymentGenerator {
+    /// Names accepted by [`PaymentGenerator::parse`].
+    pub const NAMES: &'static [&'static str] = &["iban", "bic", "aba_routing", "uk_sort_code", "card_pan", "lei", "currency"];
+
+    /// Parses the options of the generator called `name`.
+    pub fn parse(name: &str, map: &Map<String, Value>, field_name: &str) -> Result<Self, String> {
+        let options = PaymentOptions {
+            country: string_list(map.get("country"), "country", field_name)?,
+            formatted: match map.get("formatted") {
+                Some(Value::Bool(formatted)) => Some(*formatted),
+                Some(_) => return Err(format!("'formatted' for field '{}' must be a boolean", field_name)),
+                None => None,
+            },
+            length: match map.get("length") {
+                Some(length) => Some(length.as_u64().ok_or_else(|| format!("BIC length for field '{}' must be 8 or 11", field_name))?),
+                None => None,
+            },
+            scheme: string_list(map.get("scheme"), "scheme", field_name)?,
+            minor_units: match map.get("minor_units") {
+                None => None,
+                Some(Value::Array(values)) => Some(values.iter().map(Value::as_u64).collect::<Option<_>>()),
+                Some(value) => Some(value.as_u64().map(|n| vec![n])),
+            }
+            .map(|units| {
+                units.ok_or_else(|| format!("'minor_units' for field '{}' must be a number or an array of numbers", field_name))
+            })
+            .transpose()?,
+        };
+        Self::new(name, options, field_name)
+    }
+
+    /// Builds the generator called `name` from its options.
+    pub fn new(name: &str, options: PaymentOptions, field_name: &str) -> Result<Self, String> {
+        for (key, list) in [("country", &options.country), ("scheme", &options.scheme)] {
+            if list.as_ref().is_some_and(Vec::is_empty) {
+                return Err(format!("'{}' for field '{}' must be a string or a non-empty array of strings", key, field_name));
+            }
+        }
+        let formatted = |default: bool| options.formatted.unwrap_or(default);
+        match name {
+            "iban" => {
+                let countries = match &options.country {
+                    Some(codes) => codes
+                        .iter()
+                        .map(|code| {
+                            IBAN_FORMATS
+                                .iter()
+                                .find(|(country, _, _)| country.eq_ignore_ascii_case(code))
+                                .map(|(country, _, _)| *country)
+                                .ok_or_else(|| format!("No IBAN format for country '{}' (field '{}')", code, field_name))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => IBAN_FORMATS.iter().map(|(country, _, _)| *country).collect(),
+                };
+                Ok(PaymentGenerator::Iban { countries, formatted: formatted(false) })
+            }
+            "bic" => {
+                let countries = match &options.country {
+                    Some(codes) => {
+                        if let Some(code) = codes.iter().find(|code| code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic())) {
+                            return Err(format!("Invalid country code '{}' for field '{}'", code, field_name));
+                        }
+                        codes.iter().map(|code| code.to_ascii_uppercase()).collect()
+                    }
+                    None => BIC_COUNTRIES.iter().map(|code| code.to_string()).collect(),
+                };
+                let length = match options.length {
+                    Some(length @ (8 | 11)) => Some(length as usize),
+                    Some(_) => return Err(format!("BIC length for field '{}' must be 8 or 11", field_name)),
+                    None => None,
+                };
+                Ok(PaymentGenerator::Bic { countries, length })
+            }
+            "aba_routing" => Ok(PaymentGenerator::AbaRouting),
+            "uk_sort_code" => Ok(PaymentGenerator::UkSortCode { formatted: formatted(true) }),
+            "card_pan" => {
+                let schemes = match &options.scheme {
+                    Some(names) => names
+                        .iter()
+                        .map(|name| {
+                            CARD_SCHEMES
+                                .iter()
+                                .find(|(scheme, _, _)| scheme.eq_ignore_ascii_case(name))
+                                .map(|(scheme, _, _)| *scheme)
+                                .ok_or_else(|| format!("Unknown card scheme '{}' for field '{}'", name, field_name))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => CARD_SCHEMES.iter().map(|(scheme, _, _)| *scheme).collect(),
+                };
+                Ok(PaymentGenerator::CardPan { schemes })
+            }
+            "lei" => Ok(PaymentGenerator::Lei),
+            "currency" => {
+                let codes: Vec<&'static str> = CURRENCIES
+                    .iter()
+                    .filter(|(_, _, units)| options.minor_units.as_ref().is_none_or(|allowed| allowed.contains(&u64::from(*units))))
+                    .map(|(code, _, _)| *code)
+                    .collect();
+                if codes.is_empty() {
+                    return Err(format!("No currency matches 'minor_units' for field '{}'", field_name));
+                }
+                Ok(PaymentGenerator::Currency { codes })
+            }
+            _ => Err(format!("Unknown generator '{}' for field '{}'", name, field_name)),
+        }
+    }
+
+    /// Generates an identifier.
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        match self {
+            PaymentGenerator::Iban { countries, formatted } => {
+                let country = *countries.choose(rng).unwrap();
+                let iban = iban(rng, country).expect("countries are validated by parse");
+                if *formatted {
+                    group(&iban, 4, ' ')
+                } else {
+                    iban
+                }
+            }
+            PaymentGenerator::Bic { countries, length } => {
+                let length = length.unwrap_or(if rng.gen_bool(0.5) { 8 } else { 11 });
+                let country = countries.choose(rng).unwrap();
+                bic(rng, country, length)
+            }
+            PaymentGenerator::AbaRouting => aba_routing(rng),
+            PaymentGenerator::UkSortCode { formatted } => {
+                let digits = random_chars(rng, b"0123456789", 6);
+                if *formatted {
+                    group(&digits, 2, '-')
+                } else {
+                    digits
+                }
+            }
+            PaymentGenerator::CardPan { schemes } => {
+                let scheme = schemes.choose(rng).unwrap();
+                let (_, prefixes, length) = CARD_SCHEMES.iter().find(|(name, _, _)| name == scheme).unwrap();
+                let prefix = *prefixes.choose(rng).unwrap();
+                card_pan(rng, prefix, *length)
+            }
+            PaymentGenerator::Lei => lei(rng),
+            PaymentGenerator::Currency { codes } => codes.choose(rng).unwrap().to_string(),
+        }
+    }
+}
+
+/// Generates an IBAN for `country` with valid mod-97 check digits, or `None`
+/// if the country has no known IBAN format.
+pub fn iban<R: Rng + ?Sized>(rng: &mut R, country: &str) -> Option<String> {
+    let (country, length, structure) = IBAN_FORMATS.iter().find(|(code, _, _)| code.eq_ignore_ascii_case(country))?;
+    let mut bban = String::new();
+    let mut count = 0usize;
+    for c in structure.chars() {
+        match c {
+            '0'..='9' => count = count * 10 + (c as usize - '0' as usize),
+            'n' | 'a' | 'c' => {
+                let charset: &[u8] = match c {
+                    'n' => b"0123456789",
+                    'a' => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+                    _ => ALPHANUMERIC,
+                };
+                bban.push_str(&random_chars(rng, charset, count));
+                count = 0;
+            }
+            _ => {}
+        }
+    }
+    debug_assert_eq!(bban.len() + 4, *length);
+    let check = 98 - mod97(&format!("{}{}00", bban, country));
+    Some(format!("{}{:02}{}", country, check, bban))
+}
+
+/// Generates a BIC of 8 or 11 characters.
+pub fn bic<R: Rng + ?Sized>(rng: &mut R, country: &str, length: usize) -> String {
+    let bank = random_chars(rng, b"ABCDEFGHIJKLMNOPQRSTUVWXYZ", 4);
+    // A `0` as the second location character marks a test BIC; avoid it.
+    let location = format!(
+        "{}{}",
+        random_chars(rng, ALPHANUMERIC, 1),
+        random_chars(rng, b"ABCDEFGHIJKLMNOPQRSTUVWXYZ123456789", 1)
+    );
+    let branch = if length == 11 {
+        if rng.gen_bool(0.5) {
+            "XXX".to_string()
+        } else {
+            random_chars(rng, ALPHANUMERIC, 3)
+        }
+    } else {
+        String::new()
+    };
+    format!("{}{}{}{}", bank, country, location, branch)
+}
+
+/// Generates a US ABA routing number with a valid checksum.
+pub fn aba_routing<R: Rng + ?Sized>(rng: &mut R) -> String {
+    // Federal Reserve routing symbols 01-12 and thrift institutions 21-32.
+    let prefix = rng.gen_range(1..=12) + if rng.gen_bool(0.5) { 0 } else { 20 };
+    let mut digits: Vec<u32> = vec![prefix / 10, prefix % 10];
+    digits.extend((0..6).map(|_| rng.gen_range(0..10)));
+    let weighted: u32 = digits.iter().zip([3, 7, 1, 3, 7, 1, 3, 7]).map(|(d, w)| d * w).sum();
+    digits.push((10 - weighted % 10) % 10);
+    digits.iter().map(|d| char::from_digit(*d, 10).unwrap()).collect()
+}
+
+/// Generates a card number starting with `prefix` with a Luhn check digit.
+pub fn card_pan<R: Rng + ?Sized>(rng: &mut R, prefix: &str, length: usize) -> String {
+    let mut pan = prefix.to_string();
+    pan.push_str(&random_chars(rng, b"0123456789", length - prefix.len() - 1));
+    let sum: u32 = pan
+        .chars()
+        .rev()
+        .enumerate()
+        .map(|(i, c)| {
+            let d = c.to_digit(10).unwrap();
+            // The check digit will be appended, so the rightmost digit here is doubled.
+            if i % 2 == 0 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    pan.push(char::from_digit((10 - sum % 10) % 10, 10).unwrap());
+    pan
+}
+
+/// Generates an LEI with ISO 17442 (mod 97-10) check digits.
+pub fn lei<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let base = format!(
+        "{}00{}",
+        random_chars(rng, b"0123456789", 4),
+        random_chars(rng, ALPHANUMERIC, 12)
+    );
+    let check = 98 - mod97(&format!("{}00", base));
+    format!("{}{:02}", base, check)
+}
+
+// Remainder modulo 97 of an alphanumeric string where letters stand for
+// 10..35, as used by IBAN and LEI check digits.
+fn mod97(value: &str) -> u32 {
+    value.chars().fold(0, |remainder, c| {
+        let n = c.to_digit(36).unwrap_or(0);
+        if n >= 10 {
+            (remainder * 100 + n) % 97
+        } else {
+            (remainder * 10 + n) % 97
+        }
+    })
+}
+
+fn random_chars<R: Rng + ?Sized>(rng: &mut R, charset: &[u8], count: usize) -> String {
+    (0..count).map(|_| char::from(*charset.choose(rng).unwrap())).collect()
+}
+
+fn group(value: &str, size: usize, separator: char) -> String {
+    let mut grouped = String::new();
+    for (i, c) in value.chars().enumerate() {
+        if i > 0 && i % size == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+// A string or array of strings; `None` when the key is absent.
+fn string_list(value: Option<&Value>, key: &str, field_name: &str) -> Result<Option<Vec<String>>, String> {
+    match value {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(vec![s.clone()])),
+        Some(Value::Array(values)) if !values.is_empty() => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| format!("'{}' for field '{}' must contain only strings", key, field_name)),
+        Some(_) => Err(format!("'{}' for field '{}' must be a string or a non-empty array of strings", key, field_name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+
+    // The check of ISO 13616: the country code and check digits move to the
+    // end and the whole string leaves remainder 1 modulo 97.
+    fn valid_iban(iban: &str) -> bool {
+        mod97(&format!("{}{}", &iban[4..], &iban[..4])) == 1
+    }
+
+    fn valid_luhn(number: &str) -> bool {
+        let sum: u32 = number
+            .chars()
+            .rev()
+            .enumerate()
+            .map(|(i, c)| {
+                let d = c.to_digit(10).unwrap();
+                if i % 2 == 1 { (d * 2) / 10 + (d * 2) % 10 } else { d }
+            })
+            .sum();
+        sum.is_multiple_of(10)
+    }
+
+    fn valid_aba(number: &str) -> bool {
+        let digits: Vec<u32> = number.chars().map(|c| c.to_digit(10).unwrap()).collect();
+        digits.len() == 9 && digits.iter().zip([3, 7, 1, 3, 7, 1, 3, 7, 1]).map(|(d, w)| d * w).sum::<u32>().is_multiple_of(10)
+    }
+
+    #[test]
+    fn check_digit_validators_accept_published_numbers() {
+        assert!(valid_iban("GB82WEST12345698765432"));
+        assert!(valid_iban("DE89370400440532013000"));
+        assert!(!valid_iban("GB83WEST12345698765432"));
+        assert!(valid_luhn("4111111111111111") && valid_luhn("378282246310005"));
+        assert!(!valid_luhn("4111111111111112"));
+        assert!(valid_aba("021000021") && valid_aba("011000015"));
+        assert!(!valid_aba("021000022"));
+        assert_eq!(mod97("5493001KJTIIGC8Y1R12"), 1);
+    }
+
+    #[test]
+    fn ibans_follow_the_country_format_and_pass_mod97() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (country, length, _) in IBAN_FORMATS {
+            for _ in 0..20 {
+                let iban = iban(&mut rng, country).unwrap();
+                assert_eq!(iban.len(), *length, "{}", iban);
+                assert!(iban.starts_with(country) && valid_iban(&iban), "{}", iban);
+            }
+        }
+        assert_eq!(iban(&mut rng, "XX"), None);
+        let generator = PaymentGenerator::parse("iban", json!({ "country": "de", "formatted": true }).as_object().unwrap(), "x").unwrap();
+        let formatted = generator.generate(&mut rng);
+        assert_eq!(formatted.len(), 27);
+        assert!(formatted.starts_with("DE") && valid_iban(&formatted.replace(' ', "")), "{}", formatted);
+    }
+
+    #[test]
+    fn card_numbers_use_test_bins_and_pass_luhn() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for (scheme, prefixes, length) in CARD_SCHEMES {
+            let generator = PaymentGenerator::parse("card_pan", json!({ "scheme": scheme }).as_object().unwrap(), "x").unwrap();
+            for _ in 0..50 {
+                let pan = generator.generate(&mut rng);
+                assert_eq!(pan.len(), *length, "{}", pan);
+                assert!(prefixes.iter().any(|prefix| pan.starts_with(prefix)) && valid_luhn(&pan), "{}", pan);
+            }
+        }
+    }
+
+    #[test]
+    fn routing_numbers_and_leis_have_valid_check_digits() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let routing = aba_routing(&mut rng);
+            assert!(valid_aba(&routing), "{}", routing);
+            assert!(matches!(routing[..2].parse::<u32>().unwrap(), 1..=12 | 21..=32), "{}", routing);
+            let lei = lei(&mut rng);
+            assert_eq!(lei.len(), 20);
+            assert!(lei.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase()) && mod97(&lei) == 1, "{}", lei);
+        }
+    }
+}
@@ -0,0 +1,906 @@
+//! Configs compiled once into typed sampling plans.
+//!
+//! [`Plan::compile`] walks a config with the [`Shape`] of the target type:
+//! it fills in presets, parses every field config into its spec, works out
+//! the order the fields are sampled in and reports the first invalid entry.
+//! The `sample_plan` method generated by the derive then reads the specs by
+//! field index, without looking up keys or parsing JSON again. Templates,
+//! `currency_field` and constraints are bound to the slots of the sibling
+//! fields they read, and every field knows its path for unique values and
+//! sequences, so sampling allocates little beyond the values themselves.
+//! [`Sampler`] holds the plan of one type:
+//!
+//! ```
+//! # use samplify_rs::{SampleContext, Sampleable};
+//! # #[derive(Sampleable)]
+//! # struct User { name: String, age: u32 }
+//! # let config = serde_json::json!({ "name": ["Alice", "Bob"], "age": [18, 65] }).as_object().unwrap().clone();
+//! let sampler = User::compile_sampler(&config)?;
+//! let mut ctx = SampleContext::new().with_seed(1);
+//! for _ in 0..1_000_000 {
+//!     let user = sampler.sample_with_context(&mut ctx)?;
+//! }
+//! # Ok::<(), String>(())
+//! ```
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+
+use crate::constraint::Constraints;
+use crate::context::{locale_directive, Locale};
+use crate::dataset::RefSpec;
+#[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+use crate::decimal::DecimalSpec;
+use crate::config::{FieldConfig, OneOrMany};
+use crate::fields::{field_order, order_by_dependencies, slot_of, Scalar};
+use crate::float::FloatSpec;
+use crate::integer::IntSpec;
+use crate::recursion::{self, RecursiveSpec};
+use crate::shape::{EnumShape, FieldKind, FieldShape, Shape};
+use crate::string::StringSpec;
+use crate::{strict, unique, SampleContext, Sampleable};
+
+/// A config compiled for one type.
+#[derive(Debug, Clone)]
+pub enum Plan {
+    Struct(StructPlan),
+    Enum(EnumPlan),
+    /// The config of a type implementing `Sampleable` by hand, which is
+    /// sampled with `sample_with_context`.
+    Opaque(Map<String, Value>),
+}
+
+/// A compiled struct config.
+#[derive(Debug, Clone)]
+pub struct StructPlan {
+    name: &'static str,
+    locale: Option<Locale>,
+    fields: FieldsPlan,
+    constraints: Constraints,
+}
+
+/// A compiled enum config: the variants that may be picked.
+#[derive(Debug, Clone)]
+pub struct EnumPlan {
+    name: &'static str,
+    locale: Option<Locale>,
+    variants: Vec<VariantPlan>,
+}
+
+/// A compiled variant config. Unit variants have no fields.
+#[derive(Debug, Clone)]
+pub struct VariantPlan {
+    name: &'static str,
+    fields: FieldsPlan,
+}
+
+/// The fields of a struct or variant, in declaration order, and the order
+/// they are sampled in.
+#[derive(Debug, Clone, Default)]
+pub struct FieldsPlan {
+    fields: Vec<FieldPlan>,
+    order: Vec<usize>,
+}
+
+/// A compiled field config.
+#[derive(Debug, Clone)]
+pub struct FieldPlan {
+    name: &'static str,
+    // The path from the root of the plan, e.g. `address.zip`, and the number
+    // of values the field is nested in.
+    path: String,
+    depth: usize,
+    unique: bool,
+    // Whether a sibling template, `currency_field` or constraint reads the
+    // value.
+    recorded: bool,
+    source: Source,
+}
+
+/// Where the value of a field comes from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// `None` for an `Option`, empty for a `Vec`; also used for computed
+    /// fields.
+    None,
+    String(StringSpec),
+    Integer(IntSpec),
+    Float(FloatSpec),
+    Bool(bool),
+    #[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+    Decimal(DecimalSpec),
+    /// The values a `Vec` of primitives picks from.
+    Pick(Vec<Scalar>),
+    /// One plan per item of a `Vec` of structs or enums.
+    Items(Vec<Plan>),
+    Nested(Box<Plan>),
+    Ref(RefSpec),
+    Recursive(RecursiveSpec),
+}
+
+impl Plan {
+    /// Compiles `config` for a type of shape `shape`, filling in the presets
+    /// of `ctx`. A strict context also rejects unknown keys.
+    pub fn compile(shape: &Shape, config: &Map<String, Value>, ctx: &SampleContext) -> Result<Plan, String> {
+        if ctx.is_strict() {
+            strict::check_keys(shape, config)?;
+        }
+        match shape {
+            Shape::Struct(shape) => {
+                let locale = locale_directive(config)?;
+                let config = ctx.apply_presets(config, &preset_fields(&shape.fields));
+                let mut constraints = Constraints::parse(&config)?;
+                let mut fields = FieldsPlan::compile(&shape.fields, &config, ctx)?;
+                fields.link(Some(&mut constraints))?;
+                fields.check_recursion(&shape.fields, shape.name, ctx)?;
+                Ok(Plan::Struct(StructPlan { name: shape.name, locale, constraints, fields }))
+            }
+            Shape::Enum(shape) => compile_enum(shape, config, ctx).map(Plan::Enum),
+            Shape::Opaque => Ok(Plan::Opaque(config.clone())),
+        }
+    }
+
+    /// Whether `f` holds for the source of any field in the plan, including
+    /// nested plans.
+    pub fn any_source(&self, f: &impl Fn(&Source) -> bool) -> bool {
+        let fields: Vec<&FieldsPlan> = match self {
+            Plan::Struct(plan) => vec![&plan.fields],
+            Plan::Enum(plan) => plan.variants.iter().map(|variant| &variant.fields).collect(),
+            Plan::Opaque(_) => Vec::new(),
+        };
+        fields.iter().flat_map(|fields| &fields.fields).any(|field| match &field.source {
+            Source::Items(plans) => plans.iter().any(|plan| plan.any_source(f)),
+            Source::Nested(plan) => plan.any_source(f),
+            source => f(source),
+        })
+    }
+
+    /// The sequence numbers one sample of the plan draws, by field path, if
+    /// every sample draws the same: `None` when the variant picked, the depth
+    /// of a recursive field or a type implementing `Sampleable` by hand may
+    /// change the count.
+    pub fn sequence_draws(&self) -> Option<HashMap<String, u64>> {
+        let mut draws = HashMap::new();
+        self.count_sequence_draws(&mut draws)?;
+        Some(draws)
+    }
+
+    fn count_sequence_draws(&self, draws: &mut HashMap<String, u64>) -> Option<()> {
+        match self {
+            Plan::Struct(plan) => plan.fields.count_sequence_draws(draws),
+            Plan::Enum(plan) => {
+                let mut variants = plan.variants.iter().map(|variant| {
+                    let mut variant_draws = HashMap::new();
+                    variant.fields.count_sequence_draws(&mut variant_draws).map(|()| variant_draws)
+                });
+                let first = variants.next()??;
+                if !variants.all(|other| other.as_ref() == Some(&first)) {
+                    return None;
+                }
+                for (path, count) in first {
+                    *draws.entry(path).or_insert(0) += count;
+                }
+                Some(())
+            }
+            Plan::Opaque(_) => None,
+        }
+    }
+
+    // Moves the plan below field `field_name`: the paths of its fields, and of
+    // the fields of the plans nested in it, start with the field.
+    fn nest_under(&mut self, field_name: &str) {
+        let fields: Vec<&mut FieldsPlan> = match self {
+            Plan::Struct(plan) => vec![&mut plan.fields],
+            Plan::Enum(plan) => plan.variants.iter_mut().map(|variant| &mut variant.fields).collect(),
+            Plan::Opaque(_) => Vec::new(),
+        };
+        for field in fields.into_iter().flat_map(|fields| &mut fields.fields) {
+            field.path = format!("{}.{}", field_name, field.path);
+            field.depth += 1;
+            field.source.nest_under(field_name);
+        }
+    }
+
+    /// The struct plan of type `name`. Generated code calls this.
+    #[doc(hidden)]
+    pub fn as_struct(&self, name: &str) -> Result<&StructPlan, String> {
+        match self {
+            Plan::Struct(plan) if plan.name == name => Ok(plan),
+            _ => Err(format!("Plan was not compiled for struct '{}'", name)),
+        }
+    }
+
+    /// The enum plan of type `name`. Generated code calls this.
+    #[doc(hidden)]
+    pub fn as_enum(&self, name: &str) -> Result<&EnumPlan, String> {
+        match self {
+            Plan::Enum(plan) if plan.name == name => Ok(plan),
+            _ => Err(format!("Plan was not compiled for enum '{}'", name)),
+        }
+    }
+}
+
+/// The typed config of the fields of a struct or variant. Generated code
+/// builds this to compile a typed config without going through JSON.
+#[doc(hidden)]
+pub struct TypedFields<'a> {
+    /// The config of each field in declaration order: `None` for fields the
+    /// config leaves out and for computed fields.
+    pub fields: Vec<Option<&'a dyn FieldConfig>>,
+    /// The `$unique` directive.
+    pub unique: Option<&'a [String]>,
+}
+
+/// A typed struct config. Generated code builds this.
+#[doc(hidden)]
+pub struct TypedStruct<'a> {
+    pub fields: TypedFields<'a>,
+    pub locale: Option<&'a str>,
+    pub constraints: Option<&'a OneOrMany<String>>,
+    pub max_attempts: Option<u32>,
+}
+
+/// A typed enum config. Generated code builds this.
+#[doc(hidden)]
+pub struct TypedEnum<'a> {
+    /// The variants to pick from; all of them if `None`.
+    pub variants: Option<Vec<&'static str>>,
+    /// The config of each variant with fields that `variant_data` holds.
+    pub variant_data: Vec<(&'static str, TypedFields<'a>)>,
+    pub locale: Option<&'a str>,
+}
+
+impl TypedStruct<'_> {
+    /// Compiles the config for a struct of shape `shape`. Fields the config
+    /// leaves out are taken from `defaults`, then from the presets of `ctx`.
+    pub fn compile(self, shape: &Shape, defaults: &Map<String, Value>, ctx: &SampleContext) -> Result<Plan, String> {
+        let shape = match shape {
+            Shape::Struct(shape) => shape,
+            _ => return Err("Typed struct config compiled for a type that is not a struct".to_string()),
+        };
+        let constraints = self.constraints.map(OneOrMany::to_vec).unwrap_or_default();
+        let mut constraints = Constraints::new(constraints.iter().map(String::as_str), self.max_attempts)?;
+        let mut fields = FieldsPlan::compile_typed(&shape.fields, &self.fields, Some(defaults), ctx)?;
+        fields.link(Some(&mut constraints))?;
+        fields.check_recursion(&shape.fields, shape.name, ctx)?;
+        Ok(Plan::Struct(StructPlan { name: shape.name, locale: typed_locale(self.locale)?, constraints, fields }))
+    }
+}
+
+impl TypedEnum<'_> {
+    /// Compiles the config for an enum of shape `shape`.
+    pub fn compile(self, shape: &Shape, ctx: &SampleContext) -> Result<Plan, String> {
+        let shape = match shape {
+            Shape::Enum(shape) => shape,
+            _ => return Err("Typed enum config compiled for a type that is not an enum".to_string()),
+        };
+        let selected = self.variants.unwrap_or_else(|| shape.variants.iter().map(|variant| variant.name).collect());
+        if selected.is_empty() {
+            return Err("No variants specified for enum sampling".to_string());
+        }
+        let mut variants = Vec::with_capacity(selected.len());
+        for name in selected {
+            let variant = shape
+                .variants
+                .iter()
+                .find(|variant| variant.name == name)
+                .ok_or_else(|| format!("Variant '{}' is not recognized", name))?;
+            let mut fields = match &variant.fields {
+                None => FieldsPlan::default(),
+                Some(fields) => match self.variant_data.iter().find(|(data_name, _)| *data_name == name) {
+                    Some((_, data)) => FieldsPlan::compile_typed(fields, data, None, ctx)?,
+                    None => return Err(format!("Configuration for variant '{}' is missing or invalid", name)),
+                },
+            };
+            fields.link(None)?;
+            variants.push(VariantPlan { name: variant.name, fields });
+        }
+        Ok(Plan::Enum(EnumPlan { name: shape.name, locale: typed_locale(self.locale)?, variants }))
+    }
+}
+
+fn typed_locale(locale: Option<&str>) -> Result<Option<Locale>, String> {
+    locale.map(Locale::parse).transpose()
+}
+
+impl StructPlan {
+    /// The `$locale` of the struct config, if any.
+    pub fn locale(&self) -> Option<&Locale> {
+        self.locale.as_ref()
+    }
+
+    pub fn fields(&self) -> &FieldsPlan {
+        &self.fields
+    }
+
+    pub fn constraints(&self) -> &Constraints {
+        &self.constraints
+    }
+}
+
+impl EnumPlan {
+    /// The `$locale` of the enum config, if any.
+    pub fn locale(&self) -> Option<&Locale> {
+        self.locale.as_ref()
+    }
+
+    /// Picks one of the allowed variants.
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> &VariantPlan {
+        self.variants.choose(rng).expect("an enum plan has at least one variant")
+    }
+}
+
+impl VariantPlan {
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn fields(&self) -> &FieldsPlan {
+        &self.fields
+    }
+}
+
+impl FieldsPlan {
+    fn compile(fields: &[FieldShape], config: &Map<String, Value>, ctx: &SampleContext) -> Result<Self, String> {
+        let sampled: Vec<usize> = (0..fields.len()).filter(|i| !fields[*i].computed).collect();
+        let names: Vec<&str> = sampled.iter().map(|i| fields[*i].name).collect();
+        let order = field_order(config, &names)?.into_iter().map(|i| sampled[i]).collect();
+        Ok(FieldsPlan {
+            fields: fields.iter().map(|field| FieldPlan::compile(field, config, ctx)).collect::<Result<_, _>>()?,
+            order,
+        })
+    }
+
+    fn compile_typed(fields: &[FieldShape], typed: &TypedFields, defaults: Option<&Map<String, Value>>, ctx: &SampleContext) -> Result<Self, String> {
+        let mut plans = Vec::with_capacity(fields.len());
+        let mut dependencies = Vec::with_capacity(fields.len());
+        for (field, config) in fields.iter().zip(&typed.fields) {
+            if field.computed {
+                plans.push(FieldPlan::new(field.name, false, Source::None));
+                dependencies.push(Vec::new());
+                continue;
+            }
+            let default = || {
+                defaults
+                    .and_then(|defaults| defaults.get(field.name))
+                    .or_else(|| ctx.presets().lookup(field.name, field.type_name))
+            };
+            let (source, unique) = match config {
+                Some(config) => {
+                    dependencies.push(config.dependencies());
+                    (config.compile(field, ctx)?, config.is_unique())
+                }
+                None => match default() {
+                    Some(value) => {
+                        dependencies.push(crate::fields::dependencies(value));
+                        (compile_value(field, value, ctx)?, value.get("unique") == Some(&Value::Bool(true)))
+                    }
+                    None => {
+                        dependencies.push(Vec::new());
+                        (compile_missing(field)?, false)
+                    }
+                },
+            };
+            let unique = unique || typed.unique.is_some_and(|names| names.iter().any(|name| name == field.name));
+            if unique && !field.kind.is_primitive() {
+                return Err(format!("Field '{}' cannot be unique; only primitive and decimal fields can", field.name));
+            }
+            plans.push(FieldPlan::new(field.name, unique, source));
+        }
+        let sampled: Vec<usize> = (0..fields.len()).filter(|i| !fields[*i].computed).collect();
+        let names: Vec<&str> = sampled.iter().map(|i| fields[*i].name).collect();
+        let order = order_by_dependencies(&names, |i| dependencies[sampled[i]].clone())?;
+        Ok(FieldsPlan { fields: plans, order: order.into_iter().map(|i| sampled[i]).collect() })
+    }
+
+    // Binds the templates and `currency_field`s of the fields, and
+    // `constraints`, to the slots of the sibling fields they read, and marks
+    // those fields as recorded. Constraints must name fields of the struct.
+    fn link(&mut self, constraints: Option<&mut Constraints>) -> Result<(), String> {
+        let names: Vec<&str> = self.fields.iter().map(|field| field.name).collect();
+        let mut read = vec![false; names.len()];
+        for field in &mut self.fields {
+            let slots: Vec<usize> = match &mut field.source {
+                Source::String(spec) => {
+                    spec.bind(&names);
+                    match spec {
+                        StringSpec::Template(template) => template.slots().collect(),
+                        _ => Vec::new(),
+                    }
+                }
+                Source::Float(spec) => {
+                    spec.bind(&names);
+                    spec.currency_slot().into_iter().collect()
+                }
+                #[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+                Source::Decimal(spec) => {
+                    spec.bind(&names);
+                    spec.currency_slot().into_iter().collect()
+                }
+                _ => Vec::new(),
+            };
+            for slot in slots {
+                read[slot] = true;
+            }
+        }
+        if let Some(constraints) = constraints {
+            constraints.bind(&names)?;
+            for slot in constraints.fields().filter_map(|name| slot_of(&names, name)) {
+                read[slot] = true;
+            }
+        }
+        for (field, read) in self.fields.iter_mut().zip(read) {
+            field.recorded = read;
+        }
+        Ok(())
+    }
+
+    // Checks that the recursive fields of struct `type_name`, of shapes
+    // `shapes`, cannot create too many values.
+    fn check_recursion(&self, shapes: &[FieldShape], type_name: &str, ctx: &SampleContext) -> Result<(), String> {
+        let recursive = self.fields.iter().zip(shapes).filter_map(|(field, shape)| match &field.source {
+            Source::Recursive(spec) => Some((spec, matches!(shape.kind, FieldKind::List(_)))),
+            _ => None,
+        });
+        recursion::check_size(recursive, ctx.max_depth(), type_name)
+    }
+
+    fn count_sequence_draws(&self, draws: &mut HashMap<String, u64>) -> Option<()> {
+        for field in &self.fields {
+            let draws_sequence = match &field.source {
+                Source::String(StringSpec::Sequence(_)) | Source::Integer(IntSpec::Sequence(_)) => true,
+                Source::String(StringSpec::Template(template)) => template.draws_sequence(),
+                Source::Nested(plan) => {
+                    plan.count_sequence_draws(draws)?;
+                    false
+                }
+                Source::Items(plans) => {
+                    for plan in plans {
+                        plan.count_sequence_draws(draws)?;
+                    }
+                    false
+                }
+                Source::Recursive(_) => return None,
+                _ => false,
+            };
+            if draws_sequence {
+                *draws.entry(field.path.clone()).or_insert(0) += 1;
+            }
+        }
+        Some(())
+    }
+
+    /// Indices of the fields that are sampled, in sampling order. Computed
+    /// fields are left out.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// The field at declaration index `index`.
+    pub fn field(&self, index: usize) -> &FieldPlan {
+        &self.fields[index]
+    }
+}
+
+impl FieldPlan {
+    // A field at the root of its plan. Plans nested in `source` are moved
+    // below the field.
+    fn new(name: &'static str, unique: bool, mut source: Source) -> Self {
+        source.nest_under(name);
+        FieldPlan { name, path: name.to_string(), depth: 0, unique, recorded: false, source }
+    }
+
+    fn compile(field: &FieldShape, config: &Map<String, Value>, ctx: &SampleContext) -> Result<Self, String> {
+        if field.computed {
+            return Ok(FieldPlan::new(field.name, false, Source::None));
+        }
+        let unique = unique::is_unique(config, field.name)?;
+        if unique && !field.kind.is_primitive() {
+            return Err(format!("Field '{}' cannot be unique; only primitive and decimal fields can", field.name));
+        }
+        let source = match config.get(field.name) {
+            Some(value) => compile_value(field, value, ctx)?,
+            None => compile_missing(field)?,
+        };
+        Ok(FieldPlan::new(field.name, unique, source))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The path of the field from the root of the plan, e.g. `address.zip`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// How many values the field is nested in below the root of the plan.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Whether the field's values must not repeat.
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// Whether sibling fields read the value, so that it must be recorded.
+    pub fn is_recorded(&self) -> bool {
+        self.recorded
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+}
+
+/// Compiles the config of `field` in `config`, the config of the struct or
+/// variant holding it.
+pub(crate) fn check_field(field: &FieldShape, config: &Map<String, Value>) -> Result<(), String> {
+    FieldPlan::compile(field, config, &SampleContext::new()).map(drop)
+}
+
+fn preset_fields(fields: &[FieldShape]) -> Vec<(&str, &str)> {
+    fields.iter().filter(|field| !field.computed).map(|field| (field.name, field.type_name)).collect()
+}
+
+impl Source {
+    fn nest_under(&mut self, field_name: &str) {
+        match self {
+            Source::Nested(plan) => plan.nest_under(field_name),
+            Source::Items(plans) => plans.iter_mut().for_each(|plan| plan.nest_under(field_name)),
+            _ => {}
+        }
+    }
+}
+
+fn compile_enum(shape: &EnumShape, config: &Map<String, Value>, ctx: &SampleContext) -> Result<EnumPlan, String> {
+    let locale = locale_directive(config)?;
+    let selected: Vec<&str> = match config.get("variants") {
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => shape.variants.iter().map(|variant| variant.name).collect(),
+    };
+    if selected.is_empty() {
+        return Err("No variants specified for enum sampling".to_string());
+    }
+    let empty = Map::new();
+    let variant_config = match config.get("variant_data") {
+        Some(Value::Object(map)) => map,
+        _ => &empty,
+    };
+    let mut variants = Vec::with_capacity(selected.len());
+    for name in selected {
+        let variant = shape
+            .variants
+            .iter()
+            .find(|variant| variant.name == name)
+            .ok_or_else(|| format!("Variant '{}' is not recognized", name))?;
+        let mut fields = match &variant.fields {
+            None => FieldsPlan::default(),
+            Some(fields) => match variant_config.get(name) {
+                Some(Value::Object(data)) => {
+                    let data = ctx.apply_presets(data, &preset_fields(fields));
+                    FieldsPlan::compile(fields, &data, ctx)?
+                }
+                _ => return Err(format!("Configuration for variant '{}' is missing or invalid", name)),
+            },
+        };
+        fields.link(None)?;
+        variants.push(VariantPlan { name: variant.name, fields });
+    }
+    Ok(EnumPlan { name: shape.name, locale, variants })
+}
+
+// The source of a field the config leaves out.
+fn compile_missing(field: &FieldShape) -> Result<Source, String> {
+    match field.regex {
+        _ if field.required => Err(format!("Configuration for '{}' is missing", field.name)),
+        Some(pattern) if !field.optional => compile_regex(pattern, field.max_repeat, field.name),
+        _ => Ok(Source::None),
+    }
+}
+
+fn compile_value(field: &FieldShape, value: &Value, ctx: &SampleContext) -> Result<Source, String> {
+    if let Some(map) = value.as_object().filter(|_| ctx.is_strict()) {
+        strict::check_field_keys(field, map)?;
+    }
+    if let Some(map) = value.as_object().filter(|map| map.contains_key("ref")) {
+        if !field.kind.is_primitive() {
+            return Err(format!("Field '{}' cannot refer to a dataset; only primitive and decimal fields can", field.name));
+        }
+        return RefSpec::parse(map, field.name).map(Source::Ref);
+    }
+    if field.recursive {
+        if let Some(spec) = RecursiveSpec::parse(value, field.name)? {
+            return Ok(Source::Recursive(spec));
+        }
+    }
+    if field.optional && value.is_null() {
+        return Ok(Source::None);
+    }
+    compile_kind(&field.kind, value, field.name, ctx)
+}
+
+fn compile_kind(kind: &FieldKind, value: &Value, field_name: &str, ctx: &SampleContext) -> Result<Source, String> {
+    match kind {
+        FieldKind::String => StringSpec::parse(value, field_name).map(Source::String),
+        FieldKind::Integer { min, max } => {
+            let spec = IntSpec::parse(value, field_name)?;
+            spec.check_bounds(*min, *max, field_name)?;
+            Ok(Source::Integer(spec))
+        }
+        FieldKind::Float { max } => {
+            let spec = FloatSpec::parse(value, field_name)?;
+            spec.check_bounds(*max, field_name)?;
+            Ok(Source::Float(spec))
+        }
+        FieldKind::Bool => match value {
+            Value::Bool(value) => Ok(Source::Bool(*value)),
+            _ => Err(format!("Configuration for '{}' must be a boolean", field_name)),
+        },
+        #[cfg(any(feature = "rust_decimal", feature = "bigdecimal"))]
+        FieldKind::Decimal => DecimalSpec::parse(value, field_name).map(Source::Decimal),
+        #[cfg(not(any(feature = "rust_decimal", feature = "bigdecimal")))]
+        FieldKind::Decimal => Err(format!("Field '{}' needs the 'rust_decimal' or 'bigdecimal' feature", field_name)),
+        FieldKind::List(item) => {
+            let items = value
+                .as_array()
+                .ok_or_else(|| format!("Configuration for '{}' must be an array", field_name))?;
+            if let FieldKind::Nested(shape) = item.as_ref() {
+                let shape = shape();
+                return items
+                    .iter()
+                    .map(|item| match item {
+                        Value::Object(item) => Plan::compile(&shape, item, ctx),
+                        _ => Err(format!("Each item in '{}' must be an object", field_name)),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Source::Items);
+            }
+            compile_pick(item, items.iter().cloned(), field_name)
+        }
+        FieldKind::Nested(shape) => match value {
+            Value::Object(map) => Plan::compile(&shape(), map, ctx).map(|plan| Source::Nested(Box::new(plan))),
+            _ => Err(format!("Configuration for '{}' must be an object", field_name)),
+        },
+    }
+}
+
+/// The values of kind `item` a `Vec` field picks from. Values of another
+/// JSON type are left out.
+pub(crate) fn compile_pick(item: &FieldKind, values: impl Iterator<Item = Value>, field_name: &str) -> Result<Source, String> {
+    let values: Vec<Value> = values.filter(|value| item.accepts_value(value)).collect();
+    if values.is_empty() {
+        return Err(format!("Values array for field '{}' is empty or contains invalid types", field_name));
+    }
+    if let Some(value) = values.iter().find(|value| !item.fits(value)) {
+        return Err(format!("Value {} for field '{}' does not fit its type", value, field_name));
+    }
+    Ok(Source::Pick(values.iter().filter_map(Scalar::from_value).collect()))
+}
+
+#[cfg(feature = "regex")]
+fn compile_regex(pattern: &str, max_repeat: u32, field_name: &str) -> Result<Source, String> {
+    crate::pattern::RegexSpec::compile(pattern, max_repeat, false, field_name).map(|spec| Source::String(StringSpec::Regex(spec)))
+}
+
+#[cfg(not(feature = "regex"))]
+fn compile_regex(_pattern: &str, _max_repeat: u32, field_name: &str) -> Result<Source, String> {
+    Err(format!("Field '{}' uses 'regex', which requires the 'regex' feature", field_name))
+}
+
+/// Compiles `config` for `T` and samples one value from the plan. The
+/// `sample_with_context` of derived types calls this, so a JSON config is
+/// checked and sampled exactly like a typed config or a [`Sampler`].
+#[doc(hidden)]
+pub fn sample_compiled<T: Sampleable>(config: &Map<String, Value>, ctx: &mut SampleContext) -> Result<T, String> {
+    let plan = Plan::compile(&T::config_shape(), config, ctx)?;
+    T::sample_plan(&plan, ctx)
+}
+
+/// A compiled config for type `T`, returned by
+/// [`Sampleable::compile_sampler`].
+#[derive(Debug)]
+pub struct Sampler<T> {
+    plan: Plan,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Sampler<T> {
+    fn clone(&self) -> Self {
+        Sampler { plan: self.plan.clone(), _marker: PhantomData }
+    }
+}
+
+impl<T: Sampleable> Sampler<T> {
+    /// Compiles `config` for `T`, filling in the presets of `ctx`.
+    pub fn new(config: &Map<String, Value>, ctx: &SampleContext) -> Result<Self, String> {
+        Ok(Sampler { plan: Plan::compile(&T::config_shape(), config, ctx)?, _marker: PhantomData })
+    }
+
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    /// Samples a value with a fresh [`SampleContext`].
+    pub fn sample(&self) -> Result<T, String> {
+        self.sample_with_context(&mut SampleContext::new())
+    }
+
+    /// Samples a value, sharing `ctx` with every nested value.
+    pub fn sample_with_context(&self, ctx: &mut SampleContext) -> Result<T, String> {
+        T::sample_plan(&self.plan, ctx)
+    }
+
+    /// Samples `n` values with `ctx`.
+    pub fn sample_many(&self, n: usize, ctx: &mut SampleContext) -> Result<Vec<T>, String> {
+        (0..n).map(|_| self.sample_with_context(ctx)).collect()
+    }
+
+    /// Samples `n` values on the rayon thread pool, seeded from `ctx`. See
+    /// [`parallel`](crate::parallel) for how the result stays deterministic.
+    #[cfg(feature = "rayon")]
+    pub fn sample_many_par(&self, n: usize, ctx: &mut SampleContext) -> Result<Vec<T>, String>
+    where
+        T: Send,
+    {
+        crate::parallel::sample_many_par(&self.plan, n, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Sampleable;
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Line {
+        no: String,
+        sku: u32,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Order {
+        currency: String,
+        total: f64,
+        label: String,
+        lines: Vec<Line>,
+        sizes: Vec<u32>,
+    }
+
+    fn config() -> serde_json::Map<String, serde_json::Value> {
+        json!({
+            "currency": ["JPY"],
+            "total": { "range": [1.0, 100.0], "currency_field": "currency" },
+            "label": { "template": "{currency}-{seq}" },
+            "lines": [
+                { "no": { "sequence": true }, "sku": [1, 1000], "$unique": ["sku"] },
+                { "no": { "sequence": true }, "sku": [1, 1000], "$unique": ["sku"] }
+            ],
+            "sizes": [1, 2, 3],
+            "$constraints": ["total >= 1"]
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Batch {
+        codes: Vec<u32>,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    #[sample(constraint = "self.total <= 250")]
+    struct Invoice {
+        amounts: Vec<u32>,
+        #[sample(compute = "invoice_total")]
+        total: u32,
+        #[sample(compute = "invoice_summary")]
+        summary: String,
+    }
+
+    fn invoice_total(invoice: &Invoice) -> u32 {
+        invoice.amounts.iter().sum()
+    }
+
+    fn invoice_summary(invoice: &Invoice) -> String {
+        format!("{} items, {} total", invoice.amounts.len(), invoice.total)
+    }
+
+    #[test]
+    fn json_and_compiled_configs_fail_alike() {
+        let config = json!({ "codes": [1, -1] }).as_object().unwrap().clone();
+        let error = Batch::sample_with_config(&config).unwrap_err();
+        assert_eq!(error, "Value -1 for field 'codes' does not fit its type");
+        let mut ctx = crate::SampleContext::new();
+        assert_eq!(Batch::sample_with_context(&config, &mut ctx).unwrap_err(), error);
+        assert_eq!(Batch::compile_sampler(&config).unwrap_err(), error);
+
+        let config = json!({ "codes": [1, 2] }).as_object().unwrap().clone();
+        let batch = Batch::sample_with_context(&config, &mut ctx).unwrap();
+        assert!(!batch.codes.is_empty() && batch.codes.iter().all(|code| [1, 2].contains(code)));
+    }
+
+    #[test]
+    fn computed_fields_are_filled_in_order_after_the_others() {
+        let config = json!({ "amounts": [10, 20, 30, 40, 50] }).as_object().unwrap().clone();
+        let mut ctx = crate::SampleContext::new().with_seed(11);
+        for _ in 0..20 {
+            let invoice = Invoice::sample_with_context(&config, &mut ctx).unwrap();
+            assert_eq!(invoice.total, invoice.amounts.iter().sum::<u32>());
+            assert_eq!(invoice.summary, format!("{} items, {} total", invoice.amounts.len(), invoice.total));
+            assert!(invoice.total <= 250);
+        }
+        let config = json!({ "amounts": [100, 200] }).as_object().unwrap().clone();
+        // Picking both amounts gives a total of 300, which the constraint
+        // on the computed field rejects.
+        for _ in 0..20 {
+            let invoice = Invoice::sample_with_context(&config, &mut ctx).unwrap();
+            assert_eq!(invoice.amounts.len(), 1);
+            assert!([100, 200].contains(&invoice.total));
+        }
+    }
+
+    #[test]
+    fn computed_fields_take_no_config() {
+        let config = json!({ "amounts": [1, 2], "total": [1, 5] }).as_object().unwrap().clone();
+        let error = Invoice::sample_with_context(&config, &mut crate::SampleContext::new().with_strict(true)).unwrap_err();
+        assert_eq!(error, "Field 'total' of 'Invoice' is computed and takes no config");
+    }
+
+    #[test]
+    fn fields_know_their_paths_and_readers() {
+        let sampler = Order::compile_sampler(&config()).unwrap();
+        let fields = sampler.plan().as_struct("Order").unwrap().fields();
+        let recorded: Vec<(&str, bool)> = (0..5).map(|i| (fields.field(i).path(), fields.field(i).is_recorded())).collect();
+        assert_eq!(
+            recorded,
+            [("currency", true), ("total", true), ("label", false), ("lines", false), ("sizes", false)]
+        );
+        let super::Source::Items(lines) = fields.field(3).source() else { panic!("expected items") };
+        let line = lines[1].as_struct("Line").unwrap().fields().field(1);
+        assert_eq!((line.path(), line.depth()), ("lines.sku", 1));
+    }
+
+    #[test]
+    fn reused_samplers_match_sample_with_context_for_a_seed() {
+        let sampler = Order::compile_sampler(&config()).unwrap();
+        let from_json = |seed: u64| {
+            let mut ctx = crate::SampleContext::new().with_seed(seed);
+            (0..10).map(|_| format!("{:?}", Order::sample_with_context(&config(), &mut ctx).unwrap())).collect::<Vec<_>>()
+        };
+        for seed in [1, 2] {
+            // The sampler keeps no state, so each seeded context starts over.
+            for sampler in [&sampler, &sampler.clone()] {
+                let mut ctx = crate::SampleContext::new().with_seed(seed);
+                let orders: Vec<String> = sampler.sample_many(10, &mut ctx).unwrap().iter().map(|order| format!("{:?}", order)).collect();
+                assert_eq!(orders, from_json(seed));
+            }
+        }
+        assert_ne!(from_json(1), from_json(2));
+    }
+
+    #[test]
+    fn plans_sample_like_configs() {
+        let sampler = Order::compile_sampler(&config()).unwrap();
+        let mut ctx = crate::SampleContext::new().with_seed(7);
+        let orders = sampler.sample_many(3, &mut ctx).unwrap();
+        let labels: Vec<&str> = orders.iter().map(|order| order.label.as_str()).collect();
+        assert_eq!(labels, ["JPY-1", "JPY-2", "JPY-3"]);
+        assert!(orders.iter().all(|order| order.currency == "JPY" && order.total.fract() == 0.0));
+        let nos: Vec<&str> = orders.iter().flat_map(|order| &order.lines).map(|line| line.no.as_str()).collect();
+        assert_eq!(nos, ["1", "2", "3", "4", "5", "6"]);
+        let mut skus: Vec<u32> = orders.iter().flat_map(|order| &order.lines).map(|line| line.sku).collect();
+        skus.sort_unstable();
+        skus.dedup();
+        assert_eq!(skus.len(), 6);
+        assert_eq!(ctx.unique_values().count("lines.sku"), 6);
+        assert!(orders.iter().flat_map(|order| &order.sizes).all(|size| (1..=3).contains(size)));
+    }
+}
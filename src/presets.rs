@@ -0,0 +1,240 @@
+//! Default configs for fields that the user config leaves out.
+//!
+//! A [`Presets`] registry maps type names and field names to field configs.
+//! Every struct and enum variant fills keys missing from its config from the
+//! registry of the [`SampleContext`](crate::SampleContext) before sampling: first by the field's type name (ignoring `Option` and
+//! `Box`), then by the field name. Field names are compared case-insensitively
+//! and without underscores, so a preset for `CreDtTm` applies to `cre_dt_tm`.
+//!
+//! When the config does give an object for a field whose type has a preset,
+//! the keys it lacks are taken from the preset, so `{"ccy": "JPY"}` for an
+//! `ActiveCurrencyAndAmount` keeps the preset's `value`. Presets also fill
+//! `Option` fields, which would otherwise be `None` when their key is missing.
+//!
+//! A new context holds [`Presets::iso20022`], so `sample_with_config` and
+//! `compile_sampler` use them too; `with_presets(Presets::new())` turns them
+//! off.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+/// A registry of field configs keyed by type name and field name.
+#[derive(Debug, Clone, Default)]
+pub struct Presets {
+    by_type: HashMap<String, Value>,
+    by_field: HashMap<String, Value>,
+}
+
+impl Presets {
+    pub fn new() -> Self {
+        Presets::default()
+    }
+
+    /// Presets for structs generated from the ISO 20022 message schemas
+    /// (pacs.008, pain.001, camt.053 and others).
+    ///
+    /// Simple types such as `BICFIDec2014Identifier`, `IBAN2007Identifier`,
+    /// `ActiveCurrencyCode`, `ISODateTime` or `Max35Text` are expected to be
+    /// structs with a single `value` field; `ActiveCurrencyAndAmount` and
+    /// `ActiveOrHistoricCurrencyAndAmount` to have `value: f64` and
+    /// `ccy: String`. Element names such as `BICFI`, `IBAN`, `Ccy`, `MsgId`
+    /// or `CreDtTm` are covered as `String` fields.
+    pub fn iso20022() -> Self {
+        let bic = json!({ "generator": "bic" });
+        let iban = json!({ "generator": "iban" });
+        let currency = json!({ "generator": "currency" });
+        let lei = json!({ "generator": "lei" });
+        let date_time = json!({ "template": "{datetime}" });
+        let date = json!({ "template": "{date}" });
+        let uuid = json!({ "template": "{uuid}" });
+        let country = json!(["DE", "FR", "GB", "NL", "BE", "IT", "ES", "CH", "US", "JP"]);
+        let identifier = json!({ "len": [12, 35], "charset": "alphanumeric", "case": "upper" });
+        let amount = json!({
+            "ccy": currency,
+            "value": { "range": [1.0, 100000.0], "currency_field": "ccy" }
+        });
+
+        let mut presets = Presets::new();
+        for (name, config) in [
+            ("BICFIDec2014Identifier", &bic),
+            ("BICFIIdentifier", &bic),
+            ("AnyBICDec2014Identifier", &bic),
+            ("AnyBICIdentifier", &bic),
+            ("IBAN2007Identifier", &iban),
+            ("ActiveCurrencyCode", &currency),
+            ("ActiveOrHistoricCurrencyCode", &currency),
+            ("LEIIdentifier", &lei),
+            ("ISODateTime", &date_time),
+            ("ISODate", &date),
+            ("UUIDv4Identifier", &uuid),
+            ("CountryCode", &country),
+            ("Max16Text", &json!({ "len": [1, 16], "charset": "alphanumeric" })),
+            ("Max35Text", &identifier),
+            ("Max70Text", &json!({ "len": [1, 70], "charset": "alphanumeric" })),
+            ("Max140Text", &json!({ "len": [1, 140], "charset": "alphanumeric" })),
+        ] {
+            presets = presets.with_type(name, json!({ "value": config }));
+        }
+        presets = presets
+            .with_type("ActiveCurrencyAndAmount", amount.clone())
+            .with_type("ActiveOrHistoricCurrencyAndAmount", amount);
+
+        for (name, config) in [
+            ("BICFI", &bic),
+            ("AnyBIC", &bic),
+            ("IBAN", &iban),
+            ("Ccy", &currency),
+            ("LEI", &lei),
+            ("CreDtTm", &date_time),
+            ("IntrBkSttlmDt", &date),
+            ("UETR", &uuid),
+            ("Ctry", &country),
+            ("MsgId", &identifier),
+            ("PmtInfId", &identifier),
+            ("InstrId", &identifier),
+            ("EndToEndId", &identifier),
+            ("TxId", &identifier),
+            ("NbOfTxs", &json!({ "template": "{int:1..=99}" })),
+        ] {
+            presets = presets.with_field(name, config.clone());
+        }
+        presets
+    }
+
+    /// Uses `config` for fields of type `type_name` (the last path segment,
+    /// e.g. `IBAN2007Identifier`).
+    pub fn with_type(mut self, type_name: &str, config: Value) -> Self {
+        self.by_type.insert(type_name.to_string(), config);
+        self
+    }
+
+    /// Uses `config` for fields called `field_name`.
+    pub fn with_field(mut self, field_name: &str, config: Value) -> Self {
+        self.by_field.insert(normalize(field_name), config);
+        self
+    }
+
+    /// Adds the entries of `other`, replacing entries with the same key.
+    pub fn merge(mut self, other: Presets) -> Self {
+        self.by_type.extend(other.by_type);
+        self.by_field.extend(other.by_field);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_type.is_empty() && self.by_field.is_empty()
+    }
+
+    /// The preset for fields of type `type_name`, if any.
+    pub fn for_type(&self, type_name: &str) -> Option<&Value> {
+        self.by_type.get(type_name)
+    }
+
+    /// The preset for a field, if any.
+    pub fn lookup(&self, field_name: &str, type_name: &str) -> Option<&Value> {
+        self.by_type.get(type_name).or_else(|| self.by_field.get(&normalize(field_name)))
+    }
+
+    /// Returns a copy of `config` with the keys of `fields` (name and type
+    /// name) that it lacks filled from the registry, or `None` if no preset
+    /// applies.
+    pub(crate) fn fill(&self, config: &Map<String, Value>, fields: &[(&str, &str)]) -> Option<Map<String, Value>> {
+        let mut filled: Option<Map<String, Value>> = None;
+        for (field_name, type_name) in fields {
+            match (config.get(*field_name), self.by_type.get(*type_name)) {
+                (None, _) => {
+                    if let Some(preset) = self.lookup(field_name, type_name) {
+                        filled
+                            .get_or_insert_with(|| config.clone())
+                            .insert(field_name.to_string(), preset.clone());
+                    }
+                }
+                (Some(Value::Object(given)), Some(Value::Object(preset))) => {
+                    if preset.keys().any(|key| !given.contains_key(key)) {
+                        let mut merged = given.clone();
+                        for (key, value) in preset {
+                            merged.entry(key.clone()).or_insert_with(|| value.clone());
+                        }
+                        filled
+                            .get_or_insert_with(|| config.clone())
+                            .insert(field_name.to_string(), Value::Object(merged));
+                    }
+                }
+                (Some(_), _) => {}
+            }
+        }
+        filled
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| *c != '_').map(|c| c.to_ascii_lowercase()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{SampleContext, Sampleable};
+
+    use super::*;
+
+    #[derive(Debug, crate::Sampleable)]
+    struct ActiveCurrencyAndAmount {
+        value: f64,
+        ccy: String,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Transfer {
+        bicfi: String,
+        msg_id: String,
+        uetr: Option<String>,
+        amount: ActiveCurrencyAndAmount,
+    }
+
+    fn config(changes: Value) -> Map<String, Value> {
+        changes.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn built_in_presets_apply_through_sample_with_config() {
+        for _ in 0..20 {
+            let transfer = Transfer::sample_with_config(&config(json!({ "msg_id": "MSG-1" }))).unwrap();
+            assert!([8, 11].contains(&transfer.bicfi.len()), "{:?}", transfer);
+            assert_eq!(transfer.msg_id, "MSG-1");
+            assert_eq!(transfer.uetr.map(|uetr| uetr.len()), Some(36));
+            let minor_units = crate::currency::minor_units(&transfer.amount.ccy).unwrap();
+            let scaled = transfer.amount.value * 10f64.powi(minor_units as i32);
+            assert!((scaled - scaled.round()).abs() < 1e-6, "{:?}", transfer.amount);
+            assert!((1.0..=100000.0).contains(&transfer.amount.value));
+        }
+    }
+
+    #[test]
+    fn built_in_presets_apply_to_compiled_samplers() {
+        let sampler = Transfer::compile_sampler(&config(json!({ "amount": { "ccy": "JPY" } }))).unwrap();
+        let mut ctx = SampleContext::new().with_seed(5);
+        for transfer in sampler.sample_many(20, &mut ctx).unwrap() {
+            assert_eq!(transfer.amount.ccy, "JPY");
+            assert_eq!(transfer.amount.value.fract(), 0.0);
+            assert!(!transfer.msg_id.is_empty());
+        }
+    }
+
+    #[test]
+    fn presets_can_be_turned_off() {
+        let mut ctx = SampleContext::new().with_presets(Presets::new());
+        let error = Transfer::sample_with_context(&config(json!({ "msg_id": "MSG-1" })), &mut ctx).unwrap_err();
+        assert_eq!(error, "Configuration for 'bicfi' is missing");
+    }
+
+    #[test]
+    fn field_names_match_without_case_or_underscores() {
+        let presets = Presets::new().with_field("CreDtTm", json!("x")).with_type("ISODate", json!("y"));
+        assert_eq!(presets.lookup("cre_dt_tm", "String"), Some(&json!("x")));
+        assert_eq!(presets.lookup("cre_dt_tm", "ISODate"), Some(&json!("y")));
+        assert_eq!(presets.lookup("created", "String"), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! Self-referential fields sampled from the config of the struct they are in.
+//!
+//! A field of type `Option<Box<Self>>`, `Box<Self>` or `Vec<Self>` can use
+//!
+//! ```json
+//! { "next": { "recursive": { "max_depth": 5, "leaf_probability": 0.3 } } }
+//! ```
+//!
+//! to sample its value with the same config as the enclosing struct, so
+//! linked lists and trees need no hand-nested config. Recursion stops at
+//! `max_depth` levels, when the context's maximum nesting depth is reached,
+//! or at random with `leaf_probability`: an `Option` becomes `None` and a
+//! `Vec` empty, while a required `Box` fails. A `Vec` field takes its length
+//! from `len` (`[1, 3]` by default) next to `recursive`.
+//!
+//! Each level multiplies the values a `Vec` field may create, so a config
+//! whose recursive fields could create more than [`MAX_VALUES`] values in
+//! one sample, counting every item at its largest `len` down to `max_depth`,
+//! fails to compile.
+
+use rand::Rng;
+use serde_json::{Map, Value};
+
+/// Levels of recursion when `max_depth` is not given.
+pub const DEFAULT_MAX_DEPTH: u32 = 5;
+
+/// Values the recursive fields of a struct may create in one sample.
+pub const MAX_VALUES: u64 = 100_000;
+
+/// A parsed `recursive` field configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveSpec {
+    max_depth: u32,
+    leaf_probability: f64,
+    min_len: usize,
+    max_len: usize,
+}
+
+impl RecursiveSpec {
+    /// Reads the `recursive` option of field `field_name`, if it has one.
+    pub fn from_field(config: &Map<String, Value>, field_name: &str) -> Result<Option<Self>, String> {
+        match config.get(field_name) {
+            Some(field_config) => Self::parse(field_config, field_name),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the `recursive` option from the config of a field, if it has
+    /// one.
+    pub fn parse(field_config: &Value, field_name: &str) -> Result<Option<Self>, String> {
+        let field_config = match field_config {
+            Value::Object(field_config) => field_config,
+            _ => return Ok(None),
+        };
+        let options = match field_config.get("recursive") {
+            None => return Ok(None),
+            Some(Value::Bool(true)) => Map::new(),
+            Some(Value::Object(options)) => options.clone(),
+            Some(_) => return Err(format!("'recursive' for field '{}' must be true or an object", field_name)),
+        };
+        let max_depth = match options.get("max_depth") {
+            None => None,
+            Some(depth) => Some(
+                depth
+                    .as_u64()
+                    .map(|depth| depth.min(u64::from(u32::MAX)) as u32)
+                    .ok_or_else(|| format!("'max_depth' for field '{}' must be a non-negative integer", field_name))?,
+            ),
+        };
+        let leaf_probability = match options.get("leaf_probability") {
+            None => None,
+            Some(p) => Some(
+                p.as_f64()
+                    .ok_or_else(|| format!("'leaf_probability' for field '{}' must be between 0 and 1", field_name))?,
+            ),
+        };
+        let len = match field_config.get("len") {
+            None => None,
+            Some(Value::Array(bounds)) if bounds.len() == 2 => match (bounds[0].as_u64(), bounds[1].as_u64()) {
+                (Some(min), Some(max)) => Some([min, max]),
+                _ => return Err(format!("'len' for field '{}' must be [min, max] with min <= max", field_name)),
+            },
+            Some(_) => return Err(format!("'len' for field '{}' must be an array [min, max]", field_name)),
+        };
+        Self::new(max_depth, leaf_probability, len, field_name).map(Some)
+    }
+
+    /// Recursion limited to `max_depth` levels (default
+    /// [`DEFAULT_MAX_DEPTH`]), stopping at random with `leaf_probability`
+    /// (default 0). `len` bounds the items of a `Vec` field (default
+    /// `[1, 3]`).
+    pub fn new(max_depth: Option<u32>, leaf_probability: Option<f64>, len: Option<[u64; 2]>, field_name: &str) -> Result<Self, String> {
+        let leaf_probability = leaf_probability.unwrap_or(0.0);
+        if !(0.0..=1.0).contains(&leaf_probability) {
+            return Err(format!("'leaf_probability' for field '{}' must be between 0 and 1", field_name));
+        }
+        let [min_len, max_len] = len.unwrap_or([1, 3]);
+        if min_len > max_len {
+            return Err(format!("'len' for field '{}' must be [min, max] with min <= max", field_name));
+        }
+        Ok(RecursiveSpec {
+            max_depth: max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+            leaf_probability,
+            min_len: min_len as usize,
+            max_len: max_len as usize,
+        })
+    }
+
+    /// Levels of recursion allowed below the first sample.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Whether to stop at random at this level.
+    pub fn random_leaf<R: Rng + ?Sized>(&self, rng: &mut R) -> bool {
+        rng.gen_bool(self.leaf_probability)
+    }
+
+    /// Number of items for a `Vec` field.
+    pub fn len<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        rng.gen_range(self.min_len..=self.max_len)
+    }
+}
+
+/// Checks that `fields`, the recursive fields of struct `type_name` with
+/// whether each is a `Vec`, cannot create more than [`MAX_VALUES`] values in
+/// one sample. Recursion also stops `max_nesting` levels deep, the limit of
+/// the context.
+pub fn check_size<'a>(fields: impl IntoIterator<Item = (&'a RecursiveSpec, bool)>, max_nesting: usize, type_name: &str) -> Result<(), String> {
+    let fields: Vec<(u32, u64)> = fields
+        .into_iter()
+        .map(|(spec, is_vec)| (spec.max_depth, if is_vec { spec.max_len as u64 } else { 1 }))
+        .collect();
+    let max_nesting = u32::try_from(max_nesting.saturating_sub(1)).unwrap_or(u32::MAX);
+    let depth = fields.iter().map(|(max_depth, _)| *max_depth).max().unwrap_or(0).min(max_nesting);
+    let (mut total, mut level) = (1u64, 1u64);
+    for d in 0..depth {
+        // Fields whose own `max_depth` is reached stop at this level.
+        let fan_out: u64 = fields.iter().filter(|(max_depth, _)| *max_depth > d).map(|(_, items)| items).sum();
+        level = level.saturating_mul(fan_out);
+        total = total.saturating_add(level);
+        if total > MAX_VALUES {
+            return Err(format!(
+                "Recursive fields of '{}' may create more than {} values in one sample; lower their 'max_depth' or 'len'",
+                type_name, MAX_VALUES
+            ));
+        }
+        if level == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map, Value};
+
+    use super::*;
+    use crate::{SampleContext, Sampleable};
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Tree {
+        value: u32,
+        children: Vec<Tree>,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Chain {
+        value: u32,
+        next: Option<Box<Chain>>,
+    }
+
+    fn config(changes: Value) -> Map<String, Value> {
+        let mut config = json!({ "value": [0, 10] });
+        config.as_object_mut().unwrap().extend(changes.as_object().cloned().unwrap());
+        config.as_object().cloned().unwrap()
+    }
+
+    fn depth(tree: &Tree) -> usize {
+        1 + tree.children.iter().map(depth).max().unwrap_or(0)
+    }
+
+    fn size(tree: &Tree) -> usize {
+        1 + tree.children.iter().map(size).sum::<usize>()
+    }
+
+    #[test]
+    fn recursion_stops_at_max_depth() {
+        let config = config(json!({ "children": { "recursive": { "max_depth": 2 }, "len": [2, 2] } }));
+        let tree = Tree::sample_with_config(&config).unwrap();
+        assert_eq!((depth(&tree), size(&tree)), (3, 7));
+        assert!(tree.value < 10 && tree.children.iter().flat_map(|child| &child.children).all(|leaf| leaf.children.is_empty()));
+
+        // The nesting limit of the context stops recursion first.
+        let mut ctx = SampleContext::new().with_max_depth(2);
+        let tree = Tree::sample_with_context(&config, &mut ctx).unwrap();
+        assert_eq!(depth(&tree), 2);
+    }
+
+    #[test]
+    fn optional_fields_become_none_at_the_limit() {
+        let mut chain = Chain::sample_with_config(&config(json!({ "next": { "recursive": { "max_depth": 3 } } }))).unwrap();
+        let mut links = 1;
+        while let Some(next) = chain.next {
+            chain = *next;
+            links += 1;
+        }
+        assert_eq!((links, chain.value < 10), (4, true));
+
+        let leaf = config(json!({ "next": { "recursive": { "leaf_probability": 1.0 } } }));
+        assert!(Chain::sample_with_config(&leaf).unwrap().next.is_none());
+    }
+
+    #[test]
+    fn configs_that_may_create_too_many_values_fail_to_compile() {
+        let error = format!("Recursive fields of 'Tree' may create more than {} values in one sample; lower their 'max_depth' or 'len'", MAX_VALUES);
+        let wide = config(json!({ "children": { "recursive": { "max_depth": 6 }, "len": [0, 10] } }));
+        assert_eq!(Tree::compile_sampler(&wide).unwrap_err(), error);
+        assert_eq!(Tree::sample_with_config(&wide).unwrap_err(), error);
+        // 1 + 10 + ... + 10^4 values fit.
+        let narrower = config(json!({ "children": { "recursive": { "max_depth": 4, "leaf_probability": 0.9 }, "len": [0, 10] } }));
+        assert!(Tree::sample_with_config(&narrower).is_ok());
+        // A chain of options grows by one value per level, and the nesting
+        // limit of the context ends it.
+        let long = config(json!({ "next": { "recursive": { "max_depth": 1_000_000 } } }));
+        assert!(Chain::compile_sampler(&long).is_ok());
+    }
+}
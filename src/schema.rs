@@ -0,0 +1,610 @@
+//! JSON Schema for the configs of sampleable types.
+//!
+//! [`json_schema`] describes the config accepted by a [`Shape`], for editor
+//! completion and for validating config files in CI:
+//!
+//! ```
+//! # use samplify_rs::Sampleable;
+//! # #[derive(Sampleable)]
+//! # struct User { name: String, age: u32 }
+//! # let dir = std::env::temp_dir();
+//! let schema = User::config_json_schema();
+//! std::fs::write(dir.join("user.schema.json"), serde_json::to_string_pretty(&schema)?)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The schema follows draft 2020-12. Every struct and enum reachable from the
+//! type gets an entry in `$defs`, so recursive types are described once.
+//! Entries are named after the type; a second type with the same name is
+//! named by its full path, such as `billing::Address`. Struct configs list
+//! their fields, the `$` directives and which fields are required; unknown
+//! keys are rejected as in [strict mode](crate::strict). Field configs list
+//! the forms the samplers accept: literals, choices, ranges and the options
+//! objects of each generator. The schema checks forms, not values; an empty
+//! range or an unknown country code is still reported when sampling. Fields
+//! that [presets](crate::Presets) fill in are still listed as required.
+//!
+//! The keys of [composed](crate::compose) configs are accepted: `$defs`,
+//! `$profiles` and `$extends` at the top level, and `$ref` objects in place
+//! of any field config or list item. A file with `$extends` holds part of a
+//! config, so it is only checked to be an object.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Map, Value};
+
+use crate::payments::PaymentGenerator;
+use crate::shape::{EnumShape, FieldKind, FieldShape, Shape};
+use crate::strict::{STRUCT_DIRECTIVES, VARIANT_DIRECTIVES};
+
+/// The `$schema` of generated schemas.
+pub const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The schema of the configs accepted by `shape`.
+pub fn json_schema(shape: &Shape) -> Value {
+    let mut defs = Defs::default();
+    let top = match reference(shape, &mut defs)["$ref"].as_str() {
+        // The definition is copied so that only the top level takes the keys
+        // of composed configs.
+        Some(pointer) => {
+            let mut top = defs.schemas[&pointer["#/$defs/".len()..]].clone();
+            top["properties"].as_object_mut().expect("definitions list properties").extend([
+                ("$defs".to_string(), json!({ "type": "object" })),
+                ("$profiles".to_string(), json!({ "type": "object", "additionalProperties": { "type": "object" } })),
+            ]);
+            top
+        }
+        None => json!({ "type": "object" }),
+    };
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), json!(DIALECT));
+    if let Some((_, name)) = shape_id(shape) {
+        schema.insert("title".to_string(), json!(name));
+    }
+    schema.insert("anyOf".to_string(), json!([top, extends_options(), composed()]));
+    if !defs.schemas.is_empty() {
+        schema.insert("$defs".to_string(), Value::Object(defs.schemas));
+    }
+    Value::Object(schema)
+}
+
+// The definitions of the types reached so far, and the name of each type
+// under `$defs` by module and type name.
+#[derive(Default)]
+struct Defs {
+    schemas: Map<String, Value>,
+    names: HashMap<(&'static str, &'static str), String>,
+}
+
+fn shape_id(shape: &Shape) -> Option<(&'static str, &'static str)> {
+    match shape {
+        Shape::Struct(shape) => Some((shape.module, shape.name)),
+        Shape::Enum(shape) => Some((shape.module, shape.name)),
+        Shape::Opaque => None,
+    }
+}
+
+// A reference to the definition of `shape`, added to `defs` on first use.
+fn reference(shape: &Shape, defs: &mut Defs) -> Value {
+    let (module, name) = match shape_id(shape) {
+        Some(id) => id,
+        None => return json!({ "type": "object" }),
+    };
+    let key = match defs.names.get(&(module, name)) {
+        Some(key) => key.clone(),
+        None => {
+            let key = if defs.schemas.contains_key(name) { format!("{}::{}", module, name) } else { name.to_string() };
+            // Reserve the name first, so a type reaching itself refers to it.
+            defs.names.insert((module, name), key.clone());
+            defs.schemas.insert(key.clone(), Value::Null);
+            let schema = match shape {
+                Shape::Struct(shape) => fields_schema(&shape.fields, STRUCT_DIRECTIVES, defs),
+                Shape::Enum(shape) => enum_schema(shape, defs),
+                Shape::Opaque => unreachable!("opaque shapes have no name"),
+            };
+            defs.schemas.insert(key.clone(), schema);
+            key
+        }
+    };
+    json!({ "$ref": format!("#/$defs/{}", key) })
+}
+
+// A config taking the place of another through `$ref`, with keys next to it
+// replacing those of the target.
+fn composed() -> Value {
+    json!({ "type": "object", "properties": { "$ref": { "type": "string" } }, "required": ["$ref"] })
+}
+
+fn extends_options() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "$extends": { "anyOf": [{ "type": "string" }, { "type": "array", "items": { "type": "string" } }] },
+        },
+        "required": ["$extends"],
+    })
+}
+
+fn fields_schema(fields: &[FieldShape], directives: &[&str], defs: &mut Defs) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let configurable: Vec<&FieldShape> = fields.iter().filter(|field| !field.computed).collect();
+    for field in &configurable {
+        properties.insert(field.name.to_string(), field_schema(field, defs));
+        if field.required {
+            required.push(field.name);
+        }
+    }
+    let field_names: Vec<&str> = configurable.iter().map(|field| field.name).collect();
+    for directive in directives {
+        properties.insert(directive.to_string(), directive_schema(directive, &field_names));
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn directive_schema(directive: &str, field_names: &[&str]) -> Value {
+    match directive {
+        "$locale" => json!({ "type": "string" }),
+        "$constraints" => json!({ "anyOf": [{ "type": "string" }, { "type": "array", "items": { "type": "string" } }] }),
+        "$max_attempts" => json!({ "type": "integer", "minimum": 1 }),
+        "$unique" => json!({ "type": "array", "items": { "enum": field_names } }),
+        _ => json!({}),
+    }
+}
+
+fn enum_schema(shape: &EnumShape, defs: &mut Defs) -> Value {
+    let names: Vec<&str> = shape.variants.iter().map(|variant| variant.name).collect();
+    let mut variant_data = Map::new();
+    for variant in &shape.variants {
+        let schema = match &variant.fields {
+            Some(fields) => fields_schema(fields, VARIANT_DIRECTIVES, defs),
+            None => json!({ "type": "object" }),
+        };
+        variant_data.insert(variant.name.to_string(), schema);
+    }
+    json!({
+        "type": "object",
+        "properties": {
+            "variants": { "type": "array", "items": { "enum": names }, "minItems": 1 },
+            "variant_data": { "type": "object", "properties": variant_data, "additionalProperties": false },
+            "$locale": { "type": "string" },
+        },
+        "additionalProperties": false,
+    })
+}
+
+fn field_schema(field: &FieldShape, defs: &mut Defs) -> Value {
+    let mut forms = kind_forms(&field.kind, defs);
+    if field.kind.is_primitive() {
+        forms.push(ref_options());
+    }
+    if field.recursive {
+        forms.push(recursive_options());
+    }
+    if field.optional {
+        forms.push(json!({ "type": "null" }));
+    }
+    forms.push(composed());
+    json!({ "anyOf": forms })
+}
+
+// The forms of config a field of `kind` accepts, besides `ref` and
+// `recursive`.
+fn kind_forms(kind: &FieldKind, defs: &mut Defs) -> Vec<Value> {
+    match kind {
+        FieldKind::String => vec![json!({ "type": "string" }), choices(json!({ "type": "string" })), string_options()],
+        FieldKind::Integer { .. } => vec![pair(json!({ "type": "integer" })), options(sequence_properties(), &["sequence"])],
+        FieldKind::Float { .. } => vec![
+            pair(json!({ "type": "number" })),
+            options(
+                json!({
+                    "range": pair(json!({ "type": "number" })),
+                    "decimals": { "type": "integer", "minimum": 0, "maximum": 15 },
+                    "currency_field": { "type": "string" },
+                }),
+                &["range"],
+            ),
+        ],
+        FieldKind::Bool => vec![json!({ "type": "boolean" })],
+        FieldKind::Decimal => vec![decimal_value(), pair(decimal_value()), decimal_options()],
+        FieldKind::List(item) => match item.as_ref() {
+            FieldKind::Nested(shape) => {
+                vec![json!({ "type": "array", "items": { "anyOf": [reference(&shape(), defs), composed()] } })]
+            }
+            item => vec![choices(value_type(item))],
+        },
+        FieldKind::Nested(shape) => vec![reference(&shape(), defs)],
+    }
+}
+
+// One value of a `Vec` of primitives.
+fn value_type(kind: &FieldKind) -> Value {
+    match kind {
+        FieldKind::String | FieldKind::Decimal => json!({ "type": "string" }),
+        FieldKind::Integer { min, max } => json!({ "type": "integer", "minimum": min, "maximum": max }),
+        FieldKind::Float { max } => json!({ "type": "number", "minimum": -max, "maximum": max }),
+        FieldKind::Bool => json!({ "type": "boolean" }),
+        FieldKind::List(_) | FieldKind::Nested(_) => json!(false),
+    }
+}
+
+fn choices(item: Value) -> Value {
+    json!({ "type": "array", "items": item, "minItems": 1 })
+}
+
+fn pair(item: Value) -> Value {
+    json!({ "type": "array", "items": item, "minItems": 2, "maxItems": 2 })
+}
+
+// An options object with `properties` and `unique`, requiring one of the
+// `required` keys.
+fn options(properties: Value, required: &[&str]) -> Value {
+    let mut properties = match properties {
+        Value::Object(properties) => properties,
+        _ => Map::new(),
+    };
+    properties.insert("unique".to_string(), json!({ "type": "boolean" }));
+    let required: Vec<Value> = required.iter().map(|key| json!({ "required": [key] })).collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "anyOf": required,
+        "additionalProperties": false,
+    })
+}
+
+fn ref_options() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "ref": { "type": "string", "pattern": "^[^.]+(\\.[^.]+)+$" },
+            "cardinality": {
+                "type": "array",
+                "prefixItems": [
+                    { "type": "integer", "minimum": 0 },
+                    { "anyOf": [{ "type": "integer", "minimum": 1 }, { "type": "null" }] },
+                ],
+                "minItems": 2,
+                "maxItems": 2,
+            },
+            "unique": { "type": "boolean" },
+        },
+        "required": ["ref"],
+        "additionalProperties": false,
+    })
+}
+
+fn recursive_options() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "recursive": {
+                "anyOf": [
+                    { "const": true },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "max_depth": { "type": "integer", "minimum": 0 },
+                            "leaf_probability": { "type": "number", "minimum": 0, "maximum": 1 },
+                        },
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+            "len": pair(json!({ "type": "integer", "minimum": 0 })),
+        },
+        "required": ["recursive"],
+        "additionalProperties": false,
+    })
+}
+
+fn sequence_properties() -> Value {
+    json!({
+        "sequence": {
+            "anyOf": [
+                { "const": true },
+                {
+                    "type": "object",
+                    "properties": {
+                        "start": { "type": "integer" },
+                        "step": { "type": "integer", "not": { "const": 0 } },
+                        "prefix": { "type": "string" },
+                        "suffix": { "type": "string" },
+                        "width": { "type": "integer", "minimum": 0 },
+                    },
+                    "additionalProperties": false,
+                },
+            ],
+        },
+    })
+}
+
+fn string_options() -> Value {
+    let one_or_many = json!({ "anyOf": [{ "type": "string" }, { "type": "array", "items": { "type": "string" } }] });
+    let mut properties = match sequence_properties() {
+        Value::Object(properties) => properties,
+        _ => Map::new(),
+    };
+    let required = [
+        &["sequence", "generator", "template", "len", "charset"][..],
+        if cfg!(feature = "faker") { &["fake"] } else { &[] },
+        if cfg!(feature = "regex") { &["regex"] } else { &[] },
+    ]
+    .concat();
+    properties.extend([
+        ("generator".to_string(), json!({ "enum": PaymentGenerator::NAMES })),
+        ("country".to_string(), one_or_many.clone()),
+        ("formatted".to_string(), json!({ "type": "boolean" })),
+        ("length".to_string(), json!({ "enum": [8, 11] })),
+        ("scheme".to_string(), one_or_many),
+        (
+            "minor_units".to_string(),
+            json!({ "anyOf": [{ "type": "integer", "minimum": 0 }, { "type": "array", "items": { "type": "integer", "minimum": 0 } }] }),
+        ),
+        ("template".to_string(), json!({ "type": "string" })),
+        (
+            "len".to_string(),
+            json!({ "anyOf": [{ "type": "integer", "minimum": 0 }, pair(json!({ "type": "integer", "minimum": 0 }))] }),
+        ),
+        ("charset".to_string(), json!({ "type": "string", "minLength": 1 })),
+        ("case".to_string(), json!({ "enum": ["upper", "lower"] })),
+    ]);
+    #[cfg(feature = "faker")]
+    {
+        properties.insert("fake".to_string(), json!({ "enum": crate::fake::Provider::names().collect::<Vec<_>>() }));
+        properties.insert("locale".to_string(), json!({ "type": "string" }));
+    }
+    #[cfg(feature = "regex")]
+    {
+        properties.insert("regex".to_string(), json!({ "type": "string" }));
+        properties.insert("max_repeat".to_string(), json!({ "type": "integer", "minimum": 1 }));
+        properties.insert("unicode".to_string(), json!({ "type": "boolean" }));
+    }
+    options(Value::Object(properties), &required)
+}
+
+fn decimal_value() -> Value {
+    json!({ "type": ["string", "number"] })
+}
+
+fn decimal_options() -> Value {
+    options(
+        json!({
+            "range": pair(decimal_value()),
+            "scale": { "type": "integer", "minimum": 0, "maximum": 28 },
+            "distribution": { "enum": ["uniform", "log_uniform", "normal"] },
+            "mean": decimal_value(),
+            "std_dev": decimal_value(),
+            "currency_field": { "type": "string" },
+        }),
+        &["range"],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::StructShape;
+    use crate::Sampleable;
+
+    // Checks `value` against the keywords the generated schemas use, and
+    // returns the JSON pointers of the values that fail. `pattern` is not
+    // checked.
+    fn validate(schema: &Value, root: &Value, value: &Value, at: &str) -> Vec<String> {
+        let Some(schema) = schema.as_object() else { return Vec::new() };
+        let fail = || vec![at.to_string()];
+        if let Some(pointer) = schema.get("$ref").and_then(Value::as_str) {
+            let target = root.pointer(&pointer[1..]).unwrap_or_else(|| panic!("dangling {}", pointer));
+            return validate(target, root, value, at);
+        }
+        if let Some(forms) = schema.get("anyOf").and_then(Value::as_array) {
+            if forms.iter().all(|form| !validate(form, root, value, at).is_empty()) {
+                return fail();
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if validate(not, root, value, at).is_empty() {
+                return fail();
+            }
+        }
+        if schema.get("const").is_some_and(|constant| constant != value)
+            || schema.get("enum").and_then(Value::as_array).is_some_and(|values| !values.contains(value))
+        {
+            return fail();
+        }
+        if let Some(types) = schema.get("type") {
+            let is = |name: &Value| match name.as_str() {
+                Some("object") => value.is_object(),
+                Some("array") => value.is_array(),
+                Some("string") => value.is_string(),
+                Some("number") => value.is_number(),
+                Some("integer") => value.is_i64() || value.is_u64(),
+                Some("boolean") => value.is_boolean(),
+                Some("null") => value.is_null(),
+                _ => panic!("unknown type {}", name),
+            };
+            let matches = match types {
+                Value::Array(types) => types.iter().any(is),
+                name => is(name),
+            };
+            if !matches {
+                return fail();
+            }
+        }
+        let number = value.as_f64();
+        if schema.get("minimum").and_then(Value::as_f64).is_some_and(|min| number.is_some_and(|n| n < min))
+            || schema.get("maximum").and_then(Value::as_f64).is_some_and(|max| number.is_some_and(|n| n > max))
+            || schema.get("minLength").and_then(Value::as_u64).is_some_and(|min| value.as_str().is_some_and(|s| (s.chars().count() as u64) < min))
+        {
+            return fail();
+        }
+        let mut failures = Vec::new();
+        if let Value::Array(items) = value {
+            let len = items.len() as u64;
+            if schema.get("minItems").and_then(Value::as_u64).is_some_and(|min| len < min)
+                || schema.get("maxItems").and_then(Value::as_u64).is_some_and(|max| len > max)
+            {
+                return fail();
+            }
+            let prefix = schema.get("prefixItems").and_then(Value::as_array).map_or(&[][..], Vec::as_slice);
+            for (i, item) in items.iter().enumerate() {
+                let item_schema = prefix.get(i).or_else(|| schema.get("items"));
+                if let Some(item_schema) = item_schema {
+                    failures.extend(validate(item_schema, root, item, &format!("{}/{}", at, i)));
+                }
+            }
+        }
+        if let Value::Object(map) = value {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+                if !map.contains_key(name.as_str().unwrap()) {
+                    failures.push(format!("{}/{}", at, name.as_str().unwrap()));
+                }
+            }
+            for (key, item) in map {
+                let path = format!("{}/{}", at, key);
+                match (properties.and_then(|properties| properties.get(key)), schema.get("additionalProperties")) {
+                    (Some(property), _) => failures.extend(validate(property, root, item, &path)),
+                    (None, Some(Value::Bool(false))) => failures.push(path),
+                    (None, Some(additional)) => failures.extend(validate(additional, root, item, &path)),
+                    (None, None) => {}
+                }
+            }
+        }
+        failures
+    }
+
+    fn check<T: Sampleable>(config: &Value) -> Vec<String> {
+        let schema = T::config_json_schema();
+        validate(&schema, &schema, config, "")
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    enum Expr {
+        Number(i64),
+        Neg(Box<Expr>),
+        Sum { terms: Vec<Expr> },
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Contact {
+        email: String,
+        phone: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    enum Status {
+        Active,
+        Suspended { reason: String, days: u32 },
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Account {
+        id: u64,
+        balance: f64,
+        verified: bool,
+        scores: Vec<u32>,
+        contacts: Vec<Contact>,
+        billing: Option<Contact>,
+        status: Status,
+        formula: Expr,
+        parent: Option<Box<Account>>,
+        children: Vec<Account>,
+        #[sample(compute = "contact_count")]
+        contact_count: usize,
+    }
+
+    fn contact_count(account: &Account) -> usize {
+        account.contacts.len()
+    }
+
+    #[test]
+    fn templates_validate_against_the_schema() {
+        assert_eq!(check::<Account>(&Account::config_template()), Vec::<String>::new());
+        assert_eq!(check::<Contact>(&Contact::config_template()), Vec::<String>::new());
+        assert_eq!(check::<Status>(&Status::config_template()), Vec::<String>::new());
+        assert_eq!(check::<Expr>(&Expr::config_template()), Vec::<String>::new());
+
+        // The configs the template stands for sample, so the schema is not
+        // looser than the samplers here.
+        let account = Account::sample_with_config(Account::config_template().as_object().unwrap()).unwrap();
+        assert_eq!(account.contact_count, account.contacts.len());
+    }
+
+    #[test]
+    fn edited_templates_fail_where_they_break_the_schema() {
+        let edits: [fn(&mut Value); 6] = [
+            |config| config["id"] = json!("many"),
+            |config| config["contacts"][0]["emial"] = json!(["a@example.com"]),
+            |config| config["status"]["variants"] = json!(["Closed"]),
+            |config| config["contact_count"] = json!([1, 2]),
+            |config| config["formula"]["variant_data"]["Sum"]["terms"] = json!({ "recursive": "yes" }),
+            |config| drop(config.as_object_mut().unwrap().remove("balance")),
+        ];
+        for (i, edit) in edits.iter().enumerate() {
+            let mut config = Account::config_template();
+            edit(&mut config);
+            assert!(!check::<Account>(&config).is_empty(), "edit {} passed: {}", i, config);
+        }
+    }
+
+    fn address(module: &'static str, field: &'static str) -> Shape {
+        Shape::Struct(StructShape { name: "Address", module, fields: vec![FieldShape::required(field, FieldKind::String)] })
+    }
+
+    fn shipping_address() -> Shape {
+        address("shop::shipping", "street")
+    }
+
+    fn billing_address() -> Shape {
+        address("shop::billing", "iban")
+    }
+
+    fn order() -> Shape {
+        Shape::Struct(StructShape {
+            name: "Order",
+            module: "shop",
+            fields: vec![
+                FieldShape::required("shipping", FieldKind::Nested(shipping_address)),
+                FieldShape::required("billing", FieldKind::Nested(billing_address)),
+                FieldShape::required("returns", FieldKind::Nested(shipping_address)),
+            ],
+        })
+    }
+
+    #[test]
+    fn types_sharing_a_name_get_separate_definitions() {
+        let schema = json_schema(&order());
+        let defs = schema["$defs"].as_object().unwrap();
+        assert_eq!(defs.keys().collect::<Vec<_>>(), ["Address", "Order", "shop::billing::Address"]);
+        assert!(defs["Address"]["properties"]["street"].is_object());
+        assert!(defs["shop::billing::Address"]["properties"]["iban"].is_object());
+        let fields = &defs["Order"]["properties"];
+        assert_eq!(fields["shipping"]["anyOf"][0], json!({ "$ref": "#/$defs/Address" }));
+        assert_eq!(fields["returns"]["anyOf"][0], json!({ "$ref": "#/$defs/Address" }));
+        assert_eq!(fields["billing"]["anyOf"][0], json!({ "$ref": "#/$defs/shop::billing::Address" }));
+    }
+
+    #[test]
+    fn composed_configs_are_accepted() {
+        let schema = json_schema(&order());
+        assert_eq!(schema["title"], json!("Order"));
+        let [top, extends, reference] = [0, 1, 2].map(|i| &schema["anyOf"][i]);
+        assert_eq!(top["additionalProperties"], json!(false));
+        assert_eq!(top["required"], json!(["shipping", "billing", "returns"]));
+        assert!(top["properties"]["$defs"].is_object() && top["properties"]["$profiles"].is_object());
+        assert_eq!(extends["required"], json!(["$extends"]));
+        assert_eq!(reference["required"], json!(["$ref"]));
+        // Only the top level takes `$defs` and `$profiles`.
+        assert!(schema["$defs"]["Order"]["properties"].get("$defs").is_none());
+        let shipping = schema["$defs"]["Order"]["properties"]["shipping"]["anyOf"].as_array().unwrap();
+        assert!(shipping.contains(&composed()));
+        let street = schema["$defs"]["Address"]["properties"]["street"]["anyOf"].as_array().unwrap();
+        assert!(street.contains(&composed()));
+    }
+}
@@ -0,0 +1,165 @@
+//! Auto-incrementing fields.
+//!
+//! `{"sequence": {"start": 1, "step": 1}}` on an integer field yields 1, 2,
+//! 3, … across every sample drawn with the same
+//! [`SampleContext`](crate::SampleContext), including the items of a `Vec`
+//! and the values of [`Sampleable::sample_many`](crate::Sampleable::sample_many).
+//! On a `String` field, `prefix`, `suffix` and `width` format the number:
+//! `{"sequence": {"prefix": "ORD-", "width": 6}}` yields `ORD-000001`. A
+//! [template](crate::template) can count the same way with `{seq:06}`.
+//!
+//! Counters are kept per field path. Numbers drawn by a sample that a
+//! [constraint](crate::constraint) rejects are handed out again, so the
+//! numbering has no gaps.
+
+use serde_json::{Map, Value};
+
+use crate::context::FieldAt;
+use crate::SampleContext;
+
+/// A parsed `sequence` configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceSpec {
+    start: i64,
+    step: i64,
+    prefix: String,
+    suffix: String,
+    width: usize,
+}
+
+impl SequenceSpec {
+    /// Parses the `sequence` entry of a field config: `true` or an object
+    /// with optional `start`, `step`, `prefix`, `suffix` and `width`.
+    pub fn parse(value: &Value, field_name: &str) -> Result<Self, String> {
+        let empty = Map::new();
+        let map = match value {
+            Value::Bool(true) => &empty,
+            Value::Object(map) => map,
+            _ => return Err(format!("Sequence for field '{}' must be true or an object", field_name)),
+        };
+        let integer = |key: &str, default: i64| match map.get(key) {
+            None => Ok(default),
+            Some(value) => value
+                .as_i64()
+                .ok_or_else(|| format!("'{}' of the sequence for field '{}' must be an integer", key, field_name)),
+        };
+        let text = |key: &str| match map.get(key) {
+            None => Ok(String::new()),
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(_) => Err(format!("'{}' of the sequence for field '{}' must be a string", key, field_name)),
+        };
+        let width = match map.get("width") {
+            None => 0,
+            Some(width) => width
+                .as_u64()
+                .ok_or_else(|| format!("'width' of the sequence for field '{}' must be a positive integer", field_name))?,
+        };
+        Self::new(integer("start", 1)?, integer("step", 1)?, text("prefix")?, text("suffix")?, width, field_name)
+    }
+
+    /// Builds a sequence counting from `start` in steps of `step`, formatted
+    /// with `prefix`, `suffix` and zero padding to `width` digits.
+    pub fn new(start: i64, step: i64, prefix: String, suffix: String, width: u64, field_name: &str) -> Result<Self, String> {
+        if step == 0 {
+            return Err(format!("Step of the sequence for field '{}' must not be zero", field_name));
+        }
+        Ok(SequenceSpec { start, step, prefix, suffix, width: width as usize })
+    }
+
+    /// The first number of the sequence.
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// The next number of the sequence of `field`.
+    pub fn next(&self, ctx: &mut SampleContext, field: FieldAt) -> i64 {
+        ctx.next_in_sequence(field, self.start, self.step)
+    }
+
+    /// The next number, formatted with the prefix, suffix and zero padding.
+    pub fn next_string(&self, ctx: &mut SampleContext, field: FieldAt) -> String {
+        let number = self.next(ctx, field);
+        format!("{}{:0width$}{}", self.prefix, number, self.suffix, width = self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Sampleable;
+
+    use super::*;
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Line {
+        line_no: u32,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Order {
+        id: i64,
+        order_number: String,
+        lines: Vec<Line>,
+    }
+
+    fn config() -> Map<String, Value> {
+        json!({
+            "id": { "sequence": { "start": 1, "step": 1 } },
+            "order_number": { "sequence": { "prefix": "ORD-", "width": 6 } },
+            "lines": [
+                { "line_no": { "sequence": true } },
+                { "line_no": { "sequence": true } }
+            ]
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn sequences_count_across_a_batch_and_its_items() {
+        let orders = Order::sample_many(&config(), 3).unwrap();
+        let ids: Vec<i64> = orders.iter().map(|order| order.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        let numbers: Vec<&str> = orders.iter().map(|order| order.order_number.as_str()).collect();
+        assert_eq!(numbers, ["ORD-000001", "ORD-000002", "ORD-000003"]);
+        let line_nos: Vec<u32> = orders.iter().flat_map(|order| &order.lines).map(|line| line.line_no).collect();
+        assert_eq!(line_nos, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn contexts_keep_counting_across_calls() {
+        let mut ctx = SampleContext::new();
+        let first = Order::sample_with_context(&config(), &mut ctx).unwrap();
+        let second = Order::sample_with_context(&config(), &mut ctx).unwrap();
+        assert_eq!((first.id, second.id), (1, 2));
+        assert_eq!(second.lines[0].line_no, 3);
+        let fresh = Order::sample_with_config(&config()).unwrap();
+        assert_eq!(fresh.id, 1);
+    }
+
+    #[test]
+    fn start_step_and_suffix_shape_the_numbers() {
+        let mut config = config();
+        config["id"] = json!({ "sequence": { "start": 100, "step": -10 } });
+        config["order_number"] = json!({ "sequence": { "start": 7, "suffix": "/A", "width": 3 } });
+        let orders = Order::sample_many(&config, 3).unwrap();
+        let ids: Vec<i64> = orders.iter().map(|order| order.id).collect();
+        assert_eq!(ids, [100, 90, 80]);
+        let numbers: Vec<&str> = orders.iter().map(|order| order.order_number.as_str()).collect();
+        assert_eq!(numbers, ["007/A", "008/A", "009/A"]);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_sequences() {
+        let error = |value: Value| SequenceSpec::parse(&value, "id").unwrap_err();
+        assert_eq!(error(json!(5)), "Sequence for field 'id' must be true or an object");
+        assert_eq!(error(json!(false)), "Sequence for field 'id' must be true or an object");
+        assert_eq!(error(json!({ "step": 0 })), "Step of the sequence for field 'id' must not be zero");
+        assert_eq!(error(json!({ "start": 1.5 })), "'start' of the sequence for field 'id' must be an integer");
+        assert_eq!(error(json!({ "prefix": 1 })), "'prefix' of the sequence for field 'id' must be a string");
+        assert_eq!(error(json!({ "width": -1 })), "'width' of the sequence for field 'id' must be a positive integer");
+        assert_eq!(SequenceSpec::parse(&json!(true), "id").unwrap().start(), 1);
+    }
+}
@@ -0,0 +1,368 @@
+//! Descriptions of the configs that sampleable types accept.
+//!
+//! `#[derive(Sampleable)]` implements
+//! [`Sampleable::config_shape`](crate::Sampleable::config_shape), which lists
+//! the fields of a struct (or the variants of an enum) with the kind of config
+//! each one takes. [`Shape::validate`] checks a whole config against it
+//! without sampling anything, and [`Plan::compile`] turns a config into the
+//! typed plan that batches sample from.
+
+use serde_json::{json, Map, Value};
+
+use crate::plan::Plan;
+use crate::SampleContext;
+
+/// The config accepted by a type.
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Struct(StructShape),
+    Enum(EnumShape),
+    /// A type implementing `Sampleable` by hand; any object is accepted.
+    Opaque,
+}
+
+/// The fields of a struct.
+#[derive(Debug, Clone)]
+pub struct StructShape {
+    pub name: &'static str,
+    /// The module defining the type, as given by `module_path!`.
+    pub module: &'static str,
+    pub fields: Vec<FieldShape>,
+}
+
+/// The variants of an enum.
+#[derive(Debug, Clone)]
+pub struct EnumShape {
+    pub name: &'static str,
+    /// The module defining the type, as given by `module_path!`.
+    pub module: &'static str,
+    pub variants: Vec<VariantShape>,
+}
+
+/// One enum variant. Tuple variant fields are named `field0`, `field1`, ...
+#[derive(Debug, Clone)]
+pub struct VariantShape {
+    pub name: &'static str,
+    /// `None` for a unit variant, which takes no config.
+    pub fields: Option<Vec<FieldShape>>,
+}
+
+/// One field of a struct or variant.
+#[derive(Debug, Clone)]
+pub struct FieldShape {
+    pub name: &'static str,
+    /// The name presets are looked up by (see [`Presets`](crate::Presets)).
+    pub type_name: &'static str,
+    pub kind: FieldKind,
+    /// Whether sampling fails when the config leaves the field out. `Option`,
+    /// `Vec`, computed fields and fields with a `regex` attribute are not
+    /// required.
+    pub required: bool,
+    /// `Option` fields also accept `null`.
+    pub optional: bool,
+    /// Filled by a `compute` function instead of the config.
+    pub computed: bool,
+    /// A self-referential field, which accepts a `recursive` config.
+    pub recursive: bool,
+    /// The pattern of a `#[sample(regex = "...")]` attribute, used when the
+    /// config leaves the field out.
+    pub regex: Option<&'static str>,
+    /// The `max_repeat` of the regex attribute.
+    pub max_repeat: u32,
+}
+
+#[cfg(test)]
+impl FieldShape {
+    /// A required field of `kind`, for tests that build shapes by hand.
+    pub(crate) fn required(name: &'static str, kind: FieldKind) -> Self {
+        FieldShape {
+            name,
+            type_name: "",
+            kind,
+            required: true,
+            optional: false,
+            computed: false,
+            recursive: false,
+            regex: None,
+            max_repeat: 0,
+        }
+    }
+}
+
+/// The kind of config a field takes, looking through `Option` and `Box`.
+#[derive(Debug, Clone)]
+pub enum FieldKind {
+    String,
+    /// An integer type, with its smallest and largest value.
+    Integer { min: i128, max: i128 },
+    /// A float type, with its largest finite value.
+    Float { max: f64 },
+    Bool,
+    Decimal,
+    /// A `Vec`: the config of the items, or the values to pick from.
+    List(Box<FieldKind>),
+    /// A nested struct or enum. The shape is built on demand, so recursive
+    /// types have a finite description.
+    Nested(fn() -> Shape),
+}
+
+impl Shape {
+    /// Checks `config` and everything nested in it, after filling in the
+    /// presets of `ctx`.
+    pub fn validate(&self, config: &Map<String, Value>, ctx: &SampleContext) -> Result<(), String> {
+        Plan::compile(self, config, ctx).map(drop)
+    }
+
+    /// An example config setting every field, to be saved and edited: ranges
+    /// for numbers, choices for strings and lists, every variant of an enum
+    /// and nested types written out. Self-referential fields use
+    /// `"recursive": true`; other fields that lead back to a type being
+    /// written out are `null` or `[]` when they can be. Otherwise they are
+    /// `{}`, and the enum variant holding them is left out of `variants`.
+    pub fn template(&self) -> Value {
+        self.template_within(&mut Vec::new())
+    }
+
+    // `outer` holds the names of the types being written out.
+    fn template_within(&self, outer: &mut Vec<&'static str>) -> Value {
+        let template = match self {
+            Shape::Struct(shape) => {
+                outer.push(shape.name);
+                Value::Object(fields_template(&shape.fields, outer).0)
+            }
+            Shape::Enum(shape) => {
+                // Variants that cannot be written out without repeating the
+                // enum keep their data but are left out of `variants`.
+                outer.push(shape.name);
+                let mut names = Vec::new();
+                let mut variant_data = Map::new();
+                for variant in &shape.variants {
+                    let complete = match &variant.fields {
+                        Some(fields) => {
+                            let (templates, complete) = fields_template(fields, outer);
+                            variant_data.insert(variant.name.to_string(), Value::Object(templates));
+                            complete
+                        }
+                        None => true,
+                    };
+                    if complete {
+                        names.push(variant.name);
+                    }
+                }
+                if names.is_empty() {
+                    names = shape.variants.iter().map(|variant| variant.name).collect();
+                }
+                json!({ "variants": names, "variant_data": variant_data })
+            }
+            Shape::Opaque => return json!({}),
+        };
+        outer.pop();
+        template
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        match self {
+            Shape::Struct(shape) => Some(shape.name),
+            Shape::Enum(shape) => Some(shape.name),
+            Shape::Opaque => None,
+        }
+    }
+}
+
+// The templates of `fields`, and whether none of them is a `{}` placeholder
+// for a required field leading back to a type being written out.
+fn fields_template(fields: &[FieldShape], outer: &mut Vec<&'static str>) -> (Map<String, Value>, bool) {
+    let mut complete = true;
+    let templates = fields
+        .iter()
+        .filter(|field| !field.computed)
+        .map(|field| {
+            let template = field_template(field, outer).unwrap_or_else(|| {
+                complete = false;
+                json!({})
+            });
+            (field.name.to_string(), template)
+        })
+        .collect();
+    (templates, complete)
+}
+
+fn field_template(field: &FieldShape, outer: &mut Vec<&'static str>) -> Option<Value> {
+    if field.recursive {
+        return Some(match field.kind {
+            FieldKind::List(_) => json!({ "recursive": true, "len": [1, 3] }),
+            _ => json!({ "recursive": true }),
+        });
+    }
+    match &field.kind {
+        FieldKind::Nested(shape) => match nested_template(shape(), outer) {
+            Some(template) => Some(template),
+            None if field.optional => Some(Value::Null),
+            None => None,
+        },
+        FieldKind::List(item) => match item.as_ref() {
+            FieldKind::Nested(shape) => Some(Value::Array(nested_template(shape(), outer).into_iter().collect())),
+            item => Some(item.choices_template(field.name)),
+        },
+        kind => Some(kind.value_template(field.name)),
+    }
+}
+
+// The template of a nested type, or `None` if it is being written out
+// already.
+fn nested_template(shape: Shape, outer: &mut Vec<&'static str>) -> Option<Value> {
+    match shape.name() {
+        Some(name) if outer.contains(&name) => None,
+        _ => Some(shape.template_within(outer)),
+    }
+}
+
+impl FieldKind {
+    /// Whether fields of this kind are recorded, and so can be unique or
+    /// refer to a dataset.
+    pub fn is_primitive(&self) -> bool {
+        !matches!(self, FieldKind::List(_) | FieldKind::Nested(_))
+    }
+
+    // A placeholder config for a primitive field of this kind.
+    fn value_template(&self, field_name: &str) -> Value {
+        match self {
+            FieldKind::String => json!([format!("{}_1", field_name), format!("{}_2", field_name)]),
+            FieldKind::Integer { .. } => json!([0, 100]),
+            FieldKind::Float { .. } => json!([0.0, 100.0]),
+            FieldKind::Bool => json!(true),
+            FieldKind::Decimal => json!(["0.00", "100.00"]),
+            FieldKind::List(_) | FieldKind::Nested(_) => Value::Null,
+        }
+    }
+
+    // Placeholder values to pick from for a `Vec` of this kind.
+    fn choices_template(&self, field_name: &str) -> Value {
+        match self {
+            FieldKind::String => json!([format!("{}_1", field_name), format!("{}_2", field_name), format!("{}_3", field_name)]),
+            FieldKind::Integer { .. } => json!([1, 2, 3]),
+            FieldKind::Float { .. } => json!([1.5, 2.5, 3.5]),
+            FieldKind::Bool => json!([true, false]),
+            FieldKind::Decimal => json!(["1.00", "2.00", "3.00"]),
+            FieldKind::List(_) | FieldKind::Nested(_) => json!([]),
+        }
+    }
+
+    // Whether `value` can be picked as one item of a `Vec` of this kind.
+    pub(crate) fn accepts_value(&self, value: &Value) -> bool {
+        match self {
+            FieldKind::String | FieldKind::Decimal => value.is_string(),
+            FieldKind::Integer { .. } => value.is_i64(),
+            FieldKind::Float { .. } => value.is_number(),
+            FieldKind::Bool => value.is_boolean(),
+            FieldKind::List(_) | FieldKind::Nested(_) => false,
+        }
+    }
+
+    // Whether an accepted `value` lies within the bounds of the type.
+    pub(crate) fn fits(&self, value: &Value) -> bool {
+        match self {
+            FieldKind::Integer { min, max } => value.as_i64().is_some_and(|n| (*min..=*max).contains(&i128::from(n))),
+            FieldKind::Float { max } => value.as_f64().is_some_and(|n| n.abs() <= *max),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ConfigOf;
+    use crate::Sampleable;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    enum Expr {
+        Number(i64),
+        Neg(Box<Expr>),
+        Sum { terms: Vec<Expr> },
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Address {
+        street: String,
+        zip: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    enum Status {
+        Active,
+        Suspended { reason: String, days: u32 },
+    }
+
+    #[derive(Debug, PartialEq, crate::Sampleable)]
+    struct Account {
+        id: u64,
+        balance: f64,
+        verified: bool,
+        tags: Vec<String>,
+        addresses: Vec<Address>,
+        billing: Option<Address>,
+        status: Status,
+        formula: Expr,
+        parent: Option<Box<Account>>,
+        #[sample(compute = "address_count")]
+        address_count: usize,
+    }
+
+    fn address_count(account: &Account) -> usize {
+        account.addresses.len()
+    }
+
+    #[test]
+    fn templates_set_every_configurable_field() {
+        let template = Account::config_template();
+        let address = json!({ "street": ["street_1", "street_2"], "zip": ["zip_1", "zip_2"] });
+        assert_eq!(
+            template,
+            json!({
+                "id": [0, 100],
+                "balance": [0.0, 100.0],
+                "verified": true,
+                "tags": ["tags_1", "tags_2", "tags_3"],
+                "addresses": [address],
+                "billing": address,
+                "status": {
+                    "variants": ["Active", "Suspended"],
+                    "variant_data": { "Suspended": { "reason": ["reason_1", "reason_2"], "days": [0, 100] } }
+                },
+                "formula": {
+                    "variants": ["Number", "Sum"],
+                    "variant_data": { "Neg": { "field0": {} }, "Number": { "field0": [0, 100] }, "Sum": { "terms": [] } }
+                },
+                "parent": { "recursive": true }
+            })
+        );
+    }
+
+    #[test]
+    fn templates_are_working_configs() {
+        let template = Account::config_template();
+        let config = template.as_object().unwrap();
+        let mut ctx = SampleContext::new().with_strict(true).with_seed(3);
+        for _ in 0..10 {
+            let account = Account::sample_with_context(config, &mut ctx).unwrap();
+            assert!(account.id < 100 && (0.0..100.0).contains(&account.balance) && account.verified);
+            assert!(account.tags.iter().all(|tag| ["tags_1", "tags_2", "tags_3"].contains(&tag.as_str())));
+            assert_eq!(account.address_count, account.addresses.len());
+            for address in account.addresses.iter().chain(&account.billing) {
+                assert!(address.street.starts_with("street_") && address.zip.as_deref().is_some_and(|zip| zip.starts_with("zip_")));
+            }
+            match account.formula {
+                Expr::Number(n) => assert!((0..100).contains(&n)),
+                Expr::Sum { terms } => assert!(terms.is_empty()),
+                Expr::Neg(_) => panic!("'Neg' is not among the template's variants"),
+            }
+            if let Status::Suspended { reason, days } = account.status {
+                assert!(reason.starts_with("reason_") && days < 100);
+            }
+        }
+        let typed: ConfigOf<Account> = serde_json::from_value(template).unwrap();
+        Account::sample_with_config(&typed).unwrap();
+    }
+}
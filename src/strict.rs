@@ -0,0 +1,276 @@
+//! Rejecting config keys that name nothing.
+//!
+//! Configs are read by looking up the keys a type expects, so other keys are
+//! ignored: a misspelled `"zipcod"` is skipped and `zipcode` is then reported
+//! missing. A context built with
+//! [`SampleContext::with_strict`](crate::SampleContext::with_strict) checks
+//! every struct and enum config before using it. Each key must name a field,
+//! a variant or a `$` directive, and an unknown key fails with the closest
+//! known name:
+//!
+//! ```text
+//! Unknown field 'zipcod' in the config of 'Address'; did you mean 'zipcode'?
+//! ```
+//!
+//! The object config of a field is checked against the keys of the form it
+//! takes, chosen by the first of its keys the parser of the field would read
+//! (`sequence`, `generator`, `template`, `fake`, `regex`, then `len` or
+//! `charset` for a `String`). A misspelled option fails the same way, and so
+//! does a key that selects another form, such as `template` next to `regex`,
+//! which would otherwise be ignored:
+//!
+//! ```text
+//! Unknown key 'charsett' in the config of 'code'; did you mean 'charset'?
+//! Key 'regex' of field 'code' does not go with 'template'
+//! ```
+
+use serde_json::{Map, Value};
+
+use crate::shape::{EnumShape, FieldKind, FieldShape, Shape};
+
+/// Directives a struct config may contain.
+pub const STRUCT_DIRECTIVES: &[&str] = &["$locale", "$constraints", "$max_attempts", "$unique"];
+
+/// Directives a `variant_data` entry may contain.
+pub const VARIANT_DIRECTIVES: &[&str] = &["$unique"];
+
+/// Keys an enum config may contain.
+pub const ENUM_KEYS: &[&str] = &["variants", "variant_data", "$locale"];
+
+// An object form of a field config: the keys that select it, and the
+// options it takes besides `unique`.
+struct Form {
+    keys: &'static [&'static str],
+    options: &'static [&'static str],
+}
+
+// The forms of each kind of field, in the order their parsers try them.
+const STRING_FORMS: &[Form] = &[
+    Form { keys: &["sequence"], options: &[] },
+    Form { keys: &["generator"], options: &["country", "formatted", "length", "scheme", "minor_units"] },
+    Form { keys: &["template"], options: &[] },
+    Form { keys: &["fake"], options: &["locale"] },
+    Form { keys: &["regex"], options: &["max_repeat", "unicode"] },
+    Form { keys: &["len", "charset"], options: &["case"] },
+];
+const INTEGER_FORMS: &[Form] = &[Form { keys: &["sequence"], options: &[] }];
+const FLOAT_FORMS: &[Form] = &[Form { keys: &["range"], options: &["decimals", "currency_field"] }];
+const DECIMAL_FORMS: &[Form] = &[Form { keys: &["range"], options: &["scale", "distribution", "mean", "std_dev", "currency_field"] }];
+const REF_FORM: Form = Form { keys: &["ref"], options: &["cardinality"] };
+const RECURSIVE_FORM: Form = Form { keys: &["recursive"], options: &["len"] };
+
+const SEQUENCE_KEYS: &[&str] = &["start", "step", "prefix", "suffix", "width"];
+const RECURSIVE_KEYS: &[&str] = &["max_depth", "leaf_probability"];
+
+/// Checks that every key of `config` names a field, variant or directive of
+/// `shape`. Nested configs are checked when their own type is sampled or
+/// compiled.
+pub fn check_keys(shape: &Shape, config: &Map<String, Value>) -> Result<(), String> {
+    match shape {
+        Shape::Struct(shape) => check_fields(&shape.fields, STRUCT_DIRECTIVES, config, shape.name),
+        Shape::Enum(shape) => check_enum(shape, config),
+        Shape::Opaque => Ok(()),
+    }
+}
+
+fn check_fields(fields: &[FieldShape], directives: &[&str], config: &Map<String, Value>, owner: &str) -> Result<(), String> {
+    for key in config.keys() {
+        if key.starts_with('$') {
+            if !directives.contains(&key.as_str()) {
+                return Err(unknown("directive", key, directives.iter().copied(), owner));
+            }
+            continue;
+        }
+        match fields.iter().find(|field| field.name == key) {
+            Some(field) if field.computed => {
+                return Err(format!("Field '{}' of '{}' is computed and takes no config", key, owner));
+            }
+            Some(_) => {}
+            None => {
+                let names = fields.iter().filter(|field| !field.computed).map(|field| field.name);
+                return Err(unknown("field", key, names, owner));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_enum(shape: &EnumShape, config: &Map<String, Value>) -> Result<(), String> {
+    if let Some(key) = config.keys().find(|key| !ENUM_KEYS.contains(&key.as_str())) {
+        return Err(unknown("key", key, ENUM_KEYS.iter().copied(), shape.name));
+    }
+    let variant_names = || shape.variants.iter().map(|variant| variant.name);
+    if let Some(Value::Array(names)) = config.get("variants") {
+        if let Some(name) = names.iter().filter_map(Value::as_str).find(|name| !variant_names().any(|known| known == *name)) {
+            return Err(unknown("variant", name, variant_names(), shape.name));
+        }
+    }
+    if let Some(Value::Object(variant_data)) = config.get("variant_data") {
+        for (name, variant_config) in variant_data {
+            let variant = shape
+                .variants
+                .iter()
+                .find(|variant| variant.name == name)
+                .ok_or_else(|| unknown("variant", name, variant_names(), shape.name))?;
+            if let (Some(fields), Value::Object(variant_config)) = (&variant.fields, variant_config) {
+                let owner = format!("{}::{}", shape.name, variant.name);
+                check_fields(fields, VARIANT_DIRECTIVES, variant_config, &owner)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the keys of `config`, the object config of `field`: they must
+/// belong to the form the config takes, or be `unique`. The objects of
+/// `sequence` and `recursive` are checked too. Configs of nested structs and
+/// enums are checked by [`check_keys`].
+pub fn check_field_keys(field: &FieldShape, config: &Map<String, Value>) -> Result<(), String> {
+    let forms: &[Form] = if config.contains_key("ref") {
+        std::slice::from_ref(&REF_FORM)
+    } else if field.recursive && config.contains_key("recursive") {
+        std::slice::from_ref(&RECURSIVE_FORM)
+    } else {
+        match field.kind {
+            FieldKind::String => STRING_FORMS,
+            FieldKind::Integer { .. } => INTEGER_FORMS,
+            FieldKind::Float { .. } => FLOAT_FORMS,
+            FieldKind::Decimal => DECIMAL_FORMS,
+            FieldKind::Bool | FieldKind::List(_) | FieldKind::Nested(_) => return Ok(()),
+        }
+    };
+    let selected = forms.iter().find_map(|form| form.keys.iter().find(|key| config.contains_key(**key)).map(|key| (form, *key)));
+    let known: Vec<&str> = match selected {
+        Some((form, _)) => form.keys.iter().chain(form.options).copied().chain(["unique"]).collect(),
+        None => forms.iter().flat_map(|form| form.keys.iter().chain(form.options)).copied().chain(["unique"]).collect(),
+    };
+    for key in config.keys() {
+        if known.contains(&key.as_str()) {
+            continue;
+        }
+        return Err(match selected {
+            Some((_, selector)) if forms.iter().any(|form| form.keys.iter().chain(form.options).any(|other| other == key)) => {
+                format!("Key '{}' of field '{}' does not go with '{}'", key, field.name, selector)
+            }
+            _ => unknown("key", key, known.iter().copied(), field.name),
+        });
+    }
+    for (key, options) in [("sequence", SEQUENCE_KEYS), ("recursive", RECURSIVE_KEYS)] {
+        if let Some(Value::Object(nested)) = config.get(key) {
+            if let Some(name) = nested.keys().find(|name| !options.contains(&name.as_str())) {
+                return Err(unknown("key", name, options.iter().copied(), &format!("{}.{}", field.name, key)));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn unknown<'a>(what: &str, key: &str, known: impl IntoIterator<Item = &'a str>, owner: &str) -> String {
+    match did_you_mean(key, known) {
+        Some(suggestion) => format!("Unknown {} '{}' in the config of '{}'; did you mean '{}'?", what, key, owner, suggestion),
+        None => format!("Unknown {} '{}' in the config of '{}'", what, key, owner),
+    }
+}
+
+/// The candidate closest to `name`, if it is close enough to be a likely
+/// misspelling of it. Ties go to the earlier candidate.
+pub fn did_you_mean<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let threshold = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Edit distance counting insertions, deletions, substitutions and swaps of
+// adjacent characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map, Value};
+
+    use super::{did_you_mean, edit_distance};
+    use crate::{SampleContext, Sampleable, Sampler};
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Voucher {
+        code: String,
+        count: u32,
+        price: f64,
+    }
+
+    fn config(changes: Value) -> Map<String, Value> {
+        let mut config = json!({ "code": { "len": 4, "charset": "hex" }, "count": [0, 10], "price": [1.0, 2.0] });
+        config.as_object_mut().unwrap().extend(changes.as_object().cloned().unwrap());
+        config.as_object().cloned().unwrap()
+    }
+
+    fn strict() -> SampleContext {
+        SampleContext::new().with_strict(true).with_seed(1)
+    }
+
+    #[test]
+    fn edit_distance_counts_adjacent_swaps_as_one_edit() {
+        assert_eq!(edit_distance("charset", "charset"), 0);
+        assert_eq!(edit_distance("", "len"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("chraset", "charset"), 1);
+        assert_eq!(edit_distance("zipcod", "zipcode"), 1);
+    }
+
+    #[test]
+    fn did_you_mean_suggests_only_close_candidates() {
+        assert_eq!(did_you_mean("zipcod", ["street", "zipcode"]), Some("zipcode"));
+        assert_eq!(did_you_mean("ZIPCOD", ["zipcode"]), Some("zipcode"));
+        assert_eq!(did_you_mean("zip", ["zipcode"]), None);
+        assert_eq!(did_you_mean("cat", ["bat", "cot"]), Some("bat"));
+        assert_eq!(did_you_mean("anything", []), None);
+    }
+
+    #[test]
+    fn strict_contexts_check_the_keys_of_field_configs() {
+        let valid = config(json!({ "count": { "sequence": { "start": 5 }, "unique": true }, "price": { "range": [1.0, 2.0], "decimals": 2 } }));
+        let voucher = Voucher::sample_with_context(&valid, &mut strict()).unwrap();
+        assert_eq!((voucher.code.len(), voucher.count), (4, 5));
+        assert!((1.0..=2.0).contains(&voucher.price));
+        // Without strict mode the misspelled option is ignored.
+        assert!(Voucher::sample_with_config(&config(json!({ "code": { "len": 4, "charsett": "hex" } }))).is_ok());
+        let cases = [
+            (json!({ "code": { "len": 4, "charsett": "hex" } }), "Unknown key 'charsett' in the config of 'code'; did you mean 'charset'?"),
+            (json!({ "code": { "template": "{count}", "regex": "[a-z]{4}" } }), "Key 'regex' of field 'code' does not go with 'template'"),
+            (json!({ "code": { "fake": "name.first", "len": 4 } }), "Key 'len' of field 'code' does not go with 'fake'"),
+            (json!({ "count": { "sequence": { "strat": 5 } } }), "Unknown key 'strat' in the config of 'count.sequence'; did you mean 'start'?"),
+            (json!({ "price": { "range": [1.0, 2.0], "decimal": 2 } }), "Unknown key 'decimal' in the config of 'price'; did you mean 'decimals'?"),
+        ];
+        for (changes, error) in cases {
+            let config = config(changes);
+            assert_eq!(Voucher::sample_with_context(&config, &mut strict()).unwrap_err(), error);
+            assert_eq!(Sampler::<Voucher>::new(&config, &strict()).unwrap_err(), error);
+        }
+    }
+}
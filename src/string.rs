@@ -0,0 +1,348 @@
+//! Sampling for `String` fields.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "faker")]
+use crate::fake::FakeSpec;
+use crate::config::Case;
+use crate::context::FieldAt;
+use crate::fields::FieldValues;
+use crate::payments::PaymentGenerator;
+use crate::sequence::SequenceSpec;
+use crate::SampleContext;
+#[cfg(feature = "regex")]
+use crate::pattern::RegexSpec;
+use crate::template::Template;
+
+/// A parsed `String` field configuration.
+#[derive(Debug, Clone)]
+pub enum StringSpec {
+    /// `"value"`: always produces the same string.
+    Literal(String),
+    /// `["a", "b"]`: picks one of the values.
+    Choices(Vec<String>),
+    /// `{"regex": "[A-Z]{2}[0-9]{4}"}`: generates a matching string.
+    #[cfg(feature = "regex")]
+    Regex(RegexSpec),
+    /// `{"len": [8, 16], "charset": "hex", "case": "upper"}`: random characters.
+    Random(RandomStringSpec),
+    /// `{"template": "{account}@{domain}"}`: interpolates generators and sibling fields.
+    Template(Template),
+    /// `{"fake": "address.city"}`: realistic data from a bundled provider.
+    #[cfg(feature = "faker")]
+    Fake(FakeSpec),
+    /// `{"generator": "iban", "country": "DE"}`: a payment identifier with valid check digits.
+    Payment(PaymentGenerator),
+    /// `{"sequence": {"prefix": "ORD-", "width": 6}}`: a formatted counter.
+    Sequence(SequenceSpec),
+}
+
+impl StringSpec {
+    /// Parses a `String` field configuration.
+    pub fn parse(value: &Value, field_name: &str) -> Result<Self, String> {
+        match value {
+            Value::String(value_str) => Ok(StringSpec::Literal(value_str.clone())),
+            Value::Array(values_array) => {
+                let values: Vec<String> = values_array.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect();
+                if values.is_empty() {
+                    return Err(format!("Values array for field '{}' is empty", field_name));
+                }
+                Ok(StringSpec::Choices(values))
+            }
+            Value::Object(map) => {
+                if let Some(sequence) = map.get("sequence") {
+                    return SequenceSpec::parse(sequence, field_name).map(StringSpec::Sequence);
+                }
+                if let Some(generator) = map.get("generator") {
+                    let name = generator
+                        .as_str()
+                        .ok_or_else(|| format!("Generator for field '{}' must be a string", field_name))?;
+                    return PaymentGenerator::parse(name, map, field_name).map(StringSpec::Payment);
+                }
+                if let Some(template) = map.get("template") {
+                    let template = template
+                        .as_str()
+                        .ok_or_else(|| format!("Template for field '{}' must be a string", field_name))?;
+                    return Self::template(template, field_name);
+                }
+                if map.contains_key("fake") {
+                    #[cfg(feature = "faker")]
+                    return FakeSpec::parse(map, field_name).map(StringSpec::Fake);
+                    #[cfg(not(feature = "faker"))]
+                    return Err(format!("Field '{}' uses 'fake', which requires the 'faker' feature", field_name));
+                }
+                if map.contains_key("regex") {
+                    #[cfg(feature = "regex")]
+                    return RegexSpec::parse(map, field_name).map(StringSpec::Regex);
+                    #[cfg(not(feature = "regex"))]
+                    return Err(format!("Field '{}' uses 'regex', which requires the 'regex' feature", field_name));
+                }
+                if map.contains_key("len") || map.contains_key("charset") {
+                    return RandomStringSpec::parse(map, field_name).map(StringSpec::Random);
+                }
+                Err(format!("Configuration for '{}' does not name a string generator", field_name))
+            }
+            _ => Err(format!("Configuration for '{}' must be an array, a string or an object", field_name)),
+        }
+    }
+
+    /// `{"template": source}`.
+    pub fn template(source: &str, field_name: &str) -> Result<Self, String> {
+        Template::parse(source)
+            .map(StringSpec::Template)
+            .map_err(|e| format!("Invalid template for field '{}': {}", field_name, e))
+    }
+
+    /// `{"fake": name, "locale": locale}`; see [`FakeSpec::new`](crate::fake::FakeSpec::new).
+    pub fn fake(name: &str, locale: Option<&str>, field_name: &str) -> Result<Self, String> {
+        #[cfg(feature = "faker")]
+        return FakeSpec::new(name, locale, field_name).map(StringSpec::Fake);
+        #[cfg(not(feature = "faker"))]
+        {
+            let _ = (name, locale);
+            Err(format!("Field '{}' uses 'fake', which requires the 'faker' feature", field_name))
+        }
+    }
+
+    /// `{"regex": pattern, "max_repeat": max_repeat, "unicode": unicode}`.
+    pub fn regex(pattern: &str, max_repeat: Option<u32>, unicode: bool, field_name: &str) -> Result<Self, String> {
+        #[cfg(feature = "regex")]
+        return RegexSpec::compile(pattern, max_repeat.unwrap_or(crate::pattern::DEFAULT_MAX_REPEAT), unicode, field_name)
+            .map(StringSpec::Regex);
+        #[cfg(not(feature = "regex"))]
+        {
+            let _ = (pattern, max_repeat, unicode);
+            Err(format!("Field '{}' uses 'regex', which requires the 'regex' feature", field_name))
+        }
+    }
+
+    /// Binds the sibling fields a template refers to to their slots among
+    /// `names`, the fields in declaration order.
+    pub fn bind(&mut self, names: &[&str]) {
+        if let StringSpec::Template(template) = self {
+            template.bind(names);
+        }
+    }
+
+    /// Samples a string according to this specification. `values` holds the
+    /// sibling fields sampled so far, for templates that reference them.
+    pub fn sample(&self, values: &FieldValues, ctx: &mut SampleContext, field: FieldAt) -> Result<String, String> {
+        match self {
+            StringSpec::Literal(value) => Ok(value.clone()),
+            StringSpec::Choices(values) => Ok(values.choose(ctx.rng()).unwrap().clone()),
+            #[cfg(feature = "regex")]
+            StringSpec::Regex(spec) => Ok(spec.sample(ctx.rng())),
+            StringSpec::Random(spec) => Ok(spec.sample(ctx.rng())),
+            StringSpec::Template(template) => template.render(values, ctx, field),
+            #[cfg(feature = "faker")]
+            StringSpec::Fake(spec) => {
+                let (rng, locale) = ctx.rng_and_locale();
+                spec.sample(rng, locale)
+            }
+            StringSpec::Payment(generator) => Ok(generator.generate(ctx.rng())),
+            StringSpec::Sequence(sequence) => Ok(sequence.next_string(ctx, field)),
+        }
+    }
+}
+
+/// Parses `value` and samples a string from it in one step.
+pub fn sample_string(value: &Value, field_name: &str, values: &FieldValues, ctx: &mut SampleContext) -> Result<String, String> {
+    let mut spec = StringSpec::parse(value, field_name)?;
+    spec.bind(values.names());
+    spec.sample(values, ctx, FieldAt::Config(field_name))
+}
+
+/// Random strings built from a character set.
+#[derive(Debug, Clone)]
+pub struct RandomStringSpec {
+    min_len: usize,
+    max_len: usize,
+    charset: Charset,
+    case: Option<Case>,
+}
+
+#[derive(Debug, Clone)]
+enum Charset {
+    Chars(Vec<char>),
+    Unicode,
+}
+
+// Printable blocks used by the `unicode` charset: Latin-1 letters, Latin
+// Extended-A, Greek, Cyrillic, Arabic, Devanagari, Hiragana, Katakana and
+// common CJK ideographs. Greek skips the unassigned U+03A2.
+const UNICODE_RANGES: &[(char, char)] = &[
+    ('\u{00C0}', '\u{00FF}'),
+    ('\u{0100}', '\u{017F}'),
+    ('\u{0391}', '\u{03A1}'),
+    ('\u{03A3}', '\u{03C9}'),
+    ('\u{0410}', '\u{044F}'),
+    ('\u{0627}', '\u{064A}'),
+    ('\u{0905}', '\u{0939}'),
+    ('\u{3041}', '\u{3096}'),
+    ('\u{30A1}', '\u{30FA}'),
+    ('\u{4E00}', '\u{9FFF}'),
+];
+
+impl RandomStringSpec {
+    /// Parses `{"len": [min, max], "charset": ..., "case": ...}`.
+    ///
+    /// `len` is a fixed length or an inclusive `[min, max]` range (default
+    /// `[8, 16]`). `charset` is `alphanumeric` (default), `alphabetic`,
+    /// `numeric`, `hex`, `ascii_printable`, `unicode`, or any other string,
+    /// whose characters are used as the set. `case` is `upper` or `lower`.
+    pub fn parse(map: &Map<String, Value>, field_name: &str) -> Result<Self, String> {
+        let (min_len, max_len) = match map.get("len") {
+            None => (8, 16),
+            Some(Value::Array(range_array)) => {
+                if range_array.len() != 2 {
+                    return Err(format!("Length range for field '{}' must have exactly two elements", field_name));
+                }
+                match (range_array[0].as_u64(), range_array[1].as_u64()) {
+                    (Some(min), Some(max)) => (min, max),
+                    _ => return Err(format!("Invalid length range for field '{}'", field_name)),
+                }
+            }
+            Some(len) => match len.as_u64() {
+                Some(len) => (len, len),
+                None => return Err(format!("Length for field '{}' must be an integer or a range", field_name)),
+            },
+        };
+        let case = match map.get("case").map(|c| c.as_str()) {
+            None => None,
+            Some(Some("upper")) => Some(Case::Upper),
+            Some(Some("lower")) => Some(Case::Lower),
+            _ => return Err(format!("Case for field '{}' must be 'upper' or 'lower'", field_name)),
+        };
+        let charset = match map.get("charset") {
+            None => None,
+            Some(Value::String(charset)) => Some(charset.as_str()),
+            Some(_) => return Err(format!("Charset for field '{}' must be a non-empty string", field_name)),
+        };
+        Self::new(min_len, max_len, charset, case, field_name)
+    }
+
+    /// Strings of `min_len..=max_len` characters drawn from `charset` (a
+    /// named set or the characters to use; alphanumeric when `None`), in
+    /// `case` if given.
+    pub fn new(min_len: u64, max_len: u64, charset: Option<&str>, case: Option<Case>, field_name: &str) -> Result<Self, String> {
+        if min_len > max_len {
+            return Err(format!("Invalid length range for field '{}'", field_name));
+        }
+        let charset = match charset {
+            None | Some("alphanumeric") => Charset::Chars(('A'..='Z').chain('a'..='z').chain('0'..='9').collect()),
+            Some("alphabetic") => Charset::Chars(('A'..='Z').chain('a'..='z').collect()),
+            Some("numeric") => Charset::Chars(('0'..='9').collect()),
+            Some("hex") => Charset::Chars(('0'..='9').chain('a'..='f').collect()),
+            Some("ascii_printable") => Charset::Chars((' '..='~').collect()),
+            Some("unicode") => Charset::Unicode,
+            Some(custom) if !custom.is_empty() => Charset::Chars(custom.chars().collect()),
+            _ => return Err(format!("Charset for field '{}' must be a non-empty string", field_name)),
+        };
+        // Apply the case to the set itself so that, for example, uppercase
+        // alphanumerics are not biased towards letters that appear twice.
+        // Characters whose case takes several characters (`ß` is `SS` in
+        // upper case) are left out, so every character counts towards `len`.
+        let charset = match (charset, case) {
+            (Charset::Chars(chars), Some(case)) => {
+                let mut chars: Vec<char> = chars.into_iter().filter_map(|c| apply_case(c, case)).collect();
+                if chars.is_empty() {
+                    return Err(format!("Charset for field '{}' has no characters in {} case", field_name, case));
+                }
+                chars.sort_unstable();
+                chars.dedup();
+                Charset::Chars(chars)
+            }
+            (charset, _) => charset,
+        };
+        Ok(RandomStringSpec { min_len: min_len as usize, max_len: max_len as usize, charset, case })
+    }
+
+    /// Generates a random string.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        let len = rng.gen_range(self.min_len..=self.max_len);
+        match &self.charset {
+            Charset::Chars(chars) => (0..len).map(|_| *chars.choose(rng).unwrap()).collect(),
+            Charset::Unicode => (0..len)
+                .map(|_| loop {
+                    let (start, end) = *UNICODE_RANGES.choose(rng).unwrap();
+                    let c = char::from_u32(rng.gen_range(start as u32..=end as u32)).unwrap_or(start);
+                    match self.case {
+                        Some(case) => {
+                            if let Some(c) = apply_case(c, case) {
+                                break c;
+                            }
+                        }
+                        None => break c,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Case {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Case::Upper => "upper",
+            Case::Lower => "lower",
+        })
+    }
+}
+
+// `c` in `case`, unless that takes more than one character.
+fn apply_case(c: char, case: Case) -> Option<char> {
+    fn single(mut chars: impl Iterator<Item = char>) -> Option<char> {
+        let first = chars.next();
+        chars.next().map_or(first, |_| None)
+    }
+    match case {
+        Case::Upper => single(c.to_uppercase()),
+        Case::Lower => single(c.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    use super::*;
+
+    fn random(config: Value) -> Result<RandomStringSpec, String> {
+        RandomStringSpec::parse(config.as_object().unwrap(), "code")
+    }
+
+    #[test]
+    fn case_keeps_the_length() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for case in ["upper", "lower"] {
+            let spec = random(json!({ "len": 12, "charset": "unicode", "case": case })).unwrap();
+            for _ in 0..500 {
+                assert_eq!(spec.sample(&mut rng).chars().count(), 12);
+            }
+        }
+        let spec = random(json!({ "len": 6, "charset": "ßa", "case": "upper" })).unwrap();
+        assert_eq!(spec.sample(&mut rng), "AAAAAA");
+    }
+
+    #[test]
+    fn case_must_leave_a_character() {
+        assert_eq!(
+            random(json!({ "charset": "ß", "case": "upper" })).unwrap_err(),
+            "Charset for field 'code' has no characters in upper case"
+        );
+    }
+
+    #[test]
+    fn unicode_ranges_hold_assigned_characters() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let spec = random(json!({ "len": 1000, "charset": "unicode" })).unwrap();
+        assert!(!spec.sample(&mut rng).contains('\u{03A2}'));
+        assert!(UNICODE_RANGES.iter().all(|(start, end)| !(*start..=*end).contains(&'\u{03A2}')));
+    }
+}
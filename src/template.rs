@@ -0,0 +1,536 @@
+//! String templates such as `"INV-{year}-{int:1..=999999:06}"`.
+//!
+//! A placeholder is `{name}` or `{name:arg}`, optionally followed by a width
+//! (`:06` pads with zeros, `:6` with spaces). `name` is either a sibling field
+//! of the struct being sampled or one of the built-in generators:
+//!
+//! - `uuid`: a random version 4 UUID
+//! - `int:LOW..HIGH` / `int:LOW..=HIGH`: a random integer
+//! - `hex:N`, `digits:N`, `alpha:N`: `N` random characters
+//! - `seq`: the next number of a counter kept per field, starting at 1 (see
+//!   [`sequence`](crate::sequence))
+//! - `year`, `date`, `datetime`: a random year, `YYYY-MM-DD` date or
+//!   `YYYY-MM-DDThh:mm:ssZ` UTC timestamp, drawn like every other value so
+//!   a seed gives the same dates on any day. They fall between 2020 and 2029
+//!   ([`DEFAULT_DATES`]) unless a range is given, such as
+//!   `{date:2024-01-01..=2024-12-31}`,
+//!   `{datetime:2024-01-01..2024-01-01T12:00:00Z}` or `{year:1990..2000}`
+//!
+//! Sibling fields take precedence over generators with the same name. A
+//! sibling without a value, such as an `Option` field that sampled `None`,
+//! fails the sample unless the placeholder gives a default after `:-`:
+//! `{middle_name:-}` renders nothing and `{nickname:-n/a}` renders `n/a`.
+//! Everything after `:-` is the default, so such a placeholder takes no
+//! width. Use `{{` and `}}` for literal braces.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::context::FieldAt;
+use crate::fields::{slot_of, FieldValues, Scalar};
+use crate::SampleContext;
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    /// `slot` is the slot of the sibling field named `name`, once bound.
+    Placeholder { name: String, args: Vec<String>, slot: Option<usize> },
+}
+
+impl Template {
+    /// Parses a template string.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut inner = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => inner.push(c),
+                            None => return Err(format!("Unclosed placeholder in template '{}'", template)),
+                        }
+                    }
+                    let mut segments = inner.split(':').map(|s| s.trim().to_string());
+                    let name = segments.next().unwrap_or_default();
+                    if name.is_empty() {
+                        return Err(format!("Empty placeholder in template '{}'", template));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Placeholder { name, args: segments.collect(), slot: None });
+                }
+                '}' => return Err(format!("Unmatched '}}' in template '{}'", template)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Names used in placeholders, in order of appearance.
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder { name, .. } => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Binds the placeholders naming one of `names`, the sibling fields in
+    /// declaration order, to the slots they are read from.
+    pub fn bind(&mut self, names: &[&str]) {
+        for part in &mut self.parts {
+            if let Part::Placeholder { name, slot, .. } = part {
+                *slot = slot_of(names, name);
+            }
+        }
+    }
+
+    /// The slots of the bound placeholders.
+    pub fn slots(&self) -> impl Iterator<Item = usize> + '_ {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder { slot, .. } => *slot,
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Whether rendering draws a number from the sequence of the field, once
+    /// per rendering however often `{seq}` appears.
+    pub fn draws_sequence(&self) -> bool {
+        self.parts.iter().any(|part| matches!(part, Part::Placeholder { name, slot: None, .. } if name == "seq"))
+    }
+
+    /// Renders the template, reading the bound sibling fields from `values`
+    /// and drawing generated parts from `ctx`.
+    pub fn render(&self, values: &FieldValues, ctx: &mut SampleContext, field: FieldAt) -> Result<String, String> {
+        let field_name = field.name();
+        let mut output = String::new();
+        // Every `{seq}` of one rendering shows the same number.
+        let mut seq = None;
+        for part in &self.parts {
+            let (name, args, slot) = match part {
+                Part::Literal(text) => {
+                    output.push_str(text);
+                    continue;
+                }
+                Part::Placeholder { name, args, slot } => (name.as_str(), args.as_slice(), *slot),
+            };
+            // `{field:-default}` for a sibling field.
+            let default = slot.and(args.first()).and_then(|first| first.strip_prefix('-')).map(|_| args.join(":")[1..].to_string());
+            let (rendered, width) = match (slot.and_then(|slot| values.get(slot)), default) {
+                (Some(Scalar::Null), Some(default)) => (default, None),
+                (Some(Scalar::Null), None) => {
+                    return Err(format!(
+                        "Template for field '{}' reads '{}', which has no value; give a default such as {{{}:-}}",
+                        field_name, name, name
+                    ))
+                }
+                (Some(value), None) => (value.to_string(), args.first()),
+                (Some(value), Some(_)) => (value.to_string(), None),
+                (None, _) => match name {
+                    "uuid" => (uuid_v4(ctx.rng()), args.first()),
+                    "seq" => {
+                        let number = *seq.get_or_insert_with(|| ctx.next_in_sequence(field, 1, 1));
+                        (number.to_string(), args.first())
+                    }
+                    "year" | "date" | "datetime" => {
+                        // Timestamps in the range contain colons themselves.
+                        let (range, width) = match args.split_last() {
+                            None => (None, None),
+                            Some((width, [])) if !width.contains("..") => (None, Some(width)),
+                            Some((width, range)) if !range.is_empty() && width.chars().all(|c| c.is_ascii_digit()) => {
+                                (Some(range.join(":")), Some(width))
+                            }
+                            Some(_) => (Some(args.join(":")), None),
+                        };
+                        (random_date(ctx.rng(), name, range.as_deref(), field_name)?, width)
+                    }
+                    "int" => (random_int(ctx.rng(), args.first(), field_name)?, args.get(1)),
+                    "hex" | "digits" | "alpha" => {
+                        let count = args
+                            .first()
+                            .and_then(|n| n.parse::<usize>().ok())
+                            .ok_or_else(|| format!("Placeholder '{{{}}}' in field '{}' needs a length, e.g. {{{}:8}}", name, field_name, name))?;
+                        let chars: Vec<char> = match name {
+                            "hex" => ('0'..='9').chain('a'..='f').collect(),
+                            "digits" => ('0'..='9').collect(),
+                            _ => ('A'..='Z').chain('a'..='z').collect(),
+                        };
+                        ((0..count).map(|_| *chars.choose(ctx.rng()).unwrap()).collect(), args.get(1))
+                    }
+                    _ => return Err(format!("Template for field '{}' references unknown placeholder '{}'", field_name, name)),
+                },
+            };
+            output.push_str(&pad(rendered, width.map(String::as_str), field_name)?);
+        }
+        Ok(output)
+    }
+}
+
+fn random_int<R: Rng + ?Sized>(rng: &mut R, range: Option<&String>, field_name: &str) -> Result<String, String> {
+    let invalid = || format!("Placeholder 'int' in field '{}' needs a range such as {{int:100..=999}}", field_name);
+    let range = range.ok_or_else(invalid)?;
+    let (low, high, inclusive) = split_range(range).ok_or_else(invalid)?;
+    let low: i64 = low.parse().map_err(|_| invalid())?;
+    let high: i64 = high.parse().map_err(|_| invalid())?;
+    Ok(draw(rng, low, high, inclusive, range, field_name)?.to_string())
+}
+
+// A random year, date or timestamp, depending on `name`.
+fn random_date<R: Rng + ?Sized>(rng: &mut R, name: &str, range: Option<&str>, field_name: &str) -> Result<String, String> {
+    // Bounds are in years, days or seconds since 1970, depending on `name`.
+    let (low, high, inclusive) = match range {
+        Some(range) => {
+            let invalid = || {
+                format!(
+                    "Range '{}' of placeholder '{}' in field '{}' must be LOW..HIGH or LOW..=HIGH, e.g. {{date:2024-01-01..=2024-12-31}}",
+                    range, name, field_name
+                )
+            };
+            let bound = |bound: &str| match name {
+                "year" => bound.parse().ok(),
+                "date" => parse_timestamp(bound).filter(|secs| secs % 86_400 == 0).map(|secs| secs / 86_400),
+                _ => parse_timestamp(bound),
+            };
+            let (low, high, inclusive) = split_range(range).ok_or_else(invalid)?;
+            (bound(low).ok_or_else(invalid)?, bound(high).ok_or_else(invalid)?, inclusive)
+        }
+        None => {
+            let (low, high) = DEFAULT_DATES;
+            match name {
+                "year" => (civil_from_days(low / 86_400).0, civil_from_days(high / 86_400).0, false),
+                "date" => (low / 86_400, high / 86_400, false),
+                _ => (low, high, false),
+            }
+        }
+    };
+    let value = draw(rng, low, high, inclusive, range.unwrap_or_default(), field_name)?;
+    Ok(match name {
+        "year" => value.to_string(),
+        "date" => {
+            let (year, month, day) = civil_from_days(value);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }
+        _ => {
+            let (year, month, day) = civil_from_days(value.div_euclid(86_400));
+            let secs = value.rem_euclid(86_400);
+            format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, secs / 3_600, secs / 60 % 60, secs % 60)
+        }
+    })
+}
+
+// Splits `LOW..HIGH` or `LOW..=HIGH` into its bounds and whether the range
+// is inclusive.
+fn split_range(range: &str) -> Option<(&str, &str, bool)> {
+    match range.split_once("..=") {
+        Some((low, high)) => Some((low.trim(), high.trim(), true)),
+        None => range.split_once("..").map(|(low, high)| (low.trim(), high.trim(), false)),
+    }
+}
+
+fn draw<R: Rng + ?Sized>(rng: &mut R, low: i64, high: i64, inclusive: bool, range: &str, field_name: &str) -> Result<i64, String> {
+    if inclusive && low <= high {
+        Ok(rng.gen_range(low..=high))
+    } else if !inclusive && low < high {
+        Ok(rng.gen_range(low..high))
+    } else {
+        Err(format!("Range '{}' in field '{}' is empty", range, field_name))
+    }
+}
+
+fn pad(value: String, width: Option<&str>, field_name: &str) -> Result<String, String> {
+    let width_str = match width {
+        Some(width_str) => width_str,
+        None => return Ok(value),
+    };
+    let width: usize = width_str
+        .parse()
+        .map_err(|_| format!("Invalid width '{}' in template for field '{}'", width_str, field_name))?;
+    let missing = width.saturating_sub(value.chars().count());
+    if !width_str.starts_with('0') {
+        return Ok(std::iter::repeat_n(' ', missing).chain(value.chars()).collect());
+    }
+    // Zeros go after the sign, so -5 padded to 4 is -005.
+    let (sign, digits) = match value.strip_prefix(['-', '+']) {
+        Some(digits) => value.split_at(value.len() - digits.len()),
+        None => ("", value.as_str()),
+    };
+    Ok(sign.chars().chain(std::iter::repeat_n('0', missing)).chain(digits.chars()).collect())
+}
+
+fn uuid_v4<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut bytes: [u8; 16] = rng.gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Seconds since 1970 of the first and the end of the dates drawn when
+/// `year`, `date` and `datetime` are given no range: 2020-01-01 up to, but
+/// not including, 2030-01-01.
+pub const DEFAULT_DATES: (i64, i64) = (1_577_836_800, 1_893_456_000);
+
+// Seconds since 1970 of `YYYY-MM-DD` or `YYYY-MM-DDThh:mm:ssZ`.
+fn parse_timestamp(text: &str) -> Option<i64> {
+    let (date, time) = match text.split_once('T') {
+        Some((date, time)) => (date, Some(time.strip_suffix('Z')?)),
+        None => (text, None),
+    };
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    let secs = match time {
+        None => 0,
+        Some(time) => {
+            let parts: Vec<i64> = time.split(':').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+            match parts[..] {
+                [h, m, s] if (0..24).contains(&h) && (0..60).contains(&m) && (0..60).contains(&s) => h * 3_600 + m * 60 + s,
+                _ => return None,
+            }
+        }
+    };
+    Some(days_from_civil(year, month, day) * 86_400 + secs)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a date, and back, using the algorithms from
+// Howard Hinnant's date library.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::FieldValue;
+
+    fn render(template: &str, values: &FieldValues, ctx: &mut SampleContext) -> Result<String, String> {
+        let mut template = Template::parse(template)?;
+        template.bind(values.names());
+        template.render(values, ctx, FieldAt::Config("reference"))
+    }
+
+    #[test]
+    fn parse_splits_literals_and_placeholders() {
+        let template = Template::parse("INV-{{{year}}}-{int:1..=9:06}").unwrap();
+        assert_eq!(
+            template.parts,
+            [
+                Part::Literal("INV-{".to_string()),
+                Part::Placeholder { name: "year".to_string(), args: Vec::new(), slot: None },
+                Part::Literal("}-".to_string()),
+                Part::Placeholder { name: "int".to_string(), args: vec!["1..=9".to_string(), "06".to_string()], slot: None },
+            ]
+        );
+        assert_eq!(template.placeholders().collect::<Vec<_>>(), ["year", "int"]);
+    }
+
+    #[test]
+    fn parse_rejects_unbalanced_braces() {
+        assert_eq!(Template::parse("a{b").unwrap_err(), "Unclosed placeholder in template 'a{b'");
+        assert_eq!(Template::parse("a}b").unwrap_err(), "Unmatched '}' in template 'a}b'");
+        assert_eq!(Template::parse("a{ }b").unwrap_err(), "Empty placeholder in template 'a{ }b'");
+    }
+
+    #[test]
+    fn render_reads_sibling_fields_before_generators() {
+        let mut slots = [None, None, None];
+        let mut values = FieldValues::new(&["account", "uuid", "middle"], &mut slots);
+        values.set(0, "ann".to_string().to_scalar());
+        values.set(1, 7i64.to_scalar());
+        values.set(2, None::<String>.to_scalar());
+        let mut ctx = SampleContext::new().with_seed(1);
+        assert_eq!(render("{account}@{uuid:03}{middle:-}.com", &values, &mut ctx).unwrap(), "ann@007.com");
+        assert_eq!(
+            render("{acount}", &values, &mut ctx).unwrap_err(),
+            "Template for field 'reference' references unknown placeholder 'acount'"
+        );
+    }
+
+    #[test]
+    fn siblings_without_a_value_need_a_default() {
+        let mut slots = [None, None];
+        let mut values = FieldValues::new(&["middle", "age"], &mut slots);
+        values.set(0, None::<String>.to_scalar());
+        values.set(1, Some(42i64).to_scalar());
+        let mut ctx = SampleContext::new();
+        assert_eq!(
+            render("{middle} Smith", &values, &mut ctx).unwrap_err(),
+            "Template for field 'reference' reads 'middle', which has no value; give a default such as {middle:-}"
+        );
+        assert_eq!(render("{middle:-}Smith", &values, &mut ctx).unwrap(), "Smith");
+        assert_eq!(render("{middle:-n/a} Smith", &values, &mut ctx).unwrap(), "n/a Smith");
+        assert_eq!(render("{middle:-at 12:30}", &values, &mut ctx).unwrap(), "at 12:30");
+        // A default is ignored when there is a value.
+        assert_eq!(render("{age:-unknown}|{age:05}", &values, &mut ctx).unwrap(), "42|00042");
+        // Generators take ranges starting with '-' as before.
+        assert!(render("{int:-5..=-1}", &values, &mut ctx).unwrap().starts_with('-'));
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Person {
+        first: String,
+        middle_name: Option<String>,
+        last: String,
+        full_name: String,
+    }
+
+    #[test]
+    fn derived_templates_fail_on_missing_siblings_without_a_default() {
+        use crate::Sampleable;
+
+        let config = |template: &str| {
+            serde_json::json!({ "first": ["Ann"], "last": ["Smith"], "full_name": { "template": template } })
+                .as_object()
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            Person::sample_with_config(&config("{first} {middle_name} {last}")).unwrap_err(),
+            "Template for field 'full_name' reads 'middle_name', which has no value; give a default such as {middle_name:-}"
+        );
+        let person = Person::sample_with_config(&config("{first} {middle_name:-X.} {last}")).unwrap();
+        assert_eq!((person.first.as_str(), person.middle_name, person.last.as_str()), ("Ann", None, "Smith"));
+        assert_eq!(person.full_name, "Ann X. Smith");
+    }
+
+    #[test]
+    fn render_draws_generators_from_the_context() {
+        let values = FieldValues::new(&[], &mut []);
+        let template = "{uuid}|{int:-9..=9}|{hex:4}|{digits:3}|{alpha:2}";
+        let first = render(template, &values, &mut SampleContext::new().with_seed(3)).unwrap();
+        assert_eq!(first, render(template, &values, &mut SampleContext::new().with_seed(3)).unwrap());
+        let parts: Vec<&str> = first.split('|').collect();
+        assert_eq!(parts[0].len(), 36);
+        assert!((-9..=9).contains(&parts[1].parse::<i64>().unwrap()));
+        assert!(parts[2].len() == 4 && parts[2].chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(parts[3].len() == 3 && parts[3].chars().all(|c| c.is_ascii_digit()));
+        assert!(parts[4].len() == 2 && parts[4].chars().all(|c| c.is_ascii_alphabetic()));
+        assert_eq!(
+            render("{int:5..5}", &values, &mut SampleContext::new()).unwrap_err(),
+            "Range '5..5' in field 'reference' is empty"
+        );
+    }
+
+    #[test]
+    fn seq_counts_per_field() {
+        let values = FieldValues::new(&[], &mut []);
+        let template = Template::parse("ORD-{seq:06}/{seq}").unwrap();
+        let mut ctx = SampleContext::new();
+        assert_eq!(template.render(&values, &mut ctx, FieldAt::Config("id")).unwrap(), "ORD-000001/1");
+        assert_eq!(template.render(&values, &mut ctx, FieldAt::Config("id")).unwrap(), "ORD-000002/2");
+        assert_eq!(template.render(&values, &mut ctx, FieldAt::Config("other")).unwrap(), "ORD-000001/1");
+    }
+
+    #[test]
+    fn dates_are_drawn_from_the_context() {
+        let values = FieldValues::new(&[], &mut []);
+        let template = "{year}|{date}|{datetime}";
+        let first = render(template, &values, &mut SampleContext::new().with_seed(5)).unwrap();
+        assert_eq!(first, render(template, &values, &mut SampleContext::new().with_seed(5)).unwrap());
+        let parts: Vec<&str> = first.split('|').collect();
+        assert!(("2020".."2030").contains(&parts[0]));
+        assert!(("2020-01-01".."2030-01-01").contains(&parts[1]));
+        assert!(("2020-01-01T00:00:00Z".."2030-01-01T00:00:00Z").contains(&parts[2]));
+        assert!(parse_timestamp(parts[1]).is_some() && parse_timestamp(parts[2]).is_some());
+    }
+
+    #[test]
+    fn dates_stay_in_the_given_range() {
+        let values = FieldValues::new(&[], &mut []);
+        let mut ctx = SampleContext::new().with_seed(9);
+        for _ in 0..200 {
+            let date = render("{date:2024-02-28..=2024-03-01}", &values, &mut ctx).unwrap();
+            assert!(["2024-02-28", "2024-02-29", "2024-03-01"].contains(&date.as_str()), "{}", date);
+            let time = render("{datetime:2024-12-31T23:59:58Z..2025-01-01}", &values, &mut ctx).unwrap();
+            assert!(["2024-12-31T23:59:58Z", "2024-12-31T23:59:59Z"].contains(&time.as_str()), "{}", time);
+            let year = render("{year:1999..=2000:6}", &values, &mut ctx).unwrap();
+            assert!(["  1999", "  2000"].contains(&year.as_str()), "{}", year);
+        }
+        assert_eq!(render("{date:10}", &values, &mut ctx).unwrap().len(), 10);
+        assert_eq!(
+            render("{date:2023-02-29..2024-01-01}", &values, &mut ctx).unwrap_err(),
+            "Range '2023-02-29..2024-01-01' of placeholder 'date' in field 'reference' must be LOW..HIGH or LOW..=HIGH, \
+             e.g. {date:2024-01-01..=2024-12-31}"
+        );
+        assert_eq!(
+            render("{year:2000..2000}", &values, &mut ctx).unwrap_err(),
+            "Range '2000..2000' in field 'reference' is empty"
+        );
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(parse_timestamp("2020-01-01"), Some(DEFAULT_DATES.0));
+        assert_eq!(parse_timestamp("2030-01-01"), Some(DEFAULT_DATES.1));
+        for days in [-719_468, -1, 0, 59, 11_016, 19_782, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(parse_timestamp("2024-13-01"), None);
+        assert_eq!(parse_timestamp("2024-01-01T24:00:00Z"), None);
+        assert_eq!(parse_timestamp("2024-01-01T12:00:00"), None);
+    }
+
+    #[test]
+    fn pad_puts_zeros_after_the_sign() {
+        assert_eq!(pad("-5".to_string(), Some("04"), "n").unwrap(), "-005");
+        assert_eq!(pad("+5".to_string(), Some("03"), "n").unwrap(), "+05");
+        assert_eq!(pad("-5".to_string(), Some("4"), "n").unwrap(), "  -5");
+        assert_eq!(pad("12345".to_string(), Some("03"), "n").unwrap(), "12345");
+        assert_eq!(pad("ab".to_string(), Some("x"), "n").unwrap_err(), "Invalid width 'x' in template for field 'n'");
+    }
+}
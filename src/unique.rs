@@ -0,0 +1,194 @@
+//! Uniqueness of field values across the samples drawn with one context.
+//!
+//! A field is unique when its config object has `"unique": true` or its name
+//! is listed in the struct's `"$unique"` array, which also works for configs
+//! that are not objects:
+//!
+//! ```json
+//! {
+//!     "id": [1, 1000000],
+//!     "email": { "fake": "internet.email", "unique": true },
+//!     "$unique": ["id"]
+//! }
+//! ```
+//!
+//! Values are tracked per field path (`email`, `address.zip`, `items.id`) in
+//! the [`SampleContext`](crate::SampleContext), so they stay unique across
+//! [`Sampleable::sample_many`](crate::Sampleable::sample_many) and across the
+//! items of a `Vec`. A colliding value is resampled up to [`MAX_ATTEMPTS`]
+//! times before sampling fails. `None` values are not tracked, and values
+//! drawn by a sample that a [constraint](crate::constraint) rejects are
+//! released again.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::fields::Scalar;
+
+/// Attempts at a fresh value before a unique field is considered exhausted.
+pub const MAX_ATTEMPTS: u32 = 1000;
+
+/// Whether `field_name` must be unique according to `config`.
+pub fn is_unique(config: &Map<String, Value>, field_name: &str) -> Result<bool, String> {
+    let in_directive = match config.get("$unique") {
+        None => false,
+        Some(Value::Array(names)) => names.iter().any(|name| name.as_str() == Some(field_name)),
+        Some(_) => return Err("'$unique' must be an array of field names".to_string()),
+    };
+    let in_field = match config.get(field_name).and_then(|value| value.get("unique")) {
+        None => false,
+        Some(Value::Bool(unique)) => *unique,
+        Some(_) => return Err(format!("'unique' for field '{}' must be a boolean", field_name)),
+    };
+    Ok(in_directive || in_field)
+}
+
+/// Values emitted so far, per field path.
+#[derive(Debug, Clone, Default)]
+pub struct UniqueValues {
+    seen: HashMap<String, HashSet<String>>,
+}
+
+impl UniqueValues {
+    /// Records `value` for `path`, returning `false` if it was seen before.
+    pub fn claim(&mut self, path: &str, value: &Scalar) -> bool {
+        if *value == Scalar::Null {
+            return true;
+        }
+        if !self.seen.contains_key(path) {
+            self.seen.insert(path.to_string(), HashSet::new());
+        }
+        let seen = self.seen.get_mut(path).expect("the path was just inserted");
+        match value {
+            Scalar::String(s) => !seen.contains(s) && seen.insert(s.clone()),
+            value => seen.insert(value.to_string()),
+        }
+    }
+
+    /// Forgets `value` for `path`, so it can be claimed again.
+    pub fn release(&mut self, path: &str, value: &Scalar) {
+        if let Some(seen) = self.seen.get_mut(path) {
+            match value {
+                Scalar::String(s) => seen.remove(s),
+                value => seen.remove(&value.to_string()),
+            };
+        }
+    }
+
+    /// Adds the values of `other`, unless one of them was seen before; then
+    /// nothing is added and `false` is returned.
+    pub fn merge(&mut self, other: &UniqueValues) -> bool {
+        let collides = other.seen.iter().any(|(path, values)| {
+            self.seen.get(path).is_some_and(|seen| values.iter().any(|value| seen.contains(value)))
+        });
+        if collides {
+            return false;
+        }
+        for (path, values) in &other.seen {
+            self.seen.entry(path.clone()).or_default().extend(values.iter().cloned());
+        }
+        true
+    }
+
+    /// Number of distinct values recorded for `path`.
+    pub fn count(&self, path: &str) -> usize {
+        self.seen.get(path).map_or(0, HashSet::len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{SampleContext, Sampleable};
+
+    use super::*;
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Item {
+        id: u32,
+    }
+
+    #[derive(Debug, crate::Sampleable)]
+    struct Account {
+        id: u32,
+        code: String,
+        nickname: Option<String>,
+        items: Vec<Item>,
+    }
+
+    fn config(changes: Value) -> Map<String, Value> {
+        let mut config = json!({
+            "id": [1, 51],
+            "code": { "len": 3, "charset": "numeric", "unique": true },
+            "items": [{ "id": [1, 200], "$unique": ["id"] }],
+            "$unique": ["id"]
+        });
+        for (key, value) in changes.as_object().unwrap() {
+            config[key] = value.clone();
+        }
+        config.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn values_are_unique_per_path_across_a_batch() {
+        let mut ctx = SampleContext::new().with_seed(5);
+        let accounts = Account::sample_many_with_context(&config(json!({})), 50, &mut ctx).unwrap();
+        let mut ids: Vec<u32> = accounts.iter().map(|account| account.id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (1..=50).collect::<Vec<u32>>());
+        let codes: HashSet<&str> = accounts.iter().map(|account| account.code.as_str()).collect();
+        assert_eq!(codes.len(), 50);
+        // Item ids share the path `items.id`, apart from the account ids.
+        let item_ids: Vec<u32> = accounts.iter().map(|account| account.items[0].id).collect();
+        assert_eq!(item_ids.iter().collect::<HashSet<_>>().len(), 50);
+        assert_eq!(ctx.unique_values().count("id"), 50);
+        assert_eq!(ctx.unique_values().count("items.id"), 50);
+        assert!(accounts.iter().all(|account| account.nickname.is_none()));
+    }
+
+    #[test]
+    fn exhausted_value_spaces_fail() {
+        let error = Account::sample_many(&config(json!({})), 51).unwrap_err();
+        assert_eq!(error, "Could not find a unique value for 'id' after 1000 attempts; its value space may be exhausted");
+        let error = Account::sample_many(&config(json!({ "items": [{ "id": [1, 4], "$unique": ["id"] }] })), 4).unwrap_err();
+        assert_eq!(error, "Could not find a unique value for 'items.id' after 1000 attempts; its value space may be exhausted");
+    }
+
+    #[test]
+    fn only_primitive_fields_can_be_unique() {
+        let error = Account::sample_with_config(&config(json!({ "$unique": ["items"] }))).unwrap_err();
+        assert_eq!(error, "Field 'items' cannot be unique; only primitive and decimal fields can");
+        let error = Account::sample_with_config(&config(json!({ "$unique": "id" }))).unwrap_err();
+        assert_eq!(error, "'$unique' must be an array of field names");
+    }
+
+    #[test]
+    fn claimed_values_can_be_released_and_merged() {
+        let mut seen = UniqueValues::default();
+        assert!(seen.claim("id", &Scalar::Integer(1)));
+        assert!(!seen.claim("id", &Scalar::Integer(1)));
+        assert!(seen.claim("other", &Scalar::Integer(1)));
+        assert!(seen.claim("id", &Scalar::Null) && seen.claim("id", &Scalar::Null));
+        seen.release("id", &Scalar::Integer(1));
+        assert!(seen.claim("id", &Scalar::Integer(1)));
+
+        let mut other = UniqueValues::default();
+        other.claim("id", &Scalar::Integer(2));
+        assert!(seen.merge(&other));
+        assert_eq!(seen.count("id"), 2);
+        assert!(!seen.merge(&other));
+        assert_eq!(seen.count("id"), 2);
+    }
+
+    #[test]
+    fn fields_are_unique_by_option_or_directive() {
+        let config = json!({ "a": { "unique": true }, "b": [1, 2], "c": { "unique": "yes" }, "$unique": ["b"] });
+        let config = config.as_object().unwrap();
+        assert_eq!(is_unique(config, "a"), Ok(true));
+        assert_eq!(is_unique(config, "b"), Ok(true));
+        assert_eq!(is_unique(config, "d"), Ok(false));
+        assert_eq!(is_unique(config, "c").unwrap_err(), "'unique' for field 'c' must be a boolean");
+    }
+}